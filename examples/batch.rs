@@ -0,0 +1,96 @@
+//! Plays runs with a bot as fast as possible and prints how far they got
+//! as csv, one row per simulated second:
+//!
+//!     cargo run --release --example batch -- --runs 10 easy.json hard.json
+//!
+//! Every json is a full balance file, the embedded one is used when none
+//! is given.
+
+use std::time::Duration;
+
+use planet_td::balance::Balance;
+use planet_td::headless::{
+    add_bot, disable_records, headless_app, snapshot, start_run, HEADLESS_FRAME,
+};
+
+/// Runs still going after this long are cut.
+const MAX_RUN: Duration = Duration::from_secs(30 * 60);
+const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+
+struct Options {
+    runs: usize,
+    level: usize,
+    configs: Vec<String>,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        runs: 1,
+        level: 0,
+        configs: vec![],
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => options.runs = args.next().and_then(|v| v.parse().ok()).expect("--runs N"),
+            "--level" => {
+                options.level = args.next().and_then(|v| v.parse().ok()).expect("--level N")
+            }
+            _ => options.configs.push(arg),
+        }
+    }
+    options
+}
+
+fn load_balance(path: &str) -> Balance {
+    let json = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+    serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", path, err))
+}
+
+fn main() {
+    let options = parse_options();
+    let configs: Vec<(String, Balance)> = if options.configs.is_empty() {
+        vec![("default".to_string(), Balance::default())]
+    } else {
+        options
+            .configs
+            .iter()
+            .map(|path| (path.clone(), load_balance(path)))
+            .collect()
+    };
+    let frames_per_sample = (SAMPLE_EVERY.as_secs_f32() / HEADLESS_FRAME.as_secs_f32()) as u32;
+    let max_frames = (MAX_RUN.as_secs_f32() / HEADLESS_FRAME.as_secs_f32()) as u32;
+
+    println!("config,run,seconds,wave,planet_hp,enemies");
+    for (name, balance) in &configs {
+        for run in 0..options.runs {
+            let mut app = headless_app();
+            app.insert_resource(balance.clone());
+            add_bot(&mut app);
+            disable_records(&mut app);
+            start_run(&mut app, options.level);
+
+            for frame in 1..=max_frames {
+                app.update();
+                let state = snapshot(app.world_mut());
+                let ended = state.as_ref().map_or(true, |state| state.over);
+                if frame % frames_per_sample == 0 || ended {
+                    if let Some(state) = state {
+                        println!(
+                            "{},{},{:.1},{},{:.1},{}",
+                            name,
+                            run,
+                            frame as f32 * HEADLESS_FRAME.as_secs_f32(),
+                            state.wave + 1,
+                            state.planet_hp,
+                            state.enemies
+                        );
+                    }
+                }
+                if ended {
+                    break;
+                }
+            }
+        }
+    }
+}
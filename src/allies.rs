@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use bevy::color::palettes::css::{LIME, ORANGE_RED};
+use bevy::prelude::*;
+
+use bevy_rapier2d::{pipeline::CollisionEvent::*, prelude::*};
+
+use crate::audio::{PlaySound, SoundName};
+use crate::balance::Balance;
+use crate::controls::PlayerInput;
+use crate::enemy_grid::EnemyGrid;
+use crate::feed::FeedMessage;
+use crate::teams::{Role, Team};
+use crate::units::ALLY_RADIUS;
+use crate::{
+    spawn_bullet, AssetHandles, Enemy, ImageName, Planet, Player, ShotFired, Spawner, TimeScale,
+    Wallet,
+};
+
+pub const ALLY_COST: u32 = 80;
+pub const ALLY_MAX: usize = 2;
+
+const ALLY_SECONDS: f32 = 20.0;
+const ALLY_HP: f32 = 5.0;
+const ALLY_ORBIT: f32 = 160.0;
+const ALLY_ANGULAR_SPEED: f32 = -0.4;
+const ALLY_RANGE: f32 = 350.0;
+/// Seconds to fly between the spawn ring and the orbit.
+const WARP_SECONDS: f32 = 1.0;
+const ALLY_COLOR: Color = Color::srgb(0.4, 1.0, 0.5);
+
+/// Ship of the planet that helps for a while, then warps out.
+#[derive(Component)]
+pub struct Ally {
+    pub angle: f32,
+    pub hp: f32,
+    /// From 0 on the spawn ring to 1 on the orbit.
+    pub warp: f32,
+    pub lifetime: Timer,
+    pub cooldown: Timer,
+}
+
+/// Calls an ally in, a consumable that can be bought in the middle of a
+/// wave, unlike the satellites.
+pub fn buy_ally(
+    mut commands: Commands,
+    input: PlayerInput,
+    handles: Res<AssetHandles>,
+    mut wallet: ResMut<Wallet>,
+    mut feed: EventWriter<FeedMessage>,
+    player_query: Query<(&Player, &Transform)>,
+    ally_query: Query<&Ally>,
+) {
+    let (buyer, transform) = match player_query
+        .iter()
+        .find(|(player, _)| input.just_pressed(input.controls(player.index).call))
+    {
+        Some((player, transform)) => (player.index, transform),
+        None => return,
+    };
+    let purse = wallet.purse_mut(buyer);
+    if ally_query.iter().count() >= ALLY_MAX || *purse < ALLY_COST {
+        return;
+    }
+    *purse -= ALLY_COST;
+
+    // warps in above the player who called it
+    let position = transform.translation.truncate();
+    spawn_ally(&mut commands, &handles, position.y.atan2(position.x));
+    feed.send(FeedMessage::new("ally called in", Color::from(LIME)));
+}
+
+pub fn spawn_ally(commands: &mut Commands, handles: &AssetHandles, angle: f32) {
+    commands
+        .spawn(SpriteBundle {
+            texture: handles.images.get(&ImageName::Player).unwrap().clone_weak(),
+            sprite: Sprite {
+                color: ALLY_COLOR,
+                ..default()
+            },
+            // far away until the first orbit update places it
+            transform: Transform::from_xyz(0.0, 100000.0, 2.0),
+            ..default()
+        })
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Collider::ball(ALLY_RADIUS))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Team::Player)
+        .insert(Team::Player.collision_groups(Role::Body))
+        .insert(Ally {
+            angle,
+            hp: ALLY_HP,
+            warp: 0.0,
+            lifetime: Timer::new(Duration::from_secs_f32(ALLY_SECONDS), TimerMode::Once),
+            cooldown: Timer::new(Duration::from_millis(600), TimerMode::Once),
+        });
+}
+
+/// Flies the allies from the spawn ring to their orbit, and back out when
+/// their time is up.
+pub fn move_allies(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut feed: EventWriter<FeedMessage>,
+    planet_query: Query<&Planet>,
+    spawner_query: Query<&Spawner>,
+    mut ally_query: Query<(Entity, &mut Ally, &mut Transform)>,
+) {
+    let (planet, spawner) = match (planet_query.get_single(), spawner_query.get_single()) {
+        (Ok(planet), Ok(spawner)) => (planet, spawner),
+        _ => return,
+    };
+    let dt = time_scale.delta_seconds(&time);
+    let orbit = planet.size * 0.5 + ALLY_ORBIT;
+    let ring = spawner.size * 0.5;
+    for (entity, mut ally, mut transform) in &mut ally_query {
+        ally.lifetime.tick(time_scale.delta(&time));
+        if ally.lifetime.finished() {
+            if ally.warp >= 1.0 {
+                sound_events.send(PlaySound {
+                    sound: SoundName::Warp,
+                    position: Some(transform.translation.truncate()),
+                    caption: Some("ally warped out"),
+                });
+                feed.send(FeedMessage::new("ally warped out", Color::from(LIME)));
+            }
+            ally.warp -= dt / WARP_SECONDS;
+            if ally.warp <= 0.0 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        } else if ally.warp < 1.0 {
+            if ally.warp == 0.0 {
+                sound_events.send(PlaySound {
+                    sound: SoundName::Warp,
+                    position: None,
+                    caption: Some("ally warping in"),
+                });
+            }
+            ally.warp = (ally.warp + dt / WARP_SECONDS).min(1.0);
+        } else {
+            ally.angle += ALLY_ANGULAR_SPEED * dt;
+        }
+
+        let radius = ring + (orbit - ring) * ally.warp.clamp(0.0, 1.0);
+        let direction = Vec2::from_angle(ally.angle);
+        transform.translation = (direction * radius).extend(transform.translation.z);
+        // nose along the orbit, which goes clockwise
+        transform.rotation = Quat::from_rotation_z(ally.angle - std::f32::consts::PI);
+    }
+}
+
+pub fn ally_shooting(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    balance: Res<Balance>,
+    mut ally_query: Query<(&mut Ally, &Transform)>,
+    enemy_grid: Res<EnemyGrid>,
+    mut shot_events: EventWriter<ShotFired>,
+) {
+    for (mut ally, transform) in &mut ally_query {
+        ally.cooldown.tick(time_scale.delta(&time));
+        // no shooting while warping
+        if !ally.cooldown.finished() || ally.warp < 1.0 {
+            continue;
+        }
+
+        let origin = transform.translation.truncate();
+        if let Some((_, target)) = enemy_grid.nearest(origin, ALLY_RANGE) {
+            ally.cooldown.reset();
+            shot_events.send(ShotFired { by: None });
+            let dir = (target - origin).normalize_or_zero();
+            spawn_bullet(
+                &mut commands,
+                &handles,
+                transform.translation + (dir * 12.0).extend(0.0),
+                dir * 450.0,
+                10.0,
+                &balance.weapons.satellite,
+                None,
+                Team::Player,
+            );
+        }
+    }
+}
+
+pub fn ally_collisions(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut ally_query: Query<(&mut Ally, &Team)>,
+    mut enemy_query: Query<(&mut Enemy, &Team)>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    for collision_event in collision_events.read() {
+        if let Started(ent, oth, _) = collision_event {
+            for (ally_ent, enemy_ent) in [(*ent, *oth), (*oth, *ent)] {
+                let (mut ally, team) = match ally_query.get_mut(ally_ent) {
+                    Ok(ally) => ally,
+                    Err(_) => continue,
+                };
+                let (mut enemy, enemy_team) = match enemy_query.get_mut(enemy_ent) {
+                    Ok(enemy) => enemy,
+                    Err(_) => continue,
+                };
+                if !enemy_team.hurts(*team) || enemy.has_hit != 0 || ally.hp <= 0.0 {
+                    continue;
+                }
+                ally.hp -= enemy.damage;
+                enemy.has_hit = 1;
+                if ally.hp <= 0.0 {
+                    commands.entity(ally_ent).despawn();
+                    feed.send(FeedMessage::new("ally lost", Color::from(ORANGE_RED)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use bevy::color::palettes::css::{DODGER_BLUE, GRAY, ORANGE, TURQUOISE, YELLOW};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use serde::*;
+
+use crate::balance::WeaponBalance;
+use crate::feed::FeedMessage;
+use crate::{AssetHandles, EnemyKilled, FontName, Planet, Player, Spawner, TimeScale};
+
+/// Pixels per second a drop falls toward the planet.
+const DROP_SPEED: f32 = 40.0;
+/// Players pick up the drops that come this close.
+const PICKUP_RADIUS: f32 = 24.0;
+
+const SCOPE_RANGE: f32 = 0.3;
+const COOLANT_HEAT: f32 = 0.4;
+const HEAVY_BARREL_DAMAGE: f32 = 0.4;
+const HEAVY_BARREL_FIRE_RATE: f32 = -0.2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttachmentKind {
+    Scope,
+    Coolant,
+    HeavyBarrel,
+}
+
+impl AttachmentKind {
+    pub const ALL: [AttachmentKind; 3] = [
+        AttachmentKind::Scope,
+        AttachmentKind::Coolant,
+        AttachmentKind::HeavyBarrel,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            AttachmentKind::Scope => "scope",
+            AttachmentKind::Coolant => "coolant",
+            AttachmentKind::HeavyBarrel => "heavy barrel",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            AttachmentKind::Scope => "+30% range",
+            AttachmentKind::Coolant => "-40% beam heat",
+            AttachmentKind::HeavyBarrel => "+40% damage, -20% fire rate",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            AttachmentKind::Scope => Color::from(TURQUOISE),
+            AttachmentKind::Coolant => Color::from(DODGER_BLUE),
+            AttachmentKind::HeavyBarrel => Color::from(ORANGE),
+        }
+    }
+}
+
+/// Weapon an attachment is equipped on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum WeaponSlot {
+    #[default]
+    Player,
+    Satellite,
+}
+
+impl WeaponSlot {
+    const ALL: [WeaponSlot; 2] = [WeaponSlot::Player, WeaponSlot::Satellite];
+
+    fn name(&self) -> &'static str {
+        match self {
+            WeaponSlot::Player => "guns",
+            WeaponSlot::Satellite => "satellites",
+        }
+    }
+
+    fn slots(&self) -> usize {
+        match self {
+            WeaponSlot::Player => 2,
+            WeaponSlot::Satellite => 1,
+        }
+    }
+}
+
+/// Attachments found in the current run.
+#[derive(Default, Resource)]
+pub struct Attachments {
+    /// Picked up and not equipped.
+    pub stash: Vec<AttachmentKind>,
+    pub player: Vec<AttachmentKind>,
+    pub satellite: Vec<AttachmentKind>,
+}
+
+impl Attachments {
+    pub fn equipped(&self, slot: WeaponSlot) -> &Vec<AttachmentKind> {
+        match slot {
+            WeaponSlot::Player => &self.player,
+            WeaponSlot::Satellite => &self.satellite,
+        }
+    }
+
+    fn equipped_mut(&mut self, slot: WeaponSlot) -> &mut Vec<AttachmentKind> {
+        match slot {
+            WeaponSlot::Player => &mut self.player,
+            WeaponSlot::Satellite => &mut self.satellite,
+        }
+    }
+
+    fn count(&self, slot: WeaponSlot, kind: AttachmentKind) -> i32 {
+        self.equipped(slot).iter().filter(|k| **k == kind).count() as i32
+    }
+
+    /// The weapon of `slot` with its attachments on.
+    pub fn weapon(&self, slot: WeaponSlot, base: &WeaponBalance) -> WeaponBalance {
+        let scopes = self.count(slot, AttachmentKind::Scope);
+        let coolants = self.count(slot, AttachmentKind::Coolant);
+        let mut weapon = base.clone();
+        weapon.range *= 1.0 + SCOPE_RANGE * scopes as f32;
+        if let Some(beam) = &mut weapon.beam {
+            beam.heat_per_second *= (1.0 - COOLANT_HEAT).powi(coolants);
+        }
+        weapon
+    }
+
+    pub fn damage_multiplier(&self, slot: WeaponSlot) -> f32 {
+        let barrels = self.count(slot, AttachmentKind::HeavyBarrel);
+        1.0 + HEAVY_BARREL_DAMAGE * barrels as f32
+    }
+
+    pub fn fire_rate_multiplier(&self, slot: WeaponSlot) -> f32 {
+        let barrels = self.count(slot, AttachmentKind::HeavyBarrel);
+        (1.0 + HEAVY_BARREL_FIRE_RATE * barrels as f32).max(0.1)
+    }
+}
+
+/// The loadout screen, open between the waves.
+#[derive(Default, Resource)]
+pub struct AttachmentScreen {
+    pub open: bool,
+    /// Weapon the stash gets equipped on.
+    pub weapon: WeaponSlot,
+}
+
+#[derive(Component)]
+pub struct AttachmentDrop(pub AttachmentKind);
+
+#[derive(Component)]
+pub struct AttachmentScreenRoot;
+
+#[derive(Component)]
+pub enum AttachmentControl {
+    Weapon(WeaponSlot),
+    /// Back to the stash.
+    Equipped(WeaponSlot, usize),
+    Stash(usize),
+}
+
+pub fn setup_attachments(mut commands: Commands) {
+    commands.insert_resource(Attachments::default());
+    commands.insert_resource(AttachmentScreen::default());
+}
+
+/// The attachments rolled from the loot table of the killed enemies.
+pub fn drop_attachments(mut commands: Commands, mut killed_events: EventReader<EnemyKilled>) {
+    for event in killed_events.read() {
+        for kind in &event.loot.attachments {
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: kind.color(),
+                        custom_size: Some(Vec2::new(10.0, 10.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(1.5)),
+                    ..default()
+                })
+                .insert(AttachmentDrop(*kind));
+        }
+    }
+}
+
+/// Drops fall toward the planet, the players pick them up on the way and
+/// the atmosphere burns up the rest.
+pub fn collect_attachments(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    mut attachments: ResMut<Attachments>,
+    planet_query: Query<&Planet>,
+    player_query: Query<&Transform, (With<Player>, Without<AttachmentDrop>)>,
+    mut drop_query: Query<(Entity, &AttachmentDrop, &mut Transform)>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    let rim = planet_query
+        .get_single()
+        .map_or(0.0, |planet| planet.size * 0.5);
+    let dt = time_scale.delta_seconds(&time);
+    for (entity, drop, mut transform) in &mut drop_query {
+        let position = transform.translation.truncate();
+        let step = position.normalize_or_zero() * DROP_SPEED * dt;
+        transform.translation -= step.extend(0.0);
+        let picked = player_query.iter().any(|player_trans| {
+            player_trans.translation.truncate().distance(position) < PICKUP_RADIUS
+        });
+        if picked {
+            attachments.stash.push(drop.0);
+            feed.send(FeedMessage::new(
+                format!("picked up a {}", drop.0.name()),
+                drop.0.color(),
+            ));
+        }
+        if picked || position.length() < rim {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn toggle_attachment_screen(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut screen: ResMut<AttachmentScreen>,
+    spawner_query: Query<&Spawner>,
+) {
+    let intermission = spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission());
+    if keyboard_input.just_pressed(KeyCode::KeyG) && intermission {
+        screen.open = !screen.open;
+    }
+    if !intermission && screen.open {
+        screen.open = false;
+    }
+}
+
+pub fn attachment_interaction(
+    mut attachments: ResMut<Attachments>,
+    mut screen: ResMut<AttachmentScreen>,
+    control_query: Query<(&Interaction, &AttachmentControl), Changed<Interaction>>,
+) {
+    for (interaction, control) in &control_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match control {
+            AttachmentControl::Weapon(slot) => screen.weapon = *slot,
+            AttachmentControl::Equipped(slot, index) => {
+                if *index < attachments.equipped(*slot).len() {
+                    let kind = attachments.equipped_mut(*slot).remove(*index);
+                    attachments.stash.push(kind);
+                }
+            }
+            AttachmentControl::Stash(index) => {
+                let slot = screen.weapon;
+                if *index < attachments.stash.len()
+                    && attachments.equipped(slot).len() < slot.slots()
+                {
+                    let kind = attachments.stash.remove(*index);
+                    attachments.equipped_mut(slot).push(kind);
+                }
+            }
+        }
+    }
+}
+
+pub fn show_attachment_screen(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    attachments: Res<Attachments>,
+    screen: Res<AttachmentScreen>,
+    root_query: Query<Entity, With<AttachmentScreenRoot>>,
+) {
+    if !attachments.is_changed() && !screen.is_changed() {
+        return;
+    }
+    for entity in &root_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !screen.open {
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    let selected = screen.weapon;
+    let button = |parent: &mut ChildBuilder, control: AttachmentControl, text: TextSection| {
+        parent
+            .spawn(ButtonBundle {
+                style: Style {
+                    width: Val::Px(320.0),
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                ..default()
+            })
+            .insert(control)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_sections([text]));
+            });
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.0),
+                right: Val::Px(15.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.9).into(),
+            focus_policy: FocusPolicy::Block,
+            ..default()
+        })
+        .insert(AttachmentScreenRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "attachments [g]",
+                style(28.0, Color::WHITE),
+            ));
+            for slot in WeaponSlot::ALL {
+                let color = if slot == selected {
+                    Color::from(YELLOW)
+                } else {
+                    Color::WHITE
+                };
+                button(
+                    parent,
+                    AttachmentControl::Weapon(slot),
+                    TextSection::new(slot.name(), style(22.0, color)),
+                );
+                for index in 0..slot.slots() {
+                    match attachments.equipped(slot).get(index) {
+                        Some(kind) => button(
+                            parent,
+                            AttachmentControl::Equipped(slot, index),
+                            TextSection::new(
+                                format!("  {}, {}", kind.name(), kind.description()),
+                                style(18.0, kind.color()),
+                            ),
+                        ),
+                        None => {
+                            parent.spawn(TextBundle::from_section(
+                                "  empty slot",
+                                style(18.0, Color::from(GRAY)),
+                            ));
+                        }
+                    }
+                }
+            }
+            parent.spawn(TextBundle::from_section(
+                format!("stash, click to equip on the {}", selected.name()),
+                style(22.0, Color::WHITE),
+            ));
+            if attachments.stash.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    "  nothing found yet",
+                    style(18.0, Color::from(GRAY)),
+                ));
+            }
+            for (index, kind) in attachments.stash.iter().enumerate() {
+                button(
+                    parent,
+                    AttachmentControl::Stash(index),
+                    TextSection::new(
+                        format!("  {}, {}", kind.name(), kind.description()),
+                        style(18.0, kind.color()),
+                    ),
+                );
+            }
+        });
+}
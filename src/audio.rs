@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::boss::BossEncounter;
+use crate::conditions::Persistent;
+use crate::enemies::EnemyDefinitions;
+use crate::focus::InputCapture;
+use crate::launch::LaunchOptions;
+use crate::{Combo, Enemy, EnemyKilled, Planet, Spawner};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundName {
+    Warp,
+    Impact,
+    Stinger,
+}
+
+impl SoundName {
+    const ALL: [SoundName; 3] = [SoundName::Warp, SoundName::Impact, SoundName::Stinger];
+
+    fn file(&self) -> &'static str {
+        match self {
+            SoundName::Warp => "warp",
+            SoundName::Impact => "impact",
+            SoundName::Stinger => "stinger",
+        }
+    }
+
+    fn bus(&self) -> Bus {
+        match self {
+            SoundName::Warp | SoundName::Impact => Bus::Sfx,
+            SoundName::Stinger => Bus::Ui,
+        }
+    }
+
+    /// Instances playing at once, the requests past it are dropped so that
+    /// a burst of hits doesn't clip.
+    fn max_instances(&self) -> usize {
+        match self {
+            SoundName::Warp => 4,
+            SoundName::Impact => 8,
+            SoundName::Stinger => 1,
+        }
+    }
+
+    /// Seconds the music dips for under this sound.
+    fn duck(&self) -> Option<f32> {
+        match self {
+            SoundName::Stinger => Some(2.5),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    Sfx,
+    /// Cues that aren't in the world, like the stingers.
+    Ui,
+}
+
+/// Music under a ducking sound plays at this fraction of its volume.
+const DUCK_LEVEL: f32 = 0.3;
+/// Volume regained per second once the ducking sound is over.
+const DUCK_RECOVERY: f32 = 1.5;
+
+/// Gains of the buses, applied on top of the music and sfx volumes.
+#[derive(Resource)]
+pub struct Mixer {
+    pub music: f32,
+    pub sfx: f32,
+    pub ui: f32,
+    /// Seconds left on the ducking of the music.
+    pub duck_left: f32,
+    /// Current dip of the music, 1.0 when not ducked.
+    pub duck: f32,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Mixer {
+            music: 1.0,
+            sfx: 1.0,
+            ui: 1.0,
+            duck_left: 0.0,
+            duck: 1.0,
+        }
+    }
+}
+
+impl Mixer {
+    pub fn gain(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Music => self.music * self.duck,
+            Bus::Sfx => self.sfx,
+            Bus::Ui => self.ui,
+        }
+    }
+}
+
+/// Half of a sound being played, counted against its cap.
+#[derive(Component)]
+pub struct SfxInstance(SoundName);
+
+/// Request to play a sound, world positioned sounds are panned and
+/// attenuated relative to what the camera is looking at.
+#[derive(Event)]
+pub struct PlaySound {
+    pub sound: SoundName,
+    pub position: Option<Vec2>,
+    /// Shown in the caption area when captions are on, for the cues that
+    /// carry meaning beyond the noise.
+    pub caption: Option<&'static str>,
+}
+
+/// Each sound is stored as a pair of stereo files with the signal only on
+/// the left or the right channel: the sink can't pan, so the two halves
+/// are played together with equal-power gains instead.
+#[derive(Resource)]
+pub struct Sfx {
+    pub sounds: HashMap<SoundName, (Handle<AudioSource>, Handle<AudioSource>)>,
+    pub volume: f32,
+    /// Distance past the edge of the view over which sounds fade by half.
+    pub falloff: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Stem {
+    Calm,
+    Pulse,
+    Intense,
+    /// Heartbeat layered on top while the planet is close to falling.
+    Alarm,
+    /// Takes over from the intense layer while a boss is alive.
+    Boss,
+}
+
+impl Stem {
+    const ALL: [Stem; 5] = [
+        Stem::Calm,
+        Stem::Pulse,
+        Stem::Intense,
+        Stem::Alarm,
+        Stem::Boss,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            Stem::Calm => "music/stem_calm.wav",
+            Stem::Pulse => "music/stem_pulse.wav",
+            Stem::Intense => "music/stem_intense.wav",
+            Stem::Alarm => "music/stem_alarm.wav",
+            Stem::Boss => "music/stem_boss.wav",
+        }
+    }
+}
+
+pub struct MusicLayer {
+    pub stem: Stem,
+    pub source: Handle<AudioSource>,
+    /// Playing the stem, once started.
+    pub entity: Option<Entity>,
+    pub volume: f32,
+    pub target: f32,
+}
+
+/// Stems of the same track, started together and mixed by volume.
+#[derive(Resource)]
+pub struct Music {
+    pub layers: Vec<MusicLayer>,
+    pub fade_per_second: f32,
+    pub master: f32,
+}
+
+impl Music {
+    pub fn set_target(&mut self, stem: Stem, target: f32) {
+        for layer in self.layers.iter_mut().filter(|layer| layer.stem == stem) {
+            layer.target = target;
+        }
+    }
+}
+
+pub fn load_sfx(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    launch: Res<LaunchOptions>,
+) {
+    commands.insert_resource(Sfx {
+        sounds: SoundName::ALL
+            .iter()
+            .map(|sound| {
+                (
+                    *sound,
+                    (
+                        asset_server.load(format!("sfx/{}_l.wav", sound.file())),
+                        asset_server.load(format!("sfx/{}_r.wav", sound.file())),
+                    ),
+                )
+            })
+            .collect(),
+        volume: if launch.mute { 0.0 } else { 0.5 },
+        falloff: 400.0,
+    });
+}
+
+/// Returns the (left, right) gains for a sound at the given position.
+fn spatial_gains(
+    position: Vec2,
+    view_center: Vec2,
+    view_half_size: Vec2,
+    falloff: f32,
+) -> (f32, f32) {
+    let offset = position - view_center;
+    let pan = (offset.x / view_half_size.x.max(1.0)).clamp(-1.0, 1.0);
+    let outside = (offset.abs() - view_half_size).max(Vec2::ZERO).length();
+    let attenuation = 1.0 / (1.0 + outside / falloff);
+    let theta = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+    (f32::cos(theta) * attenuation, f32::sin(theta) * attenuation)
+}
+
+pub fn play_sounds(
+    mut commands: Commands,
+    sfx: Res<Sfx>,
+    mut mixer: ResMut<Mixer>,
+    instance_query: Query<&SfxInstance>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    mut sound_events: EventReader<PlaySound>,
+) {
+    let (view_center, view_half_size) = match (camera_query.get_single(), window_query.get_single())
+    {
+        (Ok((transform, projection)), Ok(window)) => (
+            transform.translation.truncate(),
+            Vec2::new(window.width(), window.height()) * 0.5 * projection.scale,
+        ),
+        _ => (Vec2::ZERO, Vec2::splat(512.0)),
+    };
+
+    let mut playing: HashMap<SoundName, usize> = HashMap::new();
+    for instance in &instance_query {
+        *playing.entry(instance.0).or_default() += 1;
+    }
+
+    for event in sound_events.read() {
+        // each instance is a pair of halves
+        let count = playing.entry(event.sound).or_default();
+        if *count / 2 >= event.sound.max_instances() {
+            continue;
+        }
+        *count += 2;
+        if let Some(seconds) = event.sound.duck() {
+            mixer.duck_left = mixer.duck_left.max(seconds);
+        }
+        let volume = sfx.volume * mixer.gain(event.sound.bus());
+        let (left, right) = match event.position {
+            Some(position) => spatial_gains(position, view_center, view_half_size, sfx.falloff),
+            None => (
+                std::f32::consts::FRAC_1_SQRT_2,
+                std::f32::consts::FRAC_1_SQRT_2,
+            ),
+        };
+        if let Some((source_left, source_right)) = sfx.sounds.get(&event.sound) {
+            for (source, gain) in [(source_left, left), (source_right, right)] {
+                commands
+                    .spawn(AudioBundle {
+                        source: source.clone(),
+                        settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(gain * volume)),
+                    })
+                    .insert(SfxInstance(event.sound));
+            }
+        }
+    }
+}
+
+pub fn load_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    launch: Res<LaunchOptions>,
+) {
+    commands.insert_resource(Music {
+        layers: Stem::ALL
+            .iter()
+            .map(|stem| MusicLayer {
+                stem: *stem,
+                source: asset_server.load(stem.path()),
+                entity: None,
+                volume: 0.0,
+                target: if *stem == Stem::Calm { 1.0 } else { 0.0 },
+            })
+            .collect(),
+        fade_per_second: 0.5,
+        master: if launch.mute { 0.0 } else { 0.6 },
+    });
+}
+
+/// Waits for every stem to be loaded, then starts them on the same frame
+/// so that the loops stay aligned.
+pub fn start_music(
+    mut commands: Commands,
+    sources: Res<Assets<AudioSource>>,
+    capture: Res<InputCapture>,
+    mut music: ResMut<Music>,
+) {
+    // browsers only start audio after a user gesture
+    if !capture.started {
+        return;
+    }
+    if music.layers.iter().any(|layer| layer.entity.is_some()) {
+        return;
+    }
+    if music
+        .layers
+        .iter()
+        .any(|layer| sources.get(&layer.source).is_none())
+    {
+        return;
+    }
+    for layer in music.layers.iter_mut() {
+        let entity = commands
+            .spawn(AudioBundle {
+                source: layer.source.clone(),
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+            })
+            .insert(Persistent)
+            .id();
+        layer.entity = Some(entity);
+    }
+}
+
+pub fn fade_music(
+    time: Res<Time>,
+    sink_query: Query<&AudioSink>,
+    mut mixer: ResMut<Mixer>,
+    mut music: ResMut<Music>,
+) {
+    let dt = time.delta_seconds();
+    mixer.duck_left = (mixer.duck_left - dt).max(0.0);
+    mixer.duck = if mixer.duck_left > 0.0 {
+        DUCK_LEVEL
+    } else {
+        (mixer.duck + DUCK_RECOVERY * dt).min(1.0)
+    };
+
+    let step = music.fade_per_second * dt;
+    let master = music.master * mixer.gain(Bus::Music);
+    for layer in music.layers.iter_mut() {
+        if layer.volume < layer.target {
+            layer.volume = (layer.volume + step).min(layer.target);
+        } else {
+            layer.volume = (layer.volume - step).max(layer.target);
+        }
+        if let Some(sink) = layer.entity.and_then(|entity| sink_query.get(entity).ok()) {
+            sink.set_volume(layer.volume * master);
+        }
+    }
+}
+
+/// Fraction of its hp under which the planet sounds the alarm.
+const ALARM_HP_FRACTION: f32 = 0.2;
+
+/// Calm during the intermission, pulse when enemies are around and
+/// the intense layer once the combo or the swarm grows. When the planet
+/// drops low a stinger plays once and the alarm replaces the calm layer.
+/// A boss brings its own theme, resolved by a stinger when it dies.
+pub fn music_intensity(
+    combo: Res<Combo>,
+    encounter: Option<Res<BossEncounter>>,
+    definitions: Res<EnemyDefinitions>,
+    mut music: ResMut<Music>,
+    mut alarm: Local<bool>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut killed_events: EventReader<EnemyKilled>,
+    spawner_query: Query<&Spawner>,
+    enemy_query: Query<&Enemy>,
+    planet_query: Query<&Planet>,
+) {
+    let intermission = spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission());
+    let alive = enemy_query.iter().count();
+
+    let (pulse, intense) = if intermission {
+        (0.0, 0.0)
+    } else {
+        let pulse = (alive as f32 / 5.0).min(1.0);
+        let intense = if combo.count >= 5 || alive >= 15 {
+            1.0
+        } else {
+            0.0
+        };
+        (pulse, intense)
+    };
+
+    let low = planet_query
+        .get_single()
+        .is_ok_and(|planet| planet.hp > 0.0 && planet.hp < planet.max_hp * ALARM_HP_FRACTION);
+    if low && !*alarm {
+        sound_events.send(PlaySound {
+            sound: SoundName::Stinger,
+            position: None,
+            caption: Some("warning: planet critical"),
+        });
+    }
+    *alarm = low;
+
+    let fighting = encounter.is_some_and(|encounter| encounter.boss.is_some());
+    if killed_events
+        .read()
+        .any(|killed| definitions.get(killed.enemy_id).boss)
+    {
+        sound_events.send(PlaySound {
+            sound: SoundName::Stinger,
+            position: None,
+            caption: Some("boss defeated"),
+        });
+    }
+
+    music.set_target(Stem::Calm, if low { 0.0 } else { 1.0 });
+    music.set_target(Stem::Pulse, pulse);
+    music.set_target(Stem::Intense, if fighting { 0.0 } else { intense });
+    music.set_target(Stem::Alarm, if low { 1.0 } else { 0.0 });
+    music.set_target(Stem::Boss, if fighting { 1.0 } else { 0.0 });
+}
+
+pub fn play_game_over_sting(mut sound_events: EventWriter<PlaySound>) {
+    sound_events.send(PlaySound {
+        sound: SoundName::Stinger,
+        position: None,
+        caption: Some("the planet has fallen"),
+    });
+}
+
+/// The alarm and the boss theme don't outlive the run they were raised in.
+pub fn silence_alarm(mut music: ResMut<Music>) {
+    music.set_target(Stem::Alarm, 0.0);
+    music.set_target(Stem::Boss, 0.0);
+    music.set_target(Stem::Calm, 1.0);
+}
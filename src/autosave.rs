@@ -0,0 +1,166 @@
+use serde::*;
+
+use bevy::color::palettes::css::CYAN;
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::level::{CurrentLevel, Levels};
+use crate::menu::MenuRoot;
+use crate::run_link::RunLink;
+use crate::snapshot::{RunSnapshot, RunState};
+use crate::spawner::RunSeed;
+use crate::storage;
+use crate::{AppState, AssetHandles, FontName, WaveEnded};
+
+const BUTTON_IDLE: Color = Color::srgb(0.05, 0.05, 0.05);
+const BUTTON_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
+
+/// Run in progress, saved at the end of every wave and removed when the
+/// run is over. Finding one at startup means the game didn't close
+/// cleanly, the menu offers to resume it.
+#[derive(Serialize, Deserialize, Clone, Resource)]
+pub struct Autosave {
+    /// Query of the run link, it sets the run up again.
+    pub run: String,
+    pub snapshot: RunSnapshot,
+}
+
+#[derive(Component)]
+pub struct ResumeButton;
+
+pub fn load_autosave(mut commands: Commands) {
+    if let Some(autosave) = storage::load::<Autosave>("autosave") {
+        commands.insert_resource(autosave);
+    }
+}
+
+pub fn autosave_wave(
+    seed: Res<RunSeed>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    mut wave_events: EventReader<WaveEnded>,
+    state: RunState,
+) {
+    if wave_events.read().last().is_none() {
+        return;
+    }
+    // practice and sandbox runs can't be set up again
+    if modifiers.practice.is_some() || modifiers.sandbox {
+        return;
+    }
+    let snapshot = match state.capture() {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+    let link = RunLink::from_run(&levels.list[current_level.0].name, seed.0, &modifiers);
+    let autosave = Autosave {
+        run: link.query(),
+        snapshot,
+    };
+    if !storage::save("autosave", &autosave) {
+        warn!("failed to autosave the run");
+    }
+}
+
+/// The run is over, or the player started another one from the menu.
+pub fn clear_autosave(mut commands: Commands) {
+    commands.remove_resource::<Autosave>();
+    storage::remove("autosave");
+}
+
+/// Puts the recovered run back once its entities are spawned.
+pub fn restore_autosave(
+    mut commands: Commands,
+    mut modifiers: ResMut<RunModifiers>,
+    mut state: RunState,
+) {
+    if let Some(snapshot) = modifiers.restore.take() {
+        let hp_multiplier = modifiers.enemy_hp_multiplier();
+        state.restore(&mut commands, &snapshot, hp_multiplier);
+    }
+}
+
+pub fn setup_resume_button(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    autosave: Option<Res<Autosave>>,
+    root_query: Query<Entity, With<MenuRoot>>,
+) {
+    let (autosave, root) = match (autosave, root_query.get_single()) {
+        (Some(autosave), Ok(root)) => (autosave, root),
+        _ => return,
+    };
+    let level = RunLink::parse(&autosave.run)
+        .map(|link| link.level)
+        .unwrap_or_default();
+    commands.entity(root).with_children(|parent| {
+        parent
+            .spawn(ButtonBundle {
+                style: Style {
+                    width: Val::Px(320.0),
+                    height: Val::Px(48.0),
+                    margin: UiRect::all(Val::Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_IDLE.into(),
+                ..default()
+            })
+            .insert(ResumeButton)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("resume {} wave {}", level, autosave.snapshot.wave + 1),
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 24.0,
+                        color: Color::from(CYAN),
+                    },
+                ));
+            });
+    });
+}
+
+pub fn resume_interaction(
+    autosave: Option<Res<Autosave>>,
+    levels: Res<Levels>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut state: ResMut<NextState<AppState>>,
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ResumeButton>),
+    >,
+) {
+    let autosave = match autosave {
+        Some(autosave) => autosave,
+        None => return,
+    };
+    for (interaction, mut color) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => {
+                let run = RunLink::parse(&autosave.run).and_then(|link| link.modifiers(&levels));
+                let (level, resumed) = match run {
+                    Some(run) => run,
+                    None => {
+                        warn!("the autosave names an unknown run {}", autosave.run);
+                        return;
+                    }
+                };
+                current_level.0 = level;
+                *modifiers = RunModifiers {
+                    restore: Some(autosave.snapshot.clone()),
+                    ..resumed
+                };
+                state.set(AppState::InGame);
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::spawner::SpawnBand;
+use crate::units::DEFAULT_PIXELS_PER_METER;
+
+// embedded for the same reason as the level files
+const BALANCE_FILE: &str = include_str!("../assets/balance.json");
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UltimateBalance {
+    pub charge_per_kill: f32,
+    /// Charge of an enemy shot going past a player without hitting.
+    pub charge_per_graze: f32,
+    pub charge_max: f32,
+    pub windup_seconds: f32,
+    pub sweep_seconds: f32,
+    pub damage_per_second: f32,
+    /// Angular half-width of the beam, in radians.
+    pub beam_width: f32,
+}
+
+/// Physical projectiles are rigid bodies that can shove what they hit,
+/// sensor ones only report intersections and move at a fixed velocity.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileMode {
+    Physical,
+    Sensor,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WeaponBalance {
+    pub projectile: ProjectileMode,
+    /// Pixels a projectile travels at full damage.
+    pub range: f32,
+    /// Pixels past the range over which the damage fades to nothing,
+    /// the projectile is gone after that.
+    pub falloff: f32,
+    /// Explosive projectiles leave a damaging zone where they hit.
+    #[serde(default)]
+    pub zone: Option<ZoneBalance>,
+    /// Enemies a projectile passes through before it stops.
+    #[serde(default)]
+    pub pierce: u8,
+    /// Lays proximity mines where the shooter stands instead of firing.
+    #[serde(default)]
+    pub mines: Option<MineBalance>,
+    /// Fires a continuous beam instead of projectiles. It stops at the
+    /// first enemy, or goes through all of them with any pierce.
+    #[serde(default)]
+    pub beam: Option<BeamBalance>,
+}
+
+/// Continuous beam, see the beam module.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BeamBalance {
+    pub damage_per_second: f32,
+    /// Seconds between two damage ticks.
+    pub tick_seconds: f32,
+    /// Pixels it reaches.
+    pub length: f32,
+    pub width: f32,
+    /// Heat gained per second of firing. The beam cuts out at full heat
+    /// until it has cooled down completely.
+    pub heat_per_second: f32,
+    pub cooling_per_second: f32,
+}
+
+/// Gun of the enemies that shoot, see the gunships module.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GunBalance {
+    /// Seconds between two shots.
+    pub cooldown_seconds: f32,
+    /// Pixels per second.
+    pub shot_speed: f32,
+    pub damage: f32,
+    /// The enemy opens fire on a player within the range.
+    pub weapon: WeaponBalance,
+}
+
+/// Timed reflection of the enemy shots, see the parry module.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ParryBalance {
+    /// Seconds the parry stays up after the press.
+    pub window_seconds: f32,
+    /// Seconds between parries, counted from the press.
+    pub cooldown_seconds: f32,
+    /// Shots closer than this to a parrying player are reflected.
+    pub radius: f32,
+    pub damage_multiplier: f32,
+}
+
+/// Proximity mine, see the mines module.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MineBalance {
+    /// Seconds after being laid before it can be set off.
+    pub arm_seconds: f32,
+    /// Pixels around it that set it off.
+    pub trigger_radius: f32,
+    pub blast_radius: f32,
+    pub damage: f32,
+    /// Seconds it lies around before fizzling out.
+    pub lifetime_seconds: f32,
+    /// Mines a player can have out, the oldest fizzles past it.
+    #[serde(default = "default_mine_limit")]
+    pub limit: usize,
+}
+
+fn default_mine_limit() -> usize {
+    6
+}
+
+/// Area left behind by an explosion, damaging what stands inside it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZoneBalance {
+    pub radius: f32,
+    pub duration_seconds: f32,
+    pub tick_seconds: f32,
+    pub damage_per_tick: f32,
+    /// Kills inside the zone set off another one where they fall.
+    #[serde(default)]
+    pub chain: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WeaponsBalance {
+    pub player: WeaponBalance,
+    pub satellite: WeaponBalance,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SpawnerBalance {
+    /// Enemies alive at once, further spawns wait in the spawner queue.
+    pub max_enemies: usize,
+    /// Enemy weights of the generated waves, by wave.
+    #[serde(default)]
+    pub bands: Vec<SpawnBand>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InputBalance {
+    /// A fire press this long before the cooldown ends still fires
+    /// once it does, even if the key was released in between.
+    pub fire_buffer_seconds: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PhysicsBalance {
+    /// Scale handed to rapier, see the units module.
+    pub pixels_per_meter: f32,
+}
+
+impl Default for PhysicsBalance {
+    fn default() -> Self {
+        PhysicsBalance {
+            pixels_per_meter: DEFAULT_PIXELS_PER_METER,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Resource)]
+pub struct Balance {
+    pub ultimate: UltimateBalance,
+    pub weapons: WeaponsBalance,
+    pub spawner: SpawnerBalance,
+    pub input: InputBalance,
+    #[serde(default)]
+    pub physics: PhysicsBalance,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        serde_json::from_str(BALANCE_FILE).expect("invalid balance file")
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::color::palettes::css::ORANGE_RED;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::attachments::{Attachments, WeaponSlot};
+use crate::balance::Balance;
+use crate::conditions::RunModifiers;
+use crate::controls::PlayerInput;
+use crate::enemy_grid::EnemyGrid;
+use crate::feed::FeedMessage;
+use crate::idle::IDLE_EFFICIENCY;
+use crate::random_events::RandomEvents;
+use crate::settings::Settings;
+use crate::shields::DamageKind;
+use crate::teams::{Role, Team};
+use crate::upgrades::RunUpgrades;
+use crate::{fire_direction, Enemy, Player, TimeScale};
+
+/// Heat a beam cuts out at.
+const OVERHEAT: f32 = 1.0;
+const BEAM_Z: f32 = 4.0;
+
+/// The beam of a player, a quad stretched along the ray.
+#[derive(Component)]
+pub struct PlayerBeam {
+    pub player: usize,
+    pub heat: f32,
+    /// Cut out until the heat is back to zero.
+    pub overheated: bool,
+    /// Seconds until the next damage tick.
+    pub tick: f32,
+}
+
+/// Casts the beams of the players holding fire, every frame: the ray
+/// stops at the first enemy, or goes through all of them with pierce.
+pub fn fire_beams(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    balance: Res<Balance>,
+    upgrades: Res<RunUpgrades>,
+    attachments: Res<Attachments>,
+    modifiers: Res<RunModifiers>,
+    random_events: Res<RandomEvents>,
+    settings: Res<Settings>,
+    enemy_grid: Res<EnemyGrid>,
+    rapier_context: Res<RapierContext>,
+    input: PlayerInput,
+    player_query: Query<(&Player, &Transform), Without<PlayerBeam>>,
+    mut beam_query: Query<(Entity, &mut PlayerBeam, &mut Transform, &mut Sprite), Without<Player>>,
+    mut enemy_query: Query<&mut Enemy>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    let weapon = attachments.weapon(
+        WeaponSlot::Player,
+        &upgrades.weapon(&balance.weapons.player),
+    );
+    let params = match &weapon.beam {
+        Some(params) => params,
+        None => {
+            for (entity, ..) in &beam_query {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    };
+    let dt = time_scale.player_delta_seconds(&time);
+    let efficiency = if modifiers.idle { IDLE_EFFICIENCY } else { 1.0 };
+    let filter = QueryFilter::default()
+        .exclude_sensors()
+        .groups(Team::Player.collision_groups(Role::Shot));
+
+    for (player, player_trans) in &player_query {
+        let beam = beam_query
+            .iter_mut()
+            .find(|(_, beam, _, _)| beam.player == player.index);
+        let (_, mut beam, mut transform, mut sprite) = match beam {
+            Some(beam) => beam,
+            None => {
+                commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::NONE,
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(PlayerBeam {
+                        player: player.index,
+                        heat: 0.0,
+                        overheated: false,
+                        tick: 0.0,
+                    });
+                continue;
+            }
+        };
+
+        let controls = input.controls(player.index);
+        let firing = (input.pressed(controls.fire) || modifiers.idle)
+            && !random_events.weapon_disabled()
+            && player.stun <= 0.0
+            && !beam.overheated;
+        if firing {
+            beam.heat += params.heat_per_second * dt;
+            if beam.heat >= OVERHEAT {
+                beam.overheated = true;
+                feed.send(FeedMessage::new(
+                    format!("p{} beam overheated", player.index + 1),
+                    Color::from(ORANGE_RED),
+                ));
+            }
+        } else {
+            beam.heat = (beam.heat - params.cooling_per_second * dt).max(0.0);
+            if beam.heat <= 0.0 {
+                beam.overheated = false;
+            }
+        }
+        if !firing {
+            sprite.color.set_alpha(0.0);
+            continue;
+        }
+
+        let origin = player_trans.translation.truncate();
+        let direction = fire_direction(player_trans, settings.aim_assist, &enemy_grid);
+        let mut hits: Vec<(Entity, f32)> = vec![];
+        rapier_context.intersections_with_ray(
+            origin,
+            direction,
+            params.length,
+            true,
+            filter,
+            |entity, intersection| {
+                hits.push((entity, intersection.time_of_impact));
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if weapon.pierce == 0 {
+            hits.truncate(1);
+        }
+        let length = match hits.last() {
+            Some((_, distance)) if weapon.pierce == 0 => *distance,
+            _ => params.length,
+        };
+
+        beam.tick -= dt;
+        if beam.tick <= 0.0 {
+            beam.tick += params.tick_seconds;
+            let damage = params.damage_per_second
+                * params.tick_seconds
+                * upgrades.damage_multiplier()
+                * attachments.damage_multiplier(WeaponSlot::Player)
+                * efficiency;
+            for (entity, _) in &hits {
+                if let Ok(mut enemy) = enemy_query.get_mut(*entity) {
+                    enemy.take_damage(damage, DamageKind::Energy);
+                    enemy.last_hit_by = Some(player.index);
+                }
+            }
+        }
+
+        transform.translation = origin.extend(BEAM_Z);
+        transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+        sprite.custom_size = Some(Vec2::new(length, params.width));
+        // it goes from white to red as it heats up
+        sprite.color = Color::srgba(1.0, 1.0 - beam.heat * 0.8, 1.0 - beam.heat, 0.9);
+    }
+}
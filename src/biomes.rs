@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use serde::*;
+
+use bevy::color::palettes::css::ORANGE_RED;
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::balance::ZoneBalance;
+use crate::feed::FeedMessage;
+use crate::level::{CurrentLevel, Levels};
+use crate::teams::Team;
+use crate::zones::spawn_zone;
+use crate::{AssetHandles, Bullet, Enemy, Planet, TimeScale};
+
+/// Linear damping of the enemies on ice, they keep drifting on turns.
+const ICY_DAMPING: f32 = 0.15;
+
+const ERUPTION_INTERVAL_MS: u64 = 12000;
+/// Pixels above the surface where the lava lands.
+const ERUPTION_HEIGHT: f32 = 60.0;
+const ERUPTION: ZoneBalance = ZoneBalance {
+    chain: false,
+    radius: 90.0,
+    duration_seconds: 2.0,
+    tick_seconds: 0.5,
+    damage_per_tick: 20.0,
+};
+
+/// Thickness of the atmosphere above the surface.
+const ATMOSPHERE_HEIGHT: f32 = 200.0;
+/// Fraction of their speed bullets lose per second in the atmosphere.
+const ATMOSPHERE_DRAG: f32 = 1.2;
+
+/// Trait of the planet of a level, each has its own systems.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Biome {
+    #[default]
+    Temperate,
+    Icy,
+    Volcanic,
+    GasGiant,
+}
+
+pub fn biome_is(biome: Biome) -> impl Fn(Res<Levels>, Res<CurrentLevel>) -> bool {
+    move |levels, current_level| levels.list[current_level.0].biome == biome
+}
+
+#[derive(Resource)]
+pub struct Eruptions {
+    pub timer: Timer,
+}
+
+pub fn setup_biome(mut commands: Commands) {
+    commands.insert_resource(Eruptions {
+        timer: Timer::new(
+            Duration::from_millis(ERUPTION_INTERVAL_MS),
+            TimerMode::Repeating,
+        ),
+    });
+}
+
+pub fn icy_slide(mut enemy_query: Query<&mut Damping, Added<Enemy>>) {
+    for mut damping in &mut enemy_query {
+        damping.linear_damping = ICY_DAMPING;
+    }
+}
+
+pub fn volcanic_eruptions(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    handles: Res<AssetHandles>,
+    mut eruptions: ResMut<Eruptions>,
+    mut feed: EventWriter<FeedMessage>,
+    planet_query: Query<&Planet>,
+) {
+    eruptions.timer.tick(time_scale.delta(&time));
+    if !eruptions.timer.just_finished() {
+        return;
+    }
+    let planet = match planet_query.get_single() {
+        Ok(planet) => planet,
+        Err(_) => return,
+    };
+    let angle: f32 = thread_rng().gen_range(0.0..(2.0 * std::f32::consts::PI));
+    let position =
+        Vec2::new(f32::cos(angle), f32::sin(angle)) * (planet.size * 0.5 + ERUPTION_HEIGHT);
+    spawn_zone(
+        &mut commands,
+        &handles,
+        position,
+        &ERUPTION,
+        // the planet's own volcanoes, they spare it
+        Team::Player,
+        None,
+    );
+    feed.send(FeedMessage::new("eruption", Color::from(ORANGE_RED)));
+}
+
+/// Bullets inside the atmosphere slow down, shots from far away lose
+/// their reach before getting to the planet.
+/// Fraction of its speed a bullet at `position` keeps over `dt` seconds,
+/// less than all of it inside the atmosphere.
+pub fn atmosphere_keep(planet: &Planet, position: Vec2, dt: f32) -> f32 {
+    if position.length() > planet.size * 0.5 + ATMOSPHERE_HEIGHT {
+        1.0
+    } else {
+        (1.0 - ATMOSPHERE_DRAG * dt).max(0.0)
+    }
+}
+
+pub fn atmosphere_drag(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    planet_query: Query<&Planet>,
+    mut bullet_query: Query<(&mut Bullet, &Transform, Option<&mut Velocity>)>,
+) {
+    let planet = match planet_query.get_single() {
+        Ok(planet) => planet,
+        Err(_) => return,
+    };
+    let dt = time_scale.delta_seconds(&time);
+    for (mut bullet, transform, velocity) in &mut bullet_query {
+        let keep = atmosphere_keep(planet, transform.translation.truncate(), dt);
+        if keep >= 1.0 {
+            continue;
+        }
+        if let Some(sensor_velocity) = &mut bullet.velocity {
+            *sensor_velocity *= keep;
+        }
+        if let Some(mut velocity) = velocity {
+            velocity.linvel *= keep;
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::color::palettes::css::{LIGHT_SKY_BLUE, ORANGE, RED};
+use bevy::prelude::*;
+
+use crate::score::RunStats;
+use crate::{AssetHandles, Enemy, FontName, TimeScale, Wallet};
+
+/// Elites below this fraction of their hp can be executed.
+const EXECUTION_HP_FRACTION: f32 = 0.1;
+/// Executions take a shot fired from closer than this.
+const EXECUTION_RANGE: f32 = 120.0;
+const EXECUTION_SCORE: u32 = 100;
+const EXECUTION_SCRAP: u32 = 10;
+const GRAZE_SCORE: u32 = 10;
+const SCORE_PER_OVERKILL: f32 = 0.5;
+/// Overkill below this is just rounding, not worth a popup.
+const OVERKILL_MIN: f32 = 10.0;
+
+const POPUP_SECONDS: f32 = 0.8;
+/// Pixels a popup rises over its lifetime.
+const POPUP_RISE: f32 = 40.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KillBonusKind {
+    /// Damage past what the killing blow needed.
+    Overkill(f32),
+    Execution,
+    /// An enemy shot that went past a player without hitting, see the
+    /// graze module.
+    Graze,
+}
+
+#[derive(Event)]
+pub struct KillBonus {
+    pub kind: KillBonusKind,
+    pub position: Vec2,
+    pub by: Option<usize>,
+}
+
+impl KillBonusKind {
+    /// The bonus of a shot that took the enemy from `hp_before` to its
+    /// current hp, fired from `distance` away.
+    pub fn of_blow(enemy: &Enemy, hp_before: f32, distance: f32) -> Option<KillBonusKind> {
+        if hp_before <= 0.0 || enemy.hp > 0.0 {
+            return None;
+        }
+        if enemy.elite
+            && hp_before < enemy.max_hp * EXECUTION_HP_FRACTION
+            && distance < EXECUTION_RANGE
+        {
+            Some(KillBonusKind::Execution)
+        } else if -enemy.hp >= OVERKILL_MIN {
+            Some(KillBonusKind::Overkill(-enemy.hp))
+        } else {
+            None
+        }
+    }
+
+    fn score(&self) -> u32 {
+        match self {
+            KillBonusKind::Overkill(damage) => (damage * SCORE_PER_OVERKILL) as u32,
+            KillBonusKind::Execution => EXECUTION_SCORE,
+            KillBonusKind::Graze => GRAZE_SCORE,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BonusPopup {
+    pub age: f32,
+}
+
+pub fn award_kill_bonuses(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut stats: ResMut<RunStats>,
+    mut wallet: ResMut<Wallet>,
+    mut bonus_events: EventReader<KillBonus>,
+) {
+    for event in bonus_events.read() {
+        let score = event.kind.score();
+        stats.bonus += score;
+        let (text, color, size) = match event.kind {
+            KillBonusKind::Overkill(_) => (format!("overkill +{}", score), ORANGE, 16.0),
+            KillBonusKind::Execution => {
+                *wallet.purse_mut(event.by.unwrap_or(0)) += EXECUTION_SCRAP;
+                (format!("execution +{}", score), RED, 24.0)
+            }
+            KillBonusKind::Graze => (format!("graze +{}", score), LIGHT_SKY_BLUE, 14.0),
+        };
+        commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: size,
+                        color: Color::from(color),
+                    },
+                ),
+                transform: Transform::from_translation(event.position.extend(10.0)),
+                ..default()
+            })
+            .insert(BonusPopup { age: 0.0 });
+    }
+}
+
+pub fn animate_bonus_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut popup_query: Query<(Entity, &mut BonusPopup, &mut Transform, &mut Text)>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    for (entity, mut popup, mut transform, mut text) in &mut popup_query {
+        popup.age += dt;
+        if popup.age >= POPUP_SECONDS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += POPUP_RISE / POPUP_SECONDS * dt;
+        let alpha = 1.0 - popup.age / POPUP_SECONDS;
+        for section in &mut text.sections {
+            section.style.color.set_alpha(alpha);
+        }
+    }
+}
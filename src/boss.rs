@@ -0,0 +1,151 @@
+use bevy::color::palettes::css::RED;
+use bevy::prelude::*;
+
+use crate::enemies::EnemyDefinitions;
+use crate::{AssetHandles, Enemy, FontName};
+
+/// Seconds of the introduction: the camera on the boss, its name up and
+/// no damage taken.
+const BOSS_INTRO_SECONDS: f32 = 2.5;
+/// Regular spawns resume once the boss drops under this fraction of its hp.
+const BOSS_PHASE_THRESHOLD: f32 = 0.5;
+/// Share of the way to its target the camera covers per second.
+const CAMERA_PAN_RATE: f32 = 4.0;
+
+/// The boss being fought, the spawner holds the waves while it's locked.
+#[derive(Default, Resource)]
+pub struct BossEncounter {
+    pub boss: Option<Entity>,
+    /// Seconds left of the introduction.
+    pub intro: f32,
+    pub locked: bool,
+}
+
+#[derive(Component)]
+pub struct BossBanner;
+
+pub fn setup_boss_encounter(
+    mut commands: Commands,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    commands.insert_resource(BossEncounter::default());
+    // a run that ended during an introduction left the camera on the boss
+    for mut transform in &mut camera_query {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
+
+pub fn start_boss_encounter(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    mut encounter: ResMut<BossEncounter>,
+    mut enemy_query: Query<(Entity, &mut Enemy), Added<Enemy>>,
+) {
+    for (entity, mut enemy) in &mut enemy_query {
+        let definition = definitions.get(enemy.enemy_id);
+        if !definition.boss || encounter.boss.is_some() {
+            continue;
+        }
+        enemy.invulnerable = true;
+        *encounter = BossEncounter {
+            boss: Some(entity),
+            intro: BOSS_INTRO_SECONDS,
+            locked: true,
+        };
+
+        let font = handles
+            .fonts
+            .get(&FontName::IosevkaRegular)
+            .unwrap()
+            .clone_weak();
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(160.0),
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::NONE.into(),
+                ..default()
+            })
+            .insert(BossBanner)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "warning",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        color: Color::from(RED),
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(
+                    definition.name.clone(),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 72.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+    }
+}
+
+/// Ends the introduction, and lifts the lock at the phase threshold or
+/// when the boss is gone.
+pub fn update_boss_encounter(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut encounter: ResMut<BossEncounter>,
+    mut enemy_query: Query<&mut Enemy>,
+    banner_query: Query<Entity, With<BossBanner>>,
+) {
+    let boss = match encounter.boss {
+        Some(boss) => boss,
+        None => return,
+    };
+    let was_intro = encounter.intro > 0.0;
+    encounter.intro = (encounter.intro - time.delta_seconds()).max(0.0);
+    let intro_over = was_intro && encounter.intro <= 0.0;
+
+    match enemy_query.get_mut(boss) {
+        Ok(mut enemy) => {
+            if intro_over {
+                enemy.invulnerable = false;
+            }
+            if enemy.hp < enemy.max_hp * BOSS_PHASE_THRESHOLD {
+                encounter.locked = false;
+            }
+        }
+        Err(_) => *encounter = BossEncounter::default(),
+    }
+    if intro_over || encounter.boss.is_none() {
+        for entity in &banner_query {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Eases the camera to the boss during its introduction, and back.
+pub fn pan_camera(
+    time: Res<Time>,
+    encounter: Option<Res<BossEncounter>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Camera2d>)>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    let target = encounter
+        .filter(|encounter| encounter.intro > 0.0)
+        .and_then(|encounter| encounter.boss)
+        .and_then(|boss| enemy_query.get(boss).ok())
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    let step = (CAMERA_PAN_RATE * time.delta_seconds()).min(1.0);
+    for mut transform in &mut camera_query {
+        let position = transform.translation.truncate().lerp(target, step);
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::*;
+
+use bevy::color::palettes::css::{GRAY, YELLOW};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+
+use crate::conditions::{RunClock, RunModifiers};
+use crate::feed::FeedMessage;
+use crate::level::{CurrentLevel, Levels};
+use crate::run_link::RunLink;
+use crate::settings::Settings;
+use crate::spawner::RunSeed;
+use crate::{AssetHandles, FontName, Spawner, WaveEnded};
+
+/// Lines of the event log kept for the reports.
+const REPORT_LOG_SIZE: usize = 100;
+/// Browsers and github reject longer urls, the oldest log lines go first.
+#[cfg(target_arch = "wasm32")]
+const ISSUE_URL_MAX: usize = 7000;
+#[cfg(target_arch = "wasm32")]
+const ISSUE_URL: &str = "https://github.com/jacopograndi/planet/issues/new";
+
+/// Recent feed messages and wave ends, attached to the bug reports.
+#[derive(Default, Resource)]
+pub struct ReportLog {
+    pub lines: VecDeque<String>,
+}
+
+/// A report being filed: F10 grabs the screenshot, then the form opens.
+#[derive(Default, Resource)]
+pub struct BugReportForm {
+    pub requested: bool,
+    pub screenshot: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+#[derive(Component)]
+pub struct UiBugReport;
+
+#[derive(Serialize, Clone)]
+pub struct BugReport {
+    pub version: String,
+    pub level: String,
+    pub wave: Option<usize>,
+    pub seconds: Option<f32>,
+    pub seed: Option<u64>,
+    /// Same query as the run link, it sets up the run again.
+    pub run: Option<String>,
+    pub settings: Settings,
+    pub log: Vec<String>,
+}
+
+impl BugReport {
+    fn summary(&self) -> String {
+        format!(
+            "{} wave {} seed {}",
+            self.level,
+            self.wave
+                .map_or("-".to_string(), |wave| (wave + 1).to_string()),
+            self.seed.map_or("-".to_string(), |seed| seed.to_string()),
+        )
+    }
+}
+
+pub fn log_report_events(
+    clock: Option<Res<RunClock>>,
+    mut log: ResMut<ReportLog>,
+    mut feed_events: EventReader<FeedMessage>,
+    mut wave_events: EventReader<WaveEnded>,
+) {
+    let seconds = clock.map_or(0.0, |clock| clock.seconds);
+    let lines = feed_events.read().map(|event| event.text.clone()).chain(
+        wave_events
+            .read()
+            .map(|event| format!("wave {} ended, {} kills", event.wave + 1, event.kills)),
+    );
+    for line in lines {
+        log.lines.push_back(format!("{:>7.1}s {}", seconds, line));
+        if log.lines.len() > REPORT_LOG_SIZE {
+            log.lines.pop_front();
+        }
+    }
+}
+
+/// F10 takes a screenshot of the current frame, before the form covers it.
+pub fn request_bug_report(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut form: ResMut<BugReportForm>,
+    screenshot_manager: Option<ResMut<ScreenshotManager>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+) {
+    if form.requested || !keyboard_input.just_pressed(KeyCode::F10) {
+        return;
+    }
+    form.requested = true;
+    let slot = form.screenshot.clone();
+    let taken = match (screenshot_manager, window_query.get_single()) {
+        (Some(mut manager), Ok(window)) => manager
+            .take_screenshot(window, move |image| {
+                *slot.lock().unwrap() = Some(encode_png(image).unwrap_or_default());
+            })
+            .is_ok(),
+        _ => false,
+    };
+    if !taken {
+        // the form opens anyway, without the screenshot
+        *form.screenshot.lock().unwrap() = Some(vec![]);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_png(image: Image) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    image
+        .try_into_dynamic()
+        .ok()?
+        .to_rgba8()
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .ok()?;
+    Some(bytes)
+}
+
+/// The issue url can't carry the screenshot.
+#[cfg(target_arch = "wasm32")]
+fn encode_png(_image: Image) -> Option<Vec<u8>> {
+    None
+}
+
+pub fn show_bug_report_form(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    form: Res<BugReportForm>,
+    report: BugReportParams,
+    form_query: Query<(), With<UiBugReport>>,
+) {
+    if !form.requested || !form_query.is_empty() || form.screenshot.lock().unwrap().is_none() {
+        return;
+    }
+    let report = report.collect();
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let send = if cfg!(target_arch = "wasm32") {
+        "[enter] open a github issue [f10] cancel"
+    } else {
+        "[enter] save the report [f10] cancel"
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+            z_index: ZIndex::Global(50),
+            ..default()
+        })
+        .insert(UiBugReport)
+        .with_children(|parent| {
+            for (line, size, color) in [
+                ("bug report".to_string(), 64.0, Color::from(YELLOW)),
+                (report.summary(), 32.0, Color::WHITE),
+                (
+                    format!(
+                        "screenshot, settings and the last {} events attached",
+                        report.log.len()
+                    ),
+                    24.0,
+                    Color::from(GRAY),
+                ),
+                (send.to_string(), 32.0, Color::WHITE),
+            ] {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: size,
+                        color,
+                    },
+                ));
+            }
+        });
+}
+
+pub fn bug_report_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut form: ResMut<BugReportForm>,
+    report: BugReportParams,
+    form_query: Query<Entity, With<UiBugReport>>,
+) {
+    let form_entity = match form_query.get_single() {
+        Ok(entity) => entity,
+        Err(_) => return,
+    };
+    let send = keyboard_input.just_pressed(KeyCode::Enter);
+    if !send && !keyboard_input.just_pressed(KeyCode::F10) {
+        return;
+    }
+    let screenshot = form.screenshot.lock().unwrap().take().unwrap_or_default();
+    if send {
+        submit(&report.collect(), screenshot);
+    }
+    form.requested = false;
+    commands.entity(form_entity).despawn_recursive();
+}
+
+/// Everything a report is made of, some of it only exists during a run.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct BugReportParams<'w, 's> {
+    settings: Res<'w, Settings>,
+    log: Res<'w, ReportLog>,
+    levels: Res<'w, Levels>,
+    current_level: Res<'w, CurrentLevel>,
+    modifiers: Res<'w, RunModifiers>,
+    seed: Option<Res<'w, RunSeed>>,
+    clock: Option<Res<'w, RunClock>>,
+    spawner_query: Query<'w, 's, &'static Spawner>,
+}
+
+impl BugReportParams<'_, '_> {
+    fn collect(&self) -> BugReport {
+        let level = &self.levels.list[self.current_level.0].name;
+        let seed = self.seed.as_ref().map(|seed| seed.0);
+        BugReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            level: level.clone(),
+            wave: self
+                .spawner_query
+                .get_single()
+                .ok()
+                .map(|spawner| spawner.current_wave),
+            seconds: self.clock.as_ref().map(|clock| clock.seconds),
+            seed,
+            run: seed.map(|seed| RunLink::from_run(level, seed, &self.modifiers).query()),
+            settings: self.settings.clone(),
+            log: self.log.lines.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Writes reports/report-<time>.zip with the report, the log and the screenshot.
+#[cfg(not(target_arch = "wasm32"))]
+fn submit(report: &BugReport, screenshot: Vec<u8>) {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = format!("reports/report-{}.zip", time);
+    let written = (|| -> zip::result::ZipResult<()> {
+        std::fs::create_dir_all("reports")?;
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path)?);
+        let options = SimpleFileOptions::default();
+        zip.start_file("report.json", options)?;
+        zip.write_all(
+            serde_json::to_string_pretty(report)
+                .unwrap_or_default()
+                .as_bytes(),
+        )?;
+        zip.start_file("events.log", options)?;
+        zip.write_all(report.log.join("\n").as_bytes())?;
+        if !screenshot.is_empty() {
+            zip.start_file("screenshot.png", options)?;
+            zip.write_all(&screenshot)?;
+        }
+        zip.finish()?;
+        Ok(())
+    })();
+    match written {
+        Ok(()) => info!("bug report saved to {}", path),
+        Err(err) => warn!("failed to save the bug report: {}", err),
+    }
+}
+
+/// Opens a new github issue with the report in its body, the screenshot
+/// can't go through a url and is left for the player to attach.
+#[cfg(target_arch = "wasm32")]
+fn submit(report: &BugReport, _screenshot: Vec<u8>) {
+    let mut report = report.clone();
+    let url = loop {
+        let body = format!(
+            "**describe the problem here**\n\n```json\n{}\n```",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+        let url = format!(
+            "{}?title={}&body={}",
+            ISSUE_URL,
+            percent_encode(&format!("bug: {}", report.summary())),
+            percent_encode(&body)
+        );
+        if url.len() <= ISSUE_URL_MAX || report.log.is_empty() {
+            break url;
+        }
+        report.log.remove(0);
+    };
+    let opened = web_sys::window().map_or(false, |window| {
+        window.open_with_url_and_target(&url, "_blank").is_ok()
+    });
+    if !opened {
+        warn!("failed to open the bug report");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::{AssetHandles, FontName, TimeScale};
+
+/// Pace of the world while the bullet time is held.
+const BULLET_TIME_SCALE: f32 = 0.3;
+/// Meter spent per real second of bullet time.
+const METER_DRAIN: f32 = 0.35;
+/// Meter regained per real second while released.
+const METER_REGEN: f32 = 0.1;
+/// Meter needed to start, so that a drained meter can't flicker it on.
+const METER_TO_START: f32 = 0.2;
+
+/// Hold [v] to slow the world down while the players keep their pace, for
+/// as long as the meter lasts.
+#[derive(Resource)]
+pub struct BulletTime {
+    /// From 0.0 to 1.0.
+    pub meter: f32,
+    pub active: bool,
+}
+
+impl Default for BulletTime {
+    fn default() -> Self {
+        BulletTime {
+            meter: 1.0,
+            active: false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct UiBulletTimeMeter;
+
+pub fn setup_bullet_time(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    commands.insert_resource(BulletTime::default());
+    time_scale.bullet_time = 1.0;
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(74.0),
+                left: Val::Px(15.0),
+                width: Val::Px(200.0),
+                height: Val::Px(8.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.3, 0.7, 1.0).into(),
+                    ..default()
+                })
+                .insert(UiBulletTimeMeter);
+            parent.spawn(
+                TextBundle::from_section(
+                    "[v]",
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(-32.0),
+                    top: Val::Px(-4.0),
+                    ..default()
+                }),
+            );
+        });
+}
+
+/// Runs on real time, like the celebration, the slow down must not slow
+/// its own meter.
+pub fn update_bullet_time(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut bullet_time: ResMut<BulletTime>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    let dt = time.delta_seconds();
+    let held = keyboard_input.pressed(KeyCode::KeyV);
+    bullet_time.active = held
+        && bullet_time.meter > 0.0
+        && (bullet_time.active || bullet_time.meter >= METER_TO_START);
+    bullet_time.meter = if bullet_time.active {
+        (bullet_time.meter - METER_DRAIN * dt).max(0.0)
+    } else {
+        (bullet_time.meter + METER_REGEN * dt).min(1.0)
+    };
+    time_scale.bullet_time = if bullet_time.active {
+        BULLET_TIME_SCALE
+    } else {
+        1.0
+    };
+}
+
+pub fn update_ui_bullet_time(
+    bullet_time: Res<BulletTime>,
+    mut meter_query: Query<(&mut Style, &mut BackgroundColor), With<UiBulletTimeMeter>>,
+) {
+    if let Ok((mut style, mut color)) = meter_query.get_single_mut() {
+        style.width = Val::Percent(bullet_time.meter * 100.0);
+        *color = if bullet_time.meter >= METER_TO_START {
+            Color::srgb(0.3, 0.7, 1.0).into()
+        } else {
+            Color::srgb(0.15, 0.3, 0.5).into()
+        };
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::audio::PlaySound;
+use crate::conditions::Persistent;
+use crate::settings::Settings;
+use crate::{AssetHandles, FontName};
+
+const CAPTION_MAX_ENTRIES: usize = 3;
+const CAPTION_LIFETIME: f32 = 4.0;
+const CAPTION_FADE: f32 = 1.0;
+
+/// Area above the bottom of the screen where the audio cues are written
+/// out, kept across runs like the music.
+#[derive(Component)]
+pub struct CaptionRoot;
+
+#[derive(Component)]
+pub struct CaptionEntry {
+    pub age: f32,
+}
+
+pub fn setup_captions(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(110.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(CaptionRoot)
+        .insert(Persistent);
+}
+
+/// Captions come with the sounds, so they show whenever the cue plays.
+pub fn push_captions(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    settings: Res<Settings>,
+    mut sound_events: EventReader<PlaySound>,
+    root_query: Query<Entity, With<CaptionRoot>>,
+    entry_query: Query<(Entity, &CaptionEntry)>,
+) {
+    let root = match root_query.get_single() {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+
+    let mut entries: Vec<(Entity, f32)> = entry_query
+        .iter()
+        .map(|(entity, entry)| (entity, entry.age))
+        .collect();
+
+    for caption in sound_events.read().filter_map(|event| event.caption) {
+        if !settings.captions {
+            continue;
+        }
+        let entry = commands
+            .spawn(
+                TextBundle::from_section(
+                    format!("[{}]", caption),
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            )
+            .insert(CaptionEntry { age: 0.0 })
+            .id();
+        commands.entity(root).add_child(entry);
+        entries.push((entry, 0.0));
+    }
+
+    // drop the oldest entries above the limit
+    if entries.len() > CAPTION_MAX_ENTRIES {
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (entity, _) in entries.iter().take(entries.len() - CAPTION_MAX_ENTRIES) {
+            commands.entity(*entity).despawn_recursive();
+        }
+    }
+}
+
+/// Real time, captions stay readable through the slow motions.
+pub fn fade_captions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entry_query: Query<(Entity, &mut CaptionEntry, &mut Text, &mut BackgroundColor)>,
+) {
+    for (entity, mut entry, mut text, mut background) in &mut entry_query {
+        entry.age += time.delta_seconds();
+        let alpha = ((CAPTION_LIFETIME - entry.age) / CAPTION_FADE).clamp(0.0, 1.0);
+        text.sections[0].style.color.set_alpha(alpha);
+        background.0.set_alpha(alpha * 0.7);
+        if entry.age >= CAPTION_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::color::palettes::css::YELLOW;
+use bevy::prelude::*;
+
+use crate::{AssetHandles, CameraZoom, FontName, TimeScale, WaveEnded};
+
+const CELEBRATION_SECONDS: f32 = 2.5;
+const SLOWMO_SECONDS: f32 = 0.8;
+const SLOWMO_SCALE: f32 = 0.3;
+const ZOOM_PUNCH: f32 = 0.1;
+const TALLY_SECONDS: f32 = 1.5;
+
+#[derive(Default, Resource)]
+pub struct WaveCelebration {
+    pub elapsed: f32,
+    pub active: bool,
+    pub wave: usize,
+    pub scrap: u32,
+}
+
+#[derive(Component)]
+pub struct UiWaveBanner;
+
+pub fn setup_celebration(mut commands: Commands, handles: Res<AssetHandles>) {
+    commands.insert_resource(WaveCelebration::default());
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+
+    commands
+        .spawn(
+            TextBundle::from_sections([
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 64.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: 40.0,
+                        color: Color::from(YELLOW),
+                    },
+                ),
+            ])
+            .with_text_justify(JustifyText::Center)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                left: Val::Percent(30.0),
+                ..default()
+            }),
+        )
+        .insert(UiWaveBanner);
+}
+
+pub fn start_celebration(
+    mut celebration: ResMut<WaveCelebration>,
+    mut wave_events: EventReader<WaveEnded>,
+) {
+    for event in wave_events.read() {
+        *celebration = WaveCelebration {
+            elapsed: 0.0,
+            active: true,
+            wave: event.wave,
+            scrap: event.scrap,
+        };
+    }
+}
+
+/// Runs on real time, the slow-mo it applies must not slow itself down.
+pub fn update_celebration(
+    time: Res<Time>,
+    mut celebration: ResMut<WaveCelebration>,
+    mut time_scale: ResMut<TimeScale>,
+    mut zoom: ResMut<CameraZoom>,
+    mut text_query: Query<&mut Text, With<UiWaveBanner>>,
+) {
+    if !celebration.active {
+        return;
+    }
+
+    celebration.elapsed += time.delta_seconds();
+    let t = celebration.elapsed;
+
+    time_scale.scale = if t < SLOWMO_SECONDS {
+        SLOWMO_SCALE
+    } else {
+        let ramp = ((t - SLOWMO_SECONDS) / SLOWMO_SECONDS).min(1.0);
+        SLOWMO_SCALE + (1.0 - SLOWMO_SCALE) * ramp
+    };
+
+    // zoom in quickly, then ease back out
+    let punch = if t < 0.1 {
+        t / 0.1
+    } else {
+        (1.0 - (t - 0.1) / 0.6).max(0.0)
+    };
+    zoom.0 = 1.0 - ZOOM_PUNCH * punch;
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        let tally = (celebration.scrap as f32 * (t / TALLY_SECONDS).min(1.0)) as u32;
+        text.sections[0].value = format!("wave {} cleared\n", celebration.wave + 1);
+        text.sections[1].value = format!("+{} scrap", tally);
+    }
+
+    if t >= CELEBRATION_SECONDS {
+        celebration.active = false;
+        time_scale.scale = 1.0;
+        zoom.0 = 1.0;
+        if let Ok(mut text) = text_query.get_single_mut() {
+            text.sections[0].value.clear();
+            text.sections[1].value.clear();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use serde::*;
+
+use bevy::color::palettes::css::CYAN;
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::feed::FeedMessage;
+use crate::level::{CurrentLevel, Levels};
+use crate::storage;
+use crate::WaveEnded;
+
+/// Waves between two checkpoints.
+pub const CHECKPOINT_INTERVAL: usize = 10;
+pub const MAX_CONTINUES: u32 = 2;
+/// Fraction of the score kept for each continue used.
+const CONTINUE_SCORE_FACTOR: f32 = 0.75;
+
+/// Last checkpoint the run went past, a game over can continue from it.
+#[derive(Serialize, Deserialize, Clone, Default, Resource)]
+pub struct RunCheckpoint {
+    pub level: String,
+    /// Wave the run continues from, zero before the first checkpoint.
+    pub wave: usize,
+    pub continues: u32,
+}
+
+impl RunCheckpoint {
+    pub fn save(&self) {
+        if !storage::save("checkpoint", self) {
+            warn!("failed to save the checkpoint");
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("checkpoint {}", self.wave / CHECKPOINT_INTERVAL)
+    }
+
+    pub fn can_continue(&self) -> bool {
+        self.wave > 0 && self.continues < MAX_CONTINUES
+    }
+
+    pub fn score_multiplier(&self) -> f32 {
+        CONTINUE_SCORE_FACTOR.powi(self.continues as i32)
+    }
+
+    /// The checkpoint a continue resumes from.
+    pub fn continued(&self) -> RunCheckpoint {
+        RunCheckpoint {
+            continues: self.continues + 1,
+            ..self.clone()
+        }
+    }
+}
+
+/// Starts from the checkpoint being continued, or from a fresh one.
+pub fn setup_checkpoint(
+    mut commands: Commands,
+    mut modifiers: ResMut<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+) {
+    let checkpoint = match modifiers.resume.take() {
+        Some(checkpoint) => checkpoint,
+        None => RunCheckpoint {
+            level: levels.list[current_level.0].name.clone(),
+            ..default()
+        },
+    };
+    if modifiers.practice.is_none() && !modifiers.sandbox {
+        checkpoint.save();
+    }
+    commands.insert_resource(checkpoint);
+}
+
+pub fn reach_checkpoint(
+    modifiers: Res<RunModifiers>,
+    mut checkpoint: ResMut<RunCheckpoint>,
+    mut wave_events: EventReader<WaveEnded>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    for event in wave_events.read() {
+        let next = event.wave + 1;
+        if next % CHECKPOINT_INTERVAL != 0 || next <= checkpoint.wave {
+            continue;
+        }
+        // practice runs already pick their wave
+        if modifiers.practice.is_some() || modifiers.sandbox {
+            continue;
+        }
+        checkpoint.wave = next;
+        checkpoint.save();
+        feed.send(FeedMessage::new(
+            format!("{} reached", checkpoint.name()),
+            Color::from(CYAN),
+        ));
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use serde::*;
+
+use bevy::color::palettes::css::{GRAY, YELLOW};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::balance::{Balance, WeaponBalance};
+use crate::conditions::RunModifiers;
+use crate::enemies::EnemyDefinitions;
+use crate::feed::FeedMessage;
+use crate::party::PARTY_MAX;
+use crate::profile::Profile;
+use crate::satellite::Satellite;
+use crate::ultimate::Ultimate;
+use crate::{AssetHandles, Enemy, FontName};
+
+// embedded for the same reason as the level files
+const CODEX_FILE: &str = include_str!("../assets/codex.json");
+
+/// Lore text of the codex entries, by entry key.
+#[derive(Serialize, Deserialize, Resource)]
+pub struct CodexLore {
+    pub lore: HashMap<String, String>,
+}
+
+impl Default for CodexLore {
+    fn default() -> Self {
+        serde_json::from_str(CODEX_FILE).expect("invalid codex file")
+    }
+}
+
+pub struct CodexEntry {
+    /// Recorded in the profile once the entry is discovered.
+    pub key: String,
+    pub name: String,
+    pub stats: String,
+}
+
+impl CodexEntry {
+    fn new(key: impl Into<String>, name: impl Into<String>, stats: impl Into<String>) -> Self {
+        CodexEntry {
+            key: key.into(),
+            name: name.into(),
+            stats: stats.into(),
+        }
+    }
+}
+
+pub fn enemy_key(name: &str) -> String {
+    format!("enemy/{}", name)
+}
+
+/// Every entry of the codex by section, with the stats of the current
+/// balance.
+pub fn codex_sections(
+    definitions: &EnemyDefinitions,
+    balance: &Balance,
+) -> [(&'static str, Vec<CodexEntry>); 3] {
+    let enemies = definitions
+        .list
+        .iter()
+        .map(|definition| {
+            let mut stats = format!(
+                "hp {} speed {} damage {} scrap {}",
+                definition.hp, definition.speed, definition.damage, definition.scrap
+            );
+            if definition.shield > 0.0 {
+                stats += &format!(" shield {}", definition.shield);
+            }
+            if definition.elite {
+                stats += " elite";
+            }
+            CodexEntry::new(enemy_key(&definition.name), &definition.name, stats)
+        })
+        .collect();
+
+    let weapon_stats = |weapon: &WeaponBalance| {
+        let mut stats = format!("range {} falloff {}", weapon.range, weapon.falloff);
+        if let Some(zone) = &weapon.zone {
+            stats += &format!(" zone {}/tick", zone.damage_per_tick);
+        }
+        stats
+    };
+    let weapons = vec![
+        CodexEntry::new(
+            "weapon/autocannon",
+            "autocannon",
+            weapon_stats(&balance.weapons.player),
+        ),
+        CodexEntry::new(
+            "weapon/satellite",
+            "satellite",
+            weapon_stats(&balance.weapons.satellite),
+        ),
+        CodexEntry::new(
+            "weapon/orbital_laser",
+            "orbital laser",
+            format!(
+                "{}/s over {}s",
+                balance.ultimate.damage_per_second, balance.ultimate.sweep_seconds
+            ),
+        ),
+    ];
+
+    let modifiers = vec![
+        CodexEntry::new("modifier/ng_plus", "new game+", "enemy hp +50% per loop"),
+        CodexEntry::new("modifier/endless", "endless", "the waves never run out"),
+        CodexEntry::new("modifier/speedrun", "speedrun", "no intermissions"),
+        CodexEntry::new("modifier/sandbox", "sandbox", "spawn anything by hand"),
+        CodexEntry::new(
+            "modifier/party",
+            "party",
+            format!("up to {} players", PARTY_MAX),
+        ),
+    ];
+
+    [
+        ("enemies", enemies),
+        ("weapons", weapons),
+        ("modifiers", modifiers),
+    ]
+}
+
+/// Adds the entries to the profile, reporting the new ones in the feed.
+/// Practice runs don't unlock anything.
+fn discover(
+    keys: impl IntoIterator<Item = String>,
+    modifiers: &RunModifiers,
+    profile: &mut Profile,
+    feed: &mut EventWriter<FeedMessage>,
+) {
+    if modifiers.practice.is_some() {
+        return;
+    }
+    let mut changed = false;
+    for key in keys {
+        if profile.discover(&key) {
+            let name = key.split('/').last().unwrap_or(&key).replace('_', " ");
+            feed.send(FeedMessage::new(
+                format!("codex: {} added", name),
+                Color::from(YELLOW),
+            ));
+            changed = true;
+        }
+    }
+    if changed {
+        profile.save();
+    }
+}
+
+/// The weapon and the modifiers a run starts with.
+pub fn discover_run_codex(
+    modifiers: Res<RunModifiers>,
+    mut profile: ResMut<Profile>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    let mut keys = vec!["weapon/autocannon".to_string()];
+    for (key, active) in [
+        ("modifier/ng_plus", modifiers.ng_plus > 0),
+        ("modifier/endless", modifiers.endless),
+        ("modifier/speedrun", modifiers.speedrun),
+        ("modifier/sandbox", modifiers.sandbox),
+        ("modifier/party", modifiers.party.players > 1),
+    ] {
+        if active {
+            keys.push(key.to_string());
+        }
+    }
+    discover(keys, &modifiers, &mut profile, &mut feed);
+}
+
+/// Enemies and weapons the first time they show up in a run.
+pub fn discover_codex(
+    definitions: Res<EnemyDefinitions>,
+    modifiers: Res<RunModifiers>,
+    ultimate: Res<Ultimate>,
+    mut profile: ResMut<Profile>,
+    mut feed: EventWriter<FeedMessage>,
+    enemy_query: Query<&Enemy, Added<Enemy>>,
+    satellite_query: Query<(), Added<Satellite>>,
+) {
+    let mut keys: Vec<String> = enemy_query
+        .iter()
+        .map(|enemy| enemy_key(&definitions.get(enemy.enemy_id).name))
+        .collect();
+    if !satellite_query.is_empty() {
+        keys.push("weapon/satellite".to_string());
+    }
+    if ultimate.sweep.is_some() {
+        keys.push("weapon/orbital_laser".to_string());
+    }
+    discover(keys, &modifiers, &mut profile, &mut feed);
+}
+
+#[derive(Component)]
+pub struct CodexButton;
+
+/// Shown over the menu, clicking anywhere closes it.
+#[derive(Component)]
+pub struct CodexScreen;
+
+pub fn codex_interaction(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    balance: Res<Balance>,
+    lore: Res<CodexLore>,
+    profile: Res<Profile>,
+    open_query: Query<&Interaction, (Changed<Interaction>, With<CodexButton>)>,
+    close_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<CodexScreen>)>,
+) {
+    for (entity, interaction) in &close_query {
+        if *interaction == Interaction::Pressed {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    if !open_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+
+    let sections = codex_sections(&definitions, &balance);
+    let total: usize = sections.iter().map(|(_, entries)| entries.len()).sum();
+    let found: usize = sections
+        .iter()
+        .flat_map(|(_, entries)| entries)
+        .filter(|entry| profile.discovered(&entry.key))
+        .count();
+
+    commands
+        .spawn(ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.95).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(CodexScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_sections([
+                TextSection::new("codex ", style(64.0, Color::WHITE)),
+                TextSection::new(
+                    format!("{}/{} discovered", found, total),
+                    style(24.0, Color::from(GRAY)),
+                ),
+            ]));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        column_gap: Val::Px(40.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (title, entries) in &sections {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(360.0),
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(12.0),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    *title,
+                                    style(32.0, Color::from(YELLOW)),
+                                ));
+                                for entry in entries {
+                                    let text = if profile.discovered(&entry.key) {
+                                        TextBundle::from_sections([
+                                            TextSection::new(
+                                                format!("{}\n", entry.name),
+                                                style(24.0, Color::WHITE),
+                                            ),
+                                            TextSection::new(
+                                                format!("{}\n", entry.stats),
+                                                style(16.0, Color::from(GRAY)),
+                                            ),
+                                            TextSection::new(
+                                                lore.lore
+                                                    .get(&entry.key)
+                                                    .cloned()
+                                                    .unwrap_or_default(),
+                                                style(16.0, Color::WHITE),
+                                            ),
+                                        ])
+                                    } else {
+                                        TextBundle::from_section(
+                                            "???",
+                                            style(24.0, Color::from(GRAY)),
+                                        )
+                                    };
+                                    parent.spawn(text);
+                                }
+                            });
+                    }
+                });
+        });
+}
+
+pub fn cleanup_codex(mut commands: Commands, codex_query: Query<Entity, With<CodexScreen>>) {
+    for entity in &codex_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,139 @@
+//! Challenge files without the game: parsing, validation and a rough
+//! difficulty estimate, for tools that generate or check waves.
+
+pub use crate::enemies::{EnemyDefinition, EnemyDefinitions};
+pub use crate::spawner::{
+    pick_enemy, Challenge, ChallengeFile, Reinforcements, ReinforcementsFile, ScriptAction,
+    ScriptedEvent, SpawnAt, SpawnBand, SpawnBlock, SpawnWeight, Wave, WaveFile,
+};
+
+/// Parses a challenge file and checks every spawn against the enemy
+/// definitions, returning all the problems found.
+pub fn validate(json: &str, enemies: &EnemyDefinitions) -> Result<Challenge, Vec<String>> {
+    let challenge = Challenge::from_json(json).map_err(|err| vec![err.to_string()])?;
+    let mut errors = vec![];
+    if challenge.waves.is_empty() {
+        errors.push("the challenge has no waves".to_string());
+    }
+    for (w, wave) in challenge.waves.iter().enumerate() {
+        let reinforcements = wave.reinforcements.iter().flat_map(|r| &r.spawns);
+        for (s, spawn) in wave.spawns.iter().chain(reinforcements).enumerate() {
+            let at = format!("wave {} spawn {}", w + 1, s + 1);
+            if spawn.enemy_id as usize >= enemies.list.len() {
+                errors.push(format!("{}: unknown enemy {}", at, spawn.enemy_id));
+            }
+            if !spawn.cooldown.is_finite() || spawn.cooldown < 0.0 {
+                errors.push(format!("{}: invalid cooldown {}", at, spawn.cooldown));
+            }
+            if spawn.angle.is_some_and(|angle| !angle.is_finite()) {
+                errors.push(format!("{}: invalid angle", at));
+            }
+        }
+        if let Some(reinforcements) = &wave.reinforcements {
+            if !(reinforcements.retreat_at > 0.0 && reinforcements.retreat_at <= 1.0) {
+                errors.push(format!(
+                    "wave {}: retreat_at {} is not a share of the wave",
+                    w + 1,
+                    reinforcements.retreat_at
+                ));
+            }
+        }
+    }
+    for event in &challenge.events {
+        let at = format!("event at wave {}", event.wave);
+        if event.wave >= challenge.waves.len() {
+            errors.push(format!("{}: the challenge has no such wave", at));
+        }
+        for action in &event.actions {
+            if let ScriptAction::Spawn { enemy_id, .. } = action {
+                if *enemy_id as usize >= enemies.list.len() {
+                    errors.push(format!("{}: unknown enemy {}", at, enemy_id));
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(challenge)
+    } else {
+        Err(errors)
+    }
+}
+
+/// What the defences have to deal with in a wave.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveDifficulty {
+    pub enemies: usize,
+    /// Hp and shields of all the enemies.
+    pub hp: f32,
+    /// Damage to the planet if every enemy gets through.
+    pub damage: f32,
+    /// From the first spawn to the last.
+    pub seconds: f32,
+}
+
+impl WaveDifficulty {
+    /// Hp arriving per second, spawns closer together are harder to
+    /// clear than the same enemies spread out.
+    pub fn pressure(&self) -> f32 {
+        self.hp / self.seconds.max(1.0)
+    }
+}
+
+pub fn estimate(challenge: &Challenge, enemies: &EnemyDefinitions) -> Vec<WaveDifficulty> {
+    challenge
+        .waves
+        .iter()
+        .map(|wave| {
+            let mut difficulty = WaveDifficulty {
+                enemies: wave.spawns.len(),
+                hp: 0.0,
+                damage: 0.0,
+                seconds: 0.0,
+            };
+            for (s, spawn) in wave.spawns.iter().enumerate() {
+                let definition = enemies.get(spawn.enemy_id);
+                difficulty.hp += definition.hp + definition.shield;
+                difficulty.damage += definition.damage;
+                // the cooldown of the last spawn only delays the wave end
+                if s + 1 < wave.spawns.len() {
+                    difficulty.seconds += spawn.cooldown / 1000.0;
+                }
+            }
+            // counted on top of the whole wave, the retreat timing depends
+            // on how fast the wave is killed
+            for spawn in wave.reinforcements.iter().flat_map(|r| &r.spawns) {
+                let definition = enemies.get(spawn.enemy_id);
+                difficulty.enemies += 1;
+                difficulty.hp += definition.hp + definition.shield;
+                difficulty.damage += definition.damage;
+            }
+            difficulty
+        })
+        .collect()
+}
+
+/// Single number to compare challenges by, the sum of the wave pressures.
+pub fn score(challenge: &Challenge, enemies: &EnemyDefinitions) -> f32 {
+    estimate(challenge, enemies)
+        .iter()
+        .map(|wave| wave.pressure())
+        .sum()
+}
+
+/// Wave by wave table of the estimate, followed by the score.
+pub fn report(challenge: &Challenge, enemies: &EnemyDefinitions) -> String {
+    let mut report = String::from("wave enemies       hp  damage seconds pressure\n");
+    for (w, wave) in estimate(challenge, enemies).iter().enumerate() {
+        report += &format!(
+            "{:>4} {:>7} {:>8.0} {:>7.0} {:>7.1} {:>8.1}\n",
+            w + 1,
+            wave.enemies,
+            wave.hp,
+            wave.damage,
+            wave.seconds,
+            wave.pressure()
+        );
+    }
+    report += &format!("score {:.1}\n", score(challenge, enemies));
+    report
+}
@@ -0,0 +1,362 @@
+use serde::*;
+
+use bevy::color::palettes::css::{CYAN, GRAY, RED};
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::attachments::AttachmentScreen;
+use crate::checkpoints::{RunCheckpoint, MAX_CONTINUES};
+use crate::construction::BuildMenu;
+use crate::level::{CurrentLevel, Levels};
+use crate::party::PartyOptions;
+use crate::score::RunStats;
+use crate::snapshot::RunSnapshot;
+use crate::spawner::RunSeed;
+use crate::upgrades::UpgradeDraft;
+use crate::{
+    AppState, AssetHandles, CameraZoom, Challenge, FontName, Planet, RunPhase, Spawner, TimeScale,
+};
+
+/// Seconds backspace has to be held to restart the run.
+const QUICK_RESTART_HOLD: f32 = 0.8;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum VictoryCondition {
+    AllWaves,
+    SurviveWaves(usize),
+    SurviveSeconds(f32),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum DefeatCondition {
+    PlanetDestroyed,
+    PlanetHpBelow(f32),
+}
+
+impl Default for VictoryCondition {
+    fn default() -> Self {
+        VictoryCondition::AllWaves
+    }
+}
+
+/// Why the run is lost with the planet at the given hp, after the first
+/// of the conditions met. The rewind checks it too.
+pub fn defeat_reason(conditions: &[DefeatCondition], planet_hp: f32) -> Option<String> {
+    conditions.iter().find_map(|condition| match condition {
+        DefeatCondition::PlanetDestroyed if planet_hp <= 0.0 => {
+            Some("the planet was destroyed".to_string())
+        }
+        DefeatCondition::PlanetHpBelow(hp) if planet_hp < *hp => {
+            Some(format!("the planet fell below {} hp", hp))
+        }
+        _ => None,
+    })
+}
+
+pub fn default_defeat() -> Vec<DefeatCondition> {
+    vec![DefeatCondition::PlanetDestroyed]
+}
+
+/// Seconds of gameplay elapsed in the current run.
+#[derive(Default, Resource)]
+pub struct RunClock {
+    pub seconds: f32,
+    /// Run time at the end of each wave.
+    pub splits: Vec<f32>,
+}
+
+#[derive(Resource)]
+pub struct RunOutcome {
+    pub victory: bool,
+    pub reason: String,
+    pub wave: usize,
+    pub seconds: f32,
+}
+
+/// Tears down every entity of the current run, then moves to the given state.
+#[derive(Event)]
+pub struct ResetRun {
+    pub to: AppState,
+}
+
+/// Starting point of a practice run, which doesn't record scores.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PracticeLoadout {
+    pub wave: usize,
+    pub planet_hp: f32,
+    pub scrap: u32,
+    pub satellites: usize,
+}
+
+impl Default for PracticeLoadout {
+    fn default() -> Self {
+        PracticeLoadout {
+            wave: 0,
+            planet_hp: 100.0,
+            scrap: 0,
+            satellites: 0,
+        }
+    }
+}
+
+/// Tweaks carried over between consecutive runs.
+#[derive(Default, Resource)]
+pub struct RunModifiers {
+    pub ng_plus: u32,
+    pub endless: bool,
+    /// No intermissions between waves.
+    pub speedrun: bool,
+    pub practice: Option<PracticeLoadout>,
+    /// No spawner and no outcome, things are spawned by hand.
+    pub sandbox: bool,
+    /// Ships and satellites fight on their own, at reduced efficiency.
+    pub idle: bool,
+    pub party: PartyOptions,
+    /// Checkpoint the next run continues from.
+    pub resume: Option<RunCheckpoint>,
+    /// Seed of the generated waves, a fresh one every run if unset.
+    pub seed: Option<u64>,
+    /// Autosave the next run is recovered from.
+    pub restore: Option<RunSnapshot>,
+}
+
+impl RunModifiers {
+    pub fn enemy_hp_multiplier(&self) -> f32 {
+        1.0 + 0.5 * self.ng_plus as f32
+    }
+}
+
+/// Marks the entities that outlive a run, like the camera.
+#[derive(Component)]
+pub struct Persistent;
+
+#[derive(Component)]
+pub struct OutcomeScreen;
+
+pub fn setup_conditions(mut commands: Commands, mut rapier_config: ResMut<RapierConfiguration>) {
+    commands.insert_resource(RunClock::default());
+    commands.insert_resource(RunStats::default());
+    commands.remove_resource::<RunOutcome>();
+    rapier_config.physics_pipeline_active = true;
+}
+
+pub fn evaluate_conditions(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    challenge: Res<Challenge>,
+    modifiers: Res<RunModifiers>,
+    mut clock: ResMut<RunClock>,
+    mut commands: Commands,
+    mut phase: ResMut<NextState<RunPhase>>,
+    spawner_query: Query<&Spawner>,
+    planet_query: Query<&Planet>,
+) {
+    clock.seconds += time_scale.delta_seconds(&time);
+    if modifiers.sandbox {
+        return;
+    }
+
+    let level = &levels.list[current_level.0];
+    let spawner = spawner_query.single();
+    let planet = planet_query.single();
+
+    let defeat = defeat_reason(&level.defeat, planet.hp);
+
+    // a wave counts as survived as soon as its intermission starts
+    let survived = spawner.current_wave + spawner.in_intermission() as usize;
+
+    let victory = match level.victory {
+        _ if modifiers.endless => None,
+        VictoryCondition::AllWaves if survived >= challenge.waves.len() => {
+            Some("all waves survived".to_string())
+        }
+        VictoryCondition::SurviveWaves(waves) if survived >= waves => {
+            Some(format!("survived {} waves", waves))
+        }
+        VictoryCondition::SurviveSeconds(seconds) if clock.seconds >= seconds => {
+            Some(format!("survived {} seconds", seconds))
+        }
+        _ => None,
+    };
+
+    let outcome = match (defeat, victory) {
+        (Some(reason), _) => Some((false, reason, RunPhase::GameOver)),
+        (None, Some(reason)) => Some((true, reason, RunPhase::Victory)),
+        (None, None) => None,
+    };
+
+    if let Some((victory, reason, next)) = outcome {
+        commands.insert_resource(RunOutcome {
+            victory,
+            reason,
+            wave: spawner.current_wave,
+            seconds: clock.seconds,
+        });
+        phase.set(next);
+    }
+}
+
+pub fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+pub fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+pub fn setup_game_over_screen(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    outcome: Res<RunOutcome>,
+    checkpoint: Res<RunCheckpoint>,
+) {
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(OutcomeScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "game over",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 96.0,
+                    color: Color::from(RED),
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!("{}, wave {}", outcome.reason, outcome.wave + 1),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+            if checkpoint.can_continue() {
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "[c] continue from {}, wave {} (score x{:.2}, {} left)",
+                        checkpoint.name(),
+                        checkpoint.wave + 1,
+                        checkpoint.continued().score_multiplier(),
+                        MAX_CONTINUES - checkpoint.continues
+                    ),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 32.0,
+                        color: Color::from(CYAN),
+                    },
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "[r] retry [enter] menu",
+                TextStyle {
+                    font,
+                    font_size: 32.0,
+                    color: Color::from(GRAY),
+                },
+            ));
+        });
+}
+
+pub fn game_over_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    checkpoint: Res<RunCheckpoint>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut reset_events: EventWriter<ResetRun>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyC) && checkpoint.can_continue() {
+        modifiers.resume = Some(checkpoint.continued());
+        reset_events.send(ResetRun {
+            to: AppState::InGame,
+        });
+    } else if keyboard_input.just_pressed(KeyCode::Enter) {
+        reset_events.send(ResetRun { to: AppState::Menu });
+    } else if keyboard_input.just_pressed(KeyCode::KeyR) {
+        reset_events.send(ResetRun {
+            to: AppState::InGame,
+        });
+    }
+}
+
+/// Holding backspace starts the run over from the first wave, on the
+/// same seed and with the same modifiers, for grinding a seed. No control
+/// scheme binds the key, and the hold doesn't count while a screen of the
+/// intermission is open.
+pub fn quick_restart(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    seed: Res<RunSeed>,
+    draft: Res<UpgradeDraft>,
+    build_menu: Res<BuildMenu>,
+    attachment_screen: Res<AttachmentScreen>,
+    mut held: Local<f32>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut reset_events: EventWriter<ResetRun>,
+) {
+    let screen_open = draft.is_open() || build_menu.open || attachment_screen.open;
+    if screen_open || !keyboard_input.pressed(KeyCode::Backspace) {
+        *held = 0.0;
+        return;
+    }
+    *held += time.delta_seconds();
+    if *held < QUICK_RESTART_HOLD {
+        return;
+    }
+    *held = 0.0;
+    modifiers.seed = Some(seed.0);
+    modifiers.resume = None;
+    modifiers.restore = None;
+    reset_events.send(ResetRun {
+        to: AppState::InGame,
+    });
+}
+
+pub fn reset_run(
+    mut commands: Commands,
+    mut state: ResMut<NextState<AppState>>,
+    mut time_scale: ResMut<TimeScale>,
+    mut zoom: ResMut<CameraZoom>,
+    mut reset_events: EventReader<ResetRun>,
+    // entities without a transform, like the window, aren't part of a run
+    root_query: Query<Entity, (With<Transform>, Without<Parent>, Without<Persistent>)>,
+) {
+    let to = match reset_events.read().last() {
+        Some(event) => event.to,
+        None => return,
+    };
+    for entity in &root_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    time_scale.scale = 1.0;
+    time_scale.bullet_time = 1.0;
+    zoom.0 = 1.0;
+    // a run can't be entered from itself, restarting passes through another state
+    state.set(match to {
+        AppState::InGame => AppState::Restart,
+        to => to,
+    });
+}
+
+pub fn restart_run(mut state: ResMut<NextState<AppState>>) {
+    state.set(AppState::InGame);
+}
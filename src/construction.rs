@@ -0,0 +1,280 @@
+use bevy::color::palettes::css::{ORANGE, ORANGE_RED, YELLOW};
+use bevy::prelude::*;
+
+use bevy_rapier2d::{pipeline::CollisionEvent::*, prelude::*};
+
+use crate::feed::FeedMessage;
+use crate::gamepad::Rumble;
+use crate::shields::DamageKind;
+use crate::targeting::Threat;
+use crate::teams::{Role, Team};
+use crate::units::{MINE_RADIUS, WALL_HALF_HEIGHT, WALL_HALF_WIDTH};
+use crate::{AssetHandles, Enemy, FontName, Planet, Player, Spawner};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StructureKind {
+    Wall,
+    Mine,
+}
+
+impl StructureKind {
+    const ALL: [StructureKind; 2] = [StructureKind::Wall, StructureKind::Mine];
+
+    fn name(&self) -> &'static str {
+        match self {
+            StructureKind::Wall => "wall",
+            StructureKind::Mine => "mine",
+        }
+    }
+
+    fn limit(&self) -> usize {
+        match self {
+            StructureKind::Wall => 4,
+            StructureKind::Mine => 6,
+        }
+    }
+
+    /// Mines are traps, the enemies don't go after them.
+    fn threat(&self) -> f32 {
+        match self {
+            StructureKind::Wall => 1.0,
+            StructureKind::Mine => 0.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Structure {
+    pub kind: StructureKind,
+    pub hp: f32,
+}
+
+#[derive(Component)]
+pub struct BuildMenuItem(usize);
+
+#[derive(Default, Resource)]
+pub struct BuildMenu {
+    pub open: bool,
+    pub selected: usize,
+}
+
+const WALL_HP: f32 = 5.0;
+/// Reach of the explosion, the trigger is the smaller collider.
+const MINE_BLAST_RADIUS: f32 = 96.0;
+const MINE_DAMAGE: f32 = 150.0;
+const BUILD_MENU_RADIUS: f32 = 72.0;
+
+pub fn setup_construction(mut commands: Commands) {
+    commands.insert_resource(BuildMenu::default());
+}
+
+pub fn build_menu_input(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<BuildMenu>,
+    spawner_query: Query<&Spawner>,
+    player_query: Query<(&Player, &Transform)>,
+    planet_query: Query<&Planet>,
+    structure_query: Query<&Structure>,
+    item_query: Query<Entity, With<BuildMenuItem>>,
+) {
+    let intermission = spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission());
+
+    let was_open = menu.open;
+    if keyboard_input.just_pressed(KeyCode::KeyB) {
+        menu.open = !menu.open;
+    }
+    if !intermission {
+        menu.open = false;
+    }
+
+    if menu.open {
+        let options = StructureKind::ALL.len();
+        if keyboard_input.just_pressed(KeyCode::KeyQ) {
+            menu.selected = (menu.selected + options - 1) % options;
+        }
+        if keyboard_input.just_pressed(KeyCode::KeyE) {
+            menu.selected = (menu.selected + 1) % options;
+        }
+
+        if keyboard_input.just_pressed(KeyCode::Space) {
+            let kind = StructureKind::ALL[menu.selected];
+            let built = structure_query.iter().filter(|s| s.kind == kind).count();
+            // the first player of the party does the building
+            let builder = player_query.iter().find(|(player, _)| player.index == 0);
+            if let Some((_, player_trans)) = builder.filter(|_| built < kind.limit()) {
+                let planet = planet_query.single();
+                let angle = Vec2::X.angle_between(player_trans.translation.truncate());
+                let angle = if angle.is_nan() { 0.0 } else { angle };
+                spawn_structure(&mut commands, kind, angle, planet.size * 0.5 + 32.0);
+            }
+        }
+    }
+
+    if was_open != menu.open {
+        for entity in &item_query {
+            commands.entity(entity).despawn();
+        }
+        if menu.open {
+            let font = handles
+                .fonts
+                .get(&FontName::IosevkaRegular)
+                .unwrap()
+                .clone_weak();
+            for (i, kind) in StructureKind::ALL.iter().enumerate() {
+                commands
+                    .spawn(Text2dBundle {
+                        text: Text::from_section(
+                            kind.name(),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 24.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_justify(JustifyText::Center),
+                        transform: Transform::from_xyz(0.0, 0.0, 20.0),
+                        ..default()
+                    })
+                    .insert(BuildMenuItem(i));
+            }
+        }
+    }
+}
+
+/// Lays the build options out on a ring around the player.
+pub fn update_build_menu(
+    menu: Res<BuildMenu>,
+    player_query: Query<(&Player, &Transform), Without<BuildMenuItem>>,
+    structure_query: Query<&Structure>,
+    mut item_query: Query<(&BuildMenuItem, &mut Transform, &mut Text)>,
+) {
+    let player_trans = match player_query.iter().find(|(player, _)| player.index == 0) {
+        Some((_, player_trans)) => player_trans,
+        None => return,
+    };
+    let options = StructureKind::ALL.len() as f32;
+    for (item, mut transform, mut text) in &mut item_query {
+        let kind = StructureKind::ALL[item.0];
+        let angle = std::f32::consts::FRAC_PI_2
+            + (item.0 as f32 - (options - 1.0) * 0.5) * std::f32::consts::FRAC_PI_4;
+        let offset = player_trans.rotation * Vec3::new(f32::cos(angle), f32::sin(angle), 0.0);
+        transform.translation = player_trans.translation + offset * BUILD_MENU_RADIUS;
+        transform.translation.z = 20.0;
+
+        let built = structure_query.iter().filter(|s| s.kind == kind).count();
+        text.sections[0].value = format!("{} {}/{}", kind.name(), built, kind.limit());
+        text.sections[0].style.color = if item.0 == menu.selected {
+            Color::from(YELLOW)
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn spawn_structure(commands: &mut Commands, kind: StructureKind, angle: f32, radius: f32) {
+    let transform = Transform {
+        translation: Vec3::new(f32::cos(angle) * radius, f32::sin(angle) * radius, 1.5),
+        rotation: Quat::from_rotation_z(angle),
+        ..default()
+    };
+    match kind {
+        StructureKind::Wall => {
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgb(0.5, 0.5, 0.6),
+                        custom_size: Some(Vec2::new(8.0, 48.0)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(Collider::cuboid(WALL_HALF_WIDTH, WALL_HALF_HEIGHT))
+                .insert(Team::Player)
+                .insert(Team::Player.collision_groups(Role::Body))
+                .insert(Threat(kind.threat()))
+                .insert(Structure { kind, hp: WALL_HP });
+        }
+        StructureKind::Mine => {
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::from(ORANGE_RED),
+                        custom_size: Some(Vec2::new(10.0, 10.0)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(Collider::ball(MINE_RADIUS))
+                .insert(Sensor)
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(Team::Player)
+                .insert(Team::Player.collision_groups(Role::Body))
+                .insert(Threat(kind.threat()))
+                .insert(Structure { kind, hp: 1.0 });
+        }
+    }
+}
+
+pub fn structure_collisions(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut structure_query: Query<(&mut Structure, &Transform)>,
+    mut enemy_query: Query<(&mut Enemy, &Transform)>,
+    mut feed: EventWriter<FeedMessage>,
+    mut rumble_events: EventWriter<Rumble>,
+) {
+    for collision_event in collision_events.read() {
+        if let Started(ent, oth, _) = collision_event {
+            for (structure_ent, enemy_ent) in [(*ent, *oth), (*oth, *ent)] {
+                let (mut structure, structure_trans) = match structure_query.get_mut(structure_ent)
+                {
+                    Ok(structure) => structure,
+                    Err(_) => continue,
+                };
+                if structure.hp <= 0.0 {
+                    continue;
+                }
+                let damage = match enemy_query.get_mut(enemy_ent) {
+                    Ok((enemy, _)) => enemy.damage,
+                    Err(_) => continue,
+                };
+                match structure.kind {
+                    StructureKind::Wall => {
+                        structure.hp -= damage;
+                        if let Ok((mut enemy, _)) = enemy_query.get_mut(enemy_ent) {
+                            enemy.has_hit = 1;
+                        }
+                    }
+                    StructureKind::Mine => {
+                        structure.hp = 0.0;
+                        rumble_events.send(Rumble::new(0.8, 250));
+                        let center = structure_trans.translation.truncate();
+                        for (mut enemy, enemy_trans) in &mut enemy_query {
+                            let dist = enemy_trans.translation.truncate().distance(center);
+                            if dist < MINE_BLAST_RADIUS {
+                                enemy.take_damage(MINE_DAMAGE, DamageKind::Kinetic);
+                            }
+                        }
+                    }
+                }
+                if structure.hp <= 0.0 {
+                    commands.entity(structure_ent).despawn();
+                    feed.send(match structure.kind {
+                        StructureKind::Wall => {
+                            FeedMessage::new("wall destroyed", Color::from(ORANGE_RED))
+                        }
+                        StructureKind::Mine => {
+                            FeedMessage::new("mine detonated", Color::from(ORANGE))
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
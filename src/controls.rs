@@ -0,0 +1,174 @@
+use serde::*;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::party::{PlayerControls, PARTY_CONTROLS, PARTY_MAX};
+use crate::settings::Settings;
+
+/// Half of the window a mouse zone covers.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// An input a control is bound to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// The left mouse button held on one half of the window.
+    MouseZone(Side),
+}
+
+/// Controls of the first player, picked in the accessibility options. The
+/// rest of the party keeps the shared keyboard layout.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ControlScheme {
+    #[default]
+    Standard,
+    /// Everything within reach of the left hand.
+    LeftHand,
+    /// Everything around the arrow keys.
+    RightHand,
+    /// Click and hold on a half of the window to rotate toward it.
+    Mouse,
+}
+
+/// Keys the game reads directly while a run is played: the draft, the
+/// shop screens, the abilities and the run shortcuts. The schemes keep
+/// off them.
+pub const RESERVED_KEYS: [KeyCode; 20] = [
+    KeyCode::KeyR,
+    KeyCode::KeyQ,
+    KeyCode::KeyE,
+    KeyCode::Space,
+    KeyCode::KeyB,
+    KeyCode::KeyG,
+    KeyCode::KeyF,
+    KeyCode::KeyX,
+    KeyCode::KeyV,
+    KeyCode::Tab,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F9,
+    KeyCode::F10,
+];
+
+const LEFT_HAND_CONTROLS: PlayerControls = PlayerControls {
+    left: Binding::Key(KeyCode::KeyA),
+    right: Binding::Key(KeyCode::KeyD),
+    fire: Binding::Key(KeyCode::KeyS),
+    buy: Binding::Key(KeyCode::KeyT),
+    call: Binding::Key(KeyCode::KeyW),
+    climb: Binding::Key(KeyCode::KeyZ),
+    parry: Binding::Key(KeyCode::ShiftLeft),
+};
+
+const RIGHT_HAND_CONTROLS: PlayerControls = PlayerControls {
+    left: Binding::Key(KeyCode::ArrowLeft),
+    right: Binding::Key(KeyCode::ArrowRight),
+    fire: Binding::Key(KeyCode::ArrowDown),
+    buy: Binding::Key(KeyCode::ArrowUp),
+    call: Binding::Key(KeyCode::ShiftRight),
+    climb: Binding::Key(KeyCode::ControlRight),
+    parry: Binding::Key(KeyCode::AltRight),
+};
+
+const MOUSE_CONTROLS: PlayerControls = PlayerControls {
+    left: Binding::MouseZone(Side::Left),
+    right: Binding::MouseZone(Side::Right),
+    fire: Binding::Mouse(MouseButton::Right),
+    buy: Binding::Mouse(MouseButton::Back),
+    call: Binding::Mouse(MouseButton::Forward),
+    climb: Binding::Mouse(MouseButton::Middle),
+    // the buttons are all taken, the free hand parries
+    parry: Binding::Key(KeyCode::ShiftLeft),
+};
+
+impl ControlScheme {
+    pub const ALL: [ControlScheme; 4] = [
+        ControlScheme::Standard,
+        ControlScheme::LeftHand,
+        ControlScheme::RightHand,
+        ControlScheme::Mouse,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlScheme::Standard => "standard",
+            ControlScheme::LeftHand => "left hand",
+            ControlScheme::RightHand => "right hand",
+            ControlScheme::Mouse => "mouse",
+        }
+    }
+
+    pub fn controls(&self, player: usize) -> &'static PlayerControls {
+        match self {
+            _ if player > 0 => &PARTY_CONTROLS[player],
+            ControlScheme::Standard => &PARTY_CONTROLS[0],
+            ControlScheme::LeftHand => &LEFT_HAND_CONTROLS,
+            ControlScheme::RightHand => &RIGHT_HAND_CONTROLS,
+            ControlScheme::Mouse => &MOUSE_CONTROLS,
+        }
+    }
+
+    /// Every binding of the scheme, the rest of the party's included.
+    pub fn bindings(&self) -> impl Iterator<Item = Binding> + '_ {
+        (0..PARTY_MAX).flat_map(move |player| self.controls(player).all())
+    }
+}
+
+/// Reads the bindings of the players, whatever device they are on. Only
+/// the keyboard goes into the input traces.
+#[derive(SystemParam)]
+pub struct PlayerInput<'w, 's> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    settings: Res<'w, Settings>,
+    window_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl PlayerInput<'_, '_> {
+    pub fn controls(&self, player: usize) -> &'static PlayerControls {
+        self.settings.control_scheme.controls(player)
+    }
+
+    fn cursor_side(&self) -> Option<Side> {
+        let window = self.window_query.get_single().ok()?;
+        let cursor = window.cursor_position()?;
+        Some(if cursor.x < window.width() * 0.5 {
+            Side::Left
+        } else {
+            Side::Right
+        })
+    }
+
+    pub fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.pressed(key),
+            Binding::Mouse(button) => self.mouse.pressed(button),
+            Binding::MouseZone(side) => {
+                self.mouse.pressed(MouseButton::Left) && self.cursor_side() == Some(side)
+            }
+        }
+    }
+
+    pub fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.just_pressed(key),
+            Binding::Mouse(button) => self.mouse.just_pressed(button),
+            Binding::MouseZone(side) => {
+                self.mouse.just_pressed(MouseButton::Left) && self.cursor_side() == Some(side)
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::Enemy;
+
+/// Distance past the edge of the view before an enemy counts as off-screen.
+const CULL_MARGIN: f32 = 128.0;
+/// Off-screen enemies update their steering once every this many frames.
+pub const OFFSCREEN_STEERING_INTERVAL: u32 = 4;
+
+/// Per-frame counters shown in the debug overlay.
+#[derive(Default, Resource)]
+pub struct CullingStats {
+    pub visible: usize,
+    pub culled: usize,
+    pub steering_updates: usize,
+    pub steering_skipped: usize,
+}
+
+/// Hides the enemies far outside the view and flags them so that
+/// their movement is simulated at a lower rate.
+pub fn cull_enemies(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    mut stats: ResMut<CullingStats>,
+    mut enemy_query: Query<(&mut Enemy, &Transform, &mut Visibility), Without<Camera2d>>,
+) {
+    let (center, half_size) = match (camera_query.get_single(), window_query.get_single()) {
+        (Ok((transform, projection)), Ok(window)) => (
+            transform.translation.truncate(),
+            Vec2::new(window.width(), window.height()) * 0.5 * projection.scale,
+        ),
+        _ => return,
+    };
+    let half_size = half_size + Vec2::splat(CULL_MARGIN);
+
+    stats.visible = 0;
+    stats.culled = 0;
+    for (mut enemy, transform, mut visibility) in &mut enemy_query {
+        let offset = (transform.translation.truncate() - center).abs();
+        let offscreen = offset.x > half_size.x || offset.y > half_size.y;
+        if enemy.offscreen != offscreen {
+            enemy.offscreen = offscreen;
+        }
+        let wanted = if offscreen {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        if offscreen {
+            stats.culled += 1;
+        } else {
+            stats.visible += 1;
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::{Planet, PlanetDamaged};
+
+const INDICATOR_SECONDS: f32 = 0.6;
+/// Width of the arc, in radians.
+const INDICATOR_ARC: f32 = 0.6;
+/// Pixels outside the planet rim.
+const INDICATOR_OFFSET: f32 = 6.0;
+/// Damage that draws the arc at full thickness.
+const INDICATOR_FULL_DAMAGE: f32 = 10.0;
+
+/// Recent hits on the planet: angle of the attacker, strength and age in
+/// seconds.
+#[derive(Default, Resource)]
+pub struct DamageIndicators {
+    pub arcs: Vec<(f32, f32, f32)>,
+}
+
+pub fn spawn_damage_indicators(
+    mut indicators: ResMut<DamageIndicators>,
+    mut damage_events: EventReader<PlanetDamaged>,
+) {
+    for event in damage_events.read() {
+        let strength = (event.damage / INDICATOR_FULL_DAMAGE).clamp(0.25, 1.0);
+        indicators.arcs.push((event.angle, strength, 0.0));
+    }
+}
+
+/// Flashes an arc on the rim toward each attacker, so that a hit on the
+/// far side of the planet is noticed.
+pub fn draw_damage_indicators(
+    time: Res<Time>,
+    mut indicators: ResMut<DamageIndicators>,
+    planet_query: Query<&Planet>,
+    mut gizmos: Gizmos,
+) {
+    for (_, _, age) in indicators.arcs.iter_mut() {
+        *age += time.delta_seconds();
+    }
+    indicators
+        .arcs
+        .retain(|(_, _, age)| *age < INDICATOR_SECONDS);
+    let planet = match planet_query.get_single() {
+        Ok(planet) => planet,
+        Err(_) => return,
+    };
+    let radius = planet.size * 0.5 + INDICATOR_OFFSET;
+    for (angle, strength, age) in &indicators.arcs {
+        let color = Color::srgba(1.0, 0.2, 0.1, strength * (1.0 - age / INDICATOR_SECONDS));
+        // the gizmo arcs are centered on an angle measured clockwise from +y
+        let direction = std::f32::consts::FRAC_PI_2 - angle;
+        for ring in 0..3 {
+            gizmos.arc_2d(
+                Vec2::ZERO,
+                direction,
+                INDICATOR_ARC,
+                radius + ring as f32 * 2.0,
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+
+use crate::level::{CurrentLevel, Levels};
+use crate::{AssetHandles, FontName, MaterialName, MeshName, TimeScale};
+
+/// Fraction of the cycle, 0.0 is midnight and 0.5 is noon.
+#[derive(Resource)]
+pub struct DayNight {
+    pub time: f32,
+    pub cycle_seconds: f32,
+}
+
+impl Default for DayNight {
+    fn default() -> Self {
+        DayNight {
+            time: 0.25,
+            cycle_seconds: 180.0,
+        }
+    }
+}
+
+impl DayNight {
+    /// 0.0 at noon, 1.0 at midnight.
+    pub fn darkness(&self) -> f32 {
+        0.5 + 0.5 * f32::cos(self.time * 2.0 * std::f32::consts::PI)
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.darkness() > 0.5
+    }
+
+    pub fn enemy_speed_multiplier(&self) -> f32 {
+        1.0 + 0.5 * self.darkness()
+    }
+
+    pub fn stealth_chance(&self) -> f32 {
+        0.05 + 0.35 * self.darkness()
+    }
+
+    pub fn clock(&self) -> String {
+        let minutes = (self.time * 24.0 * 60.0) as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+#[derive(Component)]
+pub struct NightTint;
+
+#[derive(Component)]
+pub struct UiTextClock;
+
+pub fn setup_daynight(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+) {
+    let level = &levels.list[current_level.0];
+
+    commands.insert_resource(DayNight::default());
+
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: handles
+                .meshes
+                .get(&MeshName::Circle)
+                .unwrap()
+                .clone_weak()
+                .into(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 10.0),
+                scale: Vec3::new(level.spawner_size, level.spawner_size, 1.0),
+                ..default()
+            },
+            material: handles
+                .materials
+                .get(&MaterialName::NightTint)
+                .unwrap()
+                .clone_weak(),
+            ..default()
+        })
+        .insert(NightTint);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(5.0),
+                right: Val::Px(15.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "06:00",
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(UiTextClock);
+        });
+}
+
+pub fn update_daynight(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut daynight: ResMut<DayNight>,
+    handles: Res<AssetHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut text_query: Query<&mut Text, With<UiTextClock>>,
+) {
+    daynight.time =
+        (daynight.time + time_scale.delta_seconds(&time) / daynight.cycle_seconds).fract();
+
+    if let Some(tint) = materials.get_mut(handles.materials.get(&MaterialName::NightTint).unwrap())
+    {
+        tint.color = Color::srgba(0.0, 0.02, 0.1, 0.5 * daynight.darkness());
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = daynight.clock();
+        text.sections[0].style.color = if daynight.is_night() {
+            Color::srgb(0.5, 0.6, 1.0)
+        } else {
+            Color::WHITE
+        };
+    }
+}
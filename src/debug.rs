@@ -0,0 +1,156 @@
+use bevy::color::palettes::css::{CYAN, YELLOW};
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::culling::CullingStats;
+use crate::draw_calls::DrawCalls;
+use crate::{AssetHandles, Enemy, FontName};
+
+/// Seconds of velocity used to place the predicted target point.
+const TARGET_LOOKAHEAD: f32 = 0.5;
+/// Pixels drawn per unit of steering applied in a frame.
+const STEERING_SCALE: f32 = 40.0;
+
+#[derive(Default, Resource)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+/// Shapes redrawn every frame while the overlay is enabled.
+#[derive(Component)]
+pub struct DebugMarker;
+
+pub fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    marker_query: Query<Entity, With<DebugMarker>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+        if !overlay.enabled {
+            for entity in &marker_query {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn spawn_line(commands: &mut Commands, from: Vec2, to: Vec2, color: Color) {
+    let delta = to - from;
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(delta.length(), 2.0)),
+                anchor: bevy::sprite::Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform {
+                translation: from.extend(30.0),
+                rotation: Quat::from_rotation_z(delta.y.atan2(delta.x)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(DebugMarker);
+}
+
+fn spawn_point(commands: &mut Commands, at: Vec2, color: Color) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(6.0, 6.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(at.extend(30.0)),
+            ..default()
+        })
+        .insert(DebugMarker);
+}
+
+/// Steering vector, predicted target point and behavior label of each enemy.
+pub fn draw_enemy_debug(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    overlay: Res<DebugOverlay>,
+    culling: Res<CullingStats>,
+    draw_calls: Option<Res<DrawCalls>>,
+    enemy_query: Query<(&Enemy, &Transform, &Velocity)>,
+    marker_query: Query<Entity, With<DebugMarker>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    for entity in &marker_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+
+    commands
+        .spawn(
+            TextBundle::from_section(
+                format!(
+                    "enemies {} visible {} culled\nsteering {} updated {} skipped\n2d draw calls {}",
+                    culling.visible,
+                    culling.culled,
+                    culling.steering_updates,
+                    culling.steering_skipped,
+                    draw_calls.map_or(0, |draw_calls| draw_calls.get())
+                ),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.0,
+                    color: Color::from(YELLOW),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(90.0),
+                left: Val::Px(15.0),
+                ..default()
+            }),
+        )
+        .insert(DebugMarker);
+
+    for (enemy, transform, velocity) in &enemy_query {
+        let position = transform.translation.truncate();
+        spawn_line(
+            &mut commands,
+            position,
+            position + enemy.steering * STEERING_SCALE,
+            Color::from(YELLOW),
+        );
+        let target = position + velocity.linvel * TARGET_LOOKAHEAD;
+        spawn_line(
+            &mut commands,
+            position,
+            target,
+            Color::srgba(0.0, 1.0, 1.0, 0.4),
+        );
+        spawn_point(&mut commands, target, Color::from(CYAN));
+
+        commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    format!("{} {:.0}", enemy.behavior(), enemy.hp),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                transform: Transform::from_translation((position + Vec2::Y * 28.0).extend(30.0)),
+                ..default()
+            })
+            .insert(DebugMarker);
+    }
+}
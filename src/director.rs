@@ -0,0 +1,87 @@
+use bevy::color::palettes::css::YELLOW;
+use bevy::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::conditions::RunModifiers;
+use crate::feed::FeedMessage;
+use crate::sandbox::{GrantPowerup, Powerup};
+use crate::satellite::{next_satellite_angle, spawn_satellite, Satellite, SATELLITE_MAX};
+use crate::spawner::ScriptAction;
+use crate::story::Cutscene;
+use crate::{Challenge, Spawner};
+
+/// Plays the scripted events of the challenge as their waves start.
+#[derive(Default, Resource)]
+pub struct Director {
+    /// Last wave whose events were played.
+    pub played: Option<usize>,
+}
+
+pub fn setup_director(mut commands: Commands) {
+    commands.insert_resource(Director::default());
+}
+
+pub fn direct_events(
+    mut commands: Commands,
+    challenge: Res<Challenge>,
+    modifiers: Res<RunModifiers>,
+    mut director: ResMut<Director>,
+    mut cutscene: ResMut<Cutscene>,
+    mut feed: EventWriter<FeedMessage>,
+    mut powerup_events: EventWriter<GrantPowerup>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut spawner_query: Query<&mut Spawner>,
+    satellite_query: Query<&Satellite>,
+) {
+    if modifiers.sandbox {
+        return;
+    }
+    let mut spawner = match spawner_query.get_single_mut() {
+        Ok(spawner) => spawner,
+        Err(_) => return,
+    };
+    let wave = spawner.current_wave;
+    if spawner.in_intermission() || director.played == Some(wave) {
+        return;
+    }
+    director.played = Some(wave);
+
+    let mut satellites = satellite_query.iter().count();
+    for event in challenge.events.iter().filter(|event| event.wave == wave) {
+        for action in &event.actions {
+            match action {
+                ScriptAction::Message(text) => {
+                    feed.send(FeedMessage::new(text.clone(), Color::from(YELLOW)));
+                }
+                ScriptAction::Spawn { enemy_id, count } => {
+                    for _ in 0..*count {
+                        spawner.queue.push_back((*enemy_id, None));
+                    }
+                    // scripted spawns are the bosses, announced with a sting
+                    sound_events.send(PlaySound {
+                        sound: SoundName::Stinger,
+                        position: None,
+                        caption: Some("warning: boss approaching"),
+                    });
+                }
+                ScriptAction::Scrap(scrap) => {
+                    powerup_events.send(GrantPowerup(Powerup::Scrap(*scrap)));
+                }
+                ScriptAction::Satellite => {
+                    if satellites < SATELLITE_MAX {
+                        // spread from the ones already up, this frame's
+                        // deliveries aren't in the query yet
+                        let angle = next_satellite_angle(&satellite_query)
+                            + (satellites - satellite_query.iter().count()) as f32
+                                * 2.0
+                                * std::f32::consts::PI
+                                / SATELLITE_MAX as f32;
+                        spawn_satellite(&mut commands, angle);
+                        satellites += 1;
+                    }
+                }
+                ScriptAction::Dialog(lines) => cutscene.play(lines),
+            }
+        }
+    }
+}
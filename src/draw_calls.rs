@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use bevy::core_pipeline::core_2d::Transparent2d;
+use bevy::prelude::*;
+use bevy::render::render_phase::{PhaseItem, ViewSortedRenderPhases};
+use bevy::render::renderer::render_system;
+use bevy::render::{Render, RenderApp, RenderSet};
+
+/// Draw calls of the 2d pass in the last rendered frame, written by the
+/// render world and shown in the debug overlay.
+#[derive(Resource, Clone, Default)]
+pub struct DrawCalls(Arc<AtomicUsize>);
+
+impl DrawCalls {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub fn add_draw_call_counter(app: &mut App) {
+    let draw_calls = DrawCalls::default();
+    app.insert_resource(draw_calls.clone());
+    // headless apps have no render world
+    if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+        render_app.insert_resource(draw_calls).add_systems(
+            Render,
+            count_draw_calls
+                .in_set(RenderSet::Render)
+                .before(render_system),
+        );
+    }
+}
+
+/// Batching grows the range of the first item of a batch and leaves the
+/// others empty, so every non-empty range is a draw call.
+fn count_draw_calls(
+    draw_calls: Res<DrawCalls>,
+    phases: Res<ViewSortedRenderPhases<Transparent2d>>,
+) {
+    let count = phases
+        .values()
+        .flat_map(|phase| phase.items.iter())
+        .filter(|item| !item.batch_range().is_empty())
+        .count();
+    draw_calls.0.store(count, Ordering::Relaxed);
+}
@@ -0,0 +1,97 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::balance::{GunBalance, MineBalance};
+use crate::loot::LootTable;
+
+pub const ENEMY_BASIC: u32 = 0;
+pub const ENEMY_STEALTH: u32 = 1;
+
+// embedded for the same reason as the level files
+const ENEMIES_FILE: &str = include_str!("../assets/enemies.json");
+
+/// How the enemy position is integrated: by the physics engine, or
+/// directly from the steering, which is frame rate independent.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MovementMode {
+    Dynamic,
+    Kinematic,
+}
+
+impl Default for MovementMode {
+    fn default() -> Self {
+        MovementMode::Dynamic
+    }
+}
+
+/// What the enemy flies toward: always the planet, the structure with
+/// the highest threat while there is one, or the closest player.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TargetingMode {
+    Planet,
+    Threat,
+    /// Steers ahead of the player along their orbit.
+    Player,
+    /// Flocks with the other swarm enemies on the way to the planet.
+    Swarm,
+}
+
+impl Default for TargetingMode {
+    fn default() -> Self {
+        TargetingMode::Planet
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnemyDefinition {
+    pub name: String,
+    pub hp: f32,
+    pub speed: f32,
+    pub damage: f32,
+    pub stealth: bool,
+    pub scrap: u32,
+    #[serde(default)]
+    pub movement: MovementMode,
+    #[serde(default)]
+    pub targeting: TargetingMode,
+    /// Shield hp on top of the hp, zero for unshielded enemies.
+    #[serde(default)]
+    pub shield: f32,
+    #[serde(default)]
+    pub elite: bool,
+    /// Introduced with a banner and holds the waves, see the boss module.
+    #[serde(default)]
+    pub boss: bool,
+    /// Drops a mine as it crosses the players' orbits.
+    #[serde(default)]
+    pub mines: Option<MineBalance>,
+    /// Shoots at the players in range, see the gunships module.
+    #[serde(default)]
+    pub gun: Option<GunBalance>,
+    /// Channels to bring back the enemies that died nearby, see the
+    /// revival module.
+    #[serde(default)]
+    pub revives: bool,
+    #[serde(default)]
+    pub loot: LootTable,
+}
+
+#[derive(Serialize, Deserialize, Resource)]
+pub struct EnemyDefinitions {
+    pub list: Vec<EnemyDefinition>,
+}
+
+impl EnemyDefinitions {
+    pub fn get(&self, enemy_id: u32) -> &EnemyDefinition {
+        self.list
+            .get(enemy_id as usize)
+            .unwrap_or(&self.list[ENEMY_BASIC as usize])
+    }
+}
+
+impl Default for EnemyDefinitions {
+    fn default() -> Self {
+        serde_json::from_str(ENEMIES_FILE).expect("invalid enemies file")
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::Enemy;
+
+/// Side of a grid cell, around the range of a single enemy sprite.
+const CELL_SIZE: f32 = 128.0;
+
+/// Enemy positions bucketed by cell, rebuilt every frame so that range
+/// and nearest lookups only visit the cells around the query.
+#[derive(Default, Resource)]
+pub struct EnemyGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl EnemyGrid {
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// Enemies closer than radius to center, in no particular order.
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = Self::cell(center - Vec2::splat(radius));
+        let max = Self::cell(center + Vec2::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, pos)| pos.distance_squared(center) < radius * radius)
+    }
+
+    /// Closest enemy to center that is closer than radius.
+    pub fn nearest(&self, center: Vec2, radius: f32) -> Option<(Entity, Vec2)> {
+        self.within(center, radius).min_by(|(_, a), (_, b)| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })
+    }
+}
+
+pub fn rebuild_enemy_grid(
+    mut grid: ResMut<EnemyGrid>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+) {
+    // keep the cell vectors around, the enemies only move a few cells a frame
+    for cell in grid.cells.values_mut() {
+        cell.clear();
+    }
+    for (entity, transform) in &enemy_query {
+        let pos = transform.translation.truncate();
+        grid.cells
+            .entry(EnemyGrid::cell(pos))
+            .or_default()
+            .push((entity, pos));
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::spawner::RunSeed;
+use crate::{Enemy, SPRITE_CELL};
+
+/// Largest change of the sprite size, either way.
+const SIZE_SPREAD: f32 = 0.1;
+/// Saturation of the tint, enough to tell two drones apart without
+/// hiding what they are.
+const TINT_SATURATION: f32 = 0.3;
+const TINT_LIGHTNESS: f32 = 0.9;
+
+/// Enemies spawned so far in the run, the index of the next one.
+#[derive(Default, Resource)]
+pub struct EnemyLooks {
+    pub spawned: u64,
+}
+
+/// Scrambles the bits of the seed and index, without drawing from the
+/// gameplay random generators.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+pub fn setup_enemy_looks(mut commands: Commands) {
+    commands.insert_resource(EnemyLooks::default());
+}
+
+/// Tints and resizes each enemy a little, so that a swarm doesn't look
+/// like clones. Only the sprite changes: the collider follows the
+/// transform, which is left alone.
+pub fn vary_enemies(
+    seed: Res<RunSeed>,
+    mut looks: ResMut<EnemyLooks>,
+    mut enemy_query: Query<&mut Sprite, Added<Enemy>>,
+) {
+    for mut sprite in &mut enemy_query {
+        let bits = mix(seed.0 ^ mix(looks.spawned));
+        looks.spawned += 1;
+        // two values in -1..1 from the low bits
+        let unit = |shift: u32| ((bits >> shift) & 0xffff) as f32 / 65535.0 * 2.0 - 1.0;
+        let hue = (unit(0) + 1.0) * 180.0;
+        let alpha = sprite.color.alpha();
+        sprite.color = Color::hsla(hue, TINT_SATURATION, TINT_LIGHTNESS, alpha);
+        sprite.custom_size = Some(Vec2::splat(SPRITE_CELL * (1.0 + unit(16) * SIZE_SPREAD)));
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::color::palettes::css::YELLOW;
+use bevy::prelude::*;
+
+use crate::enemies::EnemyDefinitions;
+use crate::settings::Settings;
+use crate::{AssetHandles, EnemyKilled, FontName, WaveEnded};
+
+const FEED_MAX_ENTRIES: usize = 5;
+const FEED_LIFETIME: f32 = 6.0;
+const FEED_FADE: f32 = 1.5;
+
+/// A notable event to report in the ticker.
+#[derive(Event)]
+pub struct FeedMessage {
+    pub text: String,
+    pub color: Color,
+}
+
+impl FeedMessage {
+    pub fn new(text: impl Into<String>, color: Color) -> Self {
+        FeedMessage {
+            text: text.into(),
+            color,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct FeedRoot;
+
+#[derive(Component)]
+pub struct FeedEntry {
+    pub age: f32,
+}
+
+pub fn setup_feed(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(110.0),
+                left: Val::Px(15.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(FeedRoot);
+}
+
+/// Turns the gameplay events worth reporting into feed messages.
+pub fn feed_from_events(
+    definitions: Res<EnemyDefinitions>,
+    settings: Res<Settings>,
+    mut killed_events: EventReader<EnemyKilled>,
+    mut wave_events: EventReader<WaveEnded>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    for event in killed_events.read() {
+        let definition = definitions.get(event.enemy_id);
+        if definition.stealth {
+            feed.send(FeedMessage::new(
+                format!("{} destroyed", definition.name),
+                Color::srgb(0.6, 0.6, 1.0),
+            ));
+        }
+    }
+    for event in wave_events.read() {
+        let mut text = format!("wave {} cleared, {} kills", event.wave + 1, event.kills);
+        if settings.hit_feedback && event.shots_fired > 0 {
+            text += &format!(
+                ", {:.0}% accuracy",
+                event.shots_hit as f32 / event.shots_fired as f32 * 100.0
+            );
+        }
+        feed.send(FeedMessage::new(text, Color::from(YELLOW)));
+    }
+}
+
+pub fn push_feed_entries(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut messages: EventReader<FeedMessage>,
+    root_query: Query<Entity, With<FeedRoot>>,
+    entry_query: Query<(Entity, &FeedEntry)>,
+) {
+    let root = match root_query.get_single() {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+
+    let mut entries: Vec<(Entity, f32)> = entry_query
+        .iter()
+        .map(|(entity, entry)| (entity, entry.age))
+        .collect();
+
+    for message in messages.read() {
+        let entry = commands
+            .spawn(TextBundle::from_section(
+                message.text.clone(),
+                TextStyle {
+                    font: handles
+                        .fonts
+                        .get(&FontName::IosevkaRegular)
+                        .unwrap()
+                        .clone_weak(),
+                    font_size: 20.0,
+                    color: message.color,
+                },
+            ))
+            .insert(FeedEntry { age: 0.0 })
+            .id();
+        commands.entity(root).add_child(entry);
+        entries.push((entry, 0.0));
+    }
+
+    // drop the oldest entries above the limit
+    if entries.len() > FEED_MAX_ENTRIES {
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (entity, _) in entries.iter().take(entries.len() - FEED_MAX_ENTRIES) {
+            commands.entity(*entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn fade_feed_entries(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entry_query: Query<(Entity, &mut FeedEntry, &mut Text)>,
+) {
+    for (entity, mut entry, mut text) in &mut entry_query {
+        entry.age += time.delta_seconds();
+        let alpha = ((FEED_LIFETIME - entry.age) / FEED_FADE).clamp(0.0, 1.0);
+        text.sections[0].style.color.set_alpha(alpha);
+        if entry.age >= FEED_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
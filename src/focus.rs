@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::window::WindowConfig;
+use crate::{AssetHandles, FontName};
+
+/// Whether the player has interacted with the page yet. Browsers hold
+/// back keyboard focus and audio until the first click, on native the
+/// game starts right away.
+#[derive(Resource)]
+pub struct InputCapture {
+    pub started: bool,
+}
+
+impl Default for InputCapture {
+    fn default() -> Self {
+        InputCapture {
+            started: !cfg!(target_arch = "wasm32"),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct UiClickToStart;
+
+pub fn setup_click_to_start(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    capture: Res<InputCapture>,
+) {
+    if capture.started {
+        return;
+    }
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(100),
+            ..default()
+        })
+        .insert(UiClickToStart)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "click to start",
+                TextStyle {
+                    font: handles
+                        .fonts
+                        .get(&FontName::IosevkaRegular)
+                        .unwrap()
+                        .clone_weak(),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// The first click focuses the canvas and counts as the user gesture
+/// that lets the browser play audio.
+pub fn click_to_start(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    config: Res<WindowConfig>,
+    mut capture: ResMut<InputCapture>,
+    overlay_query: Query<Entity, With<UiClickToStart>>,
+) {
+    if capture.started {
+        return;
+    }
+    if mouse_input.get_just_pressed().next().is_none() && !touches.any_just_pressed() {
+        return;
+    }
+    capture.started = true;
+    focus_canvas(&config);
+    for entity in &overlay_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn canvas(config: &WindowConfig) -> Option<web_sys::HtmlElement> {
+    use wasm_bindgen::JsCast;
+    web_sys::window()?
+        .document()?
+        .query_selector(&config.canvas)
+        .ok()??
+        .dyn_into::<web_sys::HtmlElement>()
+        .ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn focus_canvas(config: &WindowConfig) {
+    if let Some(canvas) = canvas(config) {
+        let _ = canvas.focus();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn focus_canvas(_config: &WindowConfig) {}
+
+/// Keys the page would otherwise use to scroll or move focus around.
+#[cfg(target_arch = "wasm32")]
+const CAPTURED_KEYS: [&str; 7] = [
+    "ArrowUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "Space",
+    "Tab",
+    "Backspace",
+];
+
+/// Makes the canvas focusable and keeps the game keys from reaching the
+/// page around it, so arrows don't scroll an embedding iframe.
+#[cfg(target_arch = "wasm32")]
+pub fn capture_game_keys(config: Res<WindowConfig>) {
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+
+    let canvas = match canvas(&config) {
+        Some(canvas) => canvas,
+        None => {
+            warn!("no canvas matches {}", config.canvas);
+            return;
+        }
+    };
+    canvas.set_tab_index(0);
+    let keydown =
+        Closure::<dyn FnMut(web_sys::KeyboardEvent)>::new(|event: web_sys::KeyboardEvent| {
+            if CAPTURED_KEYS.contains(&event.code().as_str()) {
+                event.prevent_default();
+            }
+        });
+    if canvas
+        .add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())
+        .is_err()
+    {
+        warn!("failed to capture the game keys");
+    }
+    // the listener lives as long as the page
+    keydown.forget();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn capture_game_keys() {}
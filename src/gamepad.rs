@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+
+/// A rumble pulse, strength goes from 0.0 to 1.0.
+#[derive(Event)]
+pub struct Rumble {
+    pub strength: f32,
+    pub duration_ms: u32,
+}
+
+impl Rumble {
+    pub fn new(strength: f32, duration_ms: u32) -> Self {
+        Rumble {
+            strength,
+            duration_ms,
+        }
+    }
+}
+
+/// Forwards the pulses to every connected gamepad, scaled by the settings;
+/// gamepads without force feedback ignore the requests.
+pub fn play_rumble(
+    settings: Res<Settings>,
+    gamepads: Res<Gamepads>,
+    mut rumble_events: EventReader<Rumble>,
+    mut requests: EventWriter<GamepadRumbleRequest>,
+) {
+    for event in rumble_events.read() {
+        let strength = (event.strength * settings.rumble_intensity).clamp(0.0, 1.0);
+        if strength <= 0.0 {
+            continue;
+        }
+        for gamepad in gamepads.iter() {
+            requests.send(GamepadRumbleRequest::Add {
+                gamepad,
+                duration: Duration::from_millis(event.duration_ms as u64),
+                intensity: GamepadRumbleIntensity::strong_motor(strength),
+            });
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::checkpoints::RunCheckpoint;
+use crate::conditions::{RunClock, RunModifiers, RunOutcome};
+use crate::level::{CurrentLevel, Levels};
+use crate::score::{RunStats, ScoreBreakdown};
+use crate::storage;
+use crate::{AssetHandles, ImageName, Planet, Player};
+
+/// Seconds of run time between two recorded samples.
+const GHOST_INTERVAL: f32 = 0.1;
+const GHOST_ALPHA: f32 = 0.35;
+
+/// Angular position of the player around the planet over a run.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct GhostRecording {
+    pub score: u32,
+    pub angles: Vec<f32>,
+}
+
+impl GhostRecording {
+    fn key(level: &str) -> String {
+        format!("ghost-{}", level)
+    }
+
+    pub fn load(level: &str) -> Option<GhostRecording> {
+        storage::load(&GhostRecording::key(level))
+    }
+
+    pub fn save(&self, level: &str) {
+        if !storage::save(&GhostRecording::key(level), self) {
+            warn!("failed to save the ghost");
+        }
+    }
+
+    /// Interpolated angle at the given run time, none once it ran out.
+    fn angle_at(&self, seconds: f32) -> Option<f32> {
+        let position = seconds / GHOST_INTERVAL;
+        let index = position as usize;
+        let from = *self.angles.get(index)?;
+        let to = self.angles.get(index + 1).copied().unwrap_or(from);
+        // take the short way around the wrap at +-pi
+        let delta = (to - from + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        Some(from + delta * position.fract())
+    }
+}
+
+/// The current run being recorded, and the personal best being replayed.
+#[derive(Resource, Default)]
+pub struct Ghosts {
+    pub recording: GhostRecording,
+    pub best: Option<GhostRecording>,
+}
+
+#[derive(Component)]
+pub struct GhostPlayer;
+
+pub fn setup_ghost(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    modifiers: Res<RunModifiers>,
+) {
+    let best = if modifiers.sandbox {
+        None
+    } else {
+        GhostRecording::load(&levels.list[current_level.0].name)
+    };
+    if best.is_some() {
+        commands
+            .spawn(SpriteBundle {
+                texture: handles.images.get(&ImageName::Player).unwrap().clone_weak(),
+                sprite: Sprite {
+                    color: Color::srgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 1.9),
+                visibility: Visibility::Hidden,
+                ..default()
+            })
+            .insert(GhostPlayer);
+    }
+    commands.insert_resource(Ghosts {
+        recording: GhostRecording::default(),
+        best,
+    });
+}
+
+pub fn record_ghost(
+    clock: Res<RunClock>,
+    mut ghosts: ResMut<Ghosts>,
+    player_query: Query<(&Player, &Transform)>,
+) {
+    // party runs record the first player
+    let transform = match player_query.iter().find(|(player, _)| player.index == 0) {
+        Some((_, transform)) => transform,
+        None => return,
+    };
+    let angle = transform.translation.y.atan2(transform.translation.x);
+    let angles = &mut ghosts.recording.angles;
+    while angles.len() as f32 * GHOST_INTERVAL <= clock.seconds {
+        angles.push(angle);
+    }
+}
+
+pub fn move_ghost(
+    clock: Res<RunClock>,
+    ghosts: Res<Ghosts>,
+    planet_query: Query<&Planet>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<GhostPlayer>>,
+) {
+    let (best, planet) = match (&ghosts.best, planet_query.get_single()) {
+        (Some(best), Ok(planet)) => (best, planet),
+        _ => return,
+    };
+    for (mut transform, mut visibility) in &mut ghost_query {
+        match best.angle_at(clock.seconds) {
+            Some(angle) => {
+                let radius = planet.size * 0.5 + 8.0;
+                transform.translation.x = angle.cos() * radius;
+                transform.translation.y = angle.sin() * radius;
+                transform.rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Keeps the recording if the run beat the stored ghost.
+pub fn save_ghost(
+    outcome: Res<RunOutcome>,
+    stats: Res<RunStats>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    mut ghosts: ResMut<Ghosts>,
+    checkpoint: Res<RunCheckpoint>,
+    planet_query: Query<&Planet>,
+) {
+    if modifiers.practice.is_some() || modifiers.sandbox {
+        return;
+    }
+    let planet_hp = planet_query.get_single().map(|p| p.hp).unwrap_or(0.0);
+    let score =
+        ScoreBreakdown::compute(&stats, &outcome, planet_hp, checkpoint.score_multiplier()).total();
+    if ghosts
+        .best
+        .as_ref()
+        .map_or(false, |best| best.score >= score)
+    {
+        return;
+    }
+    ghosts.recording.score = score;
+    ghosts.recording.save(&levels.list[current_level.0].name);
+    ghosts.best = Some(ghosts.recording.clone());
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+use bevy_rapier2d::{pipeline::CollisionEvent::*, prelude::*};
+
+use crate::balance::Balance;
+use crate::bonuses::{KillBonus, KillBonusKind};
+use crate::teams::{Role, Team};
+use crate::ultimate::Ultimate;
+use crate::{Bullet, Player};
+
+/// Reach of the sensor around the players, a bit past the distance an
+/// enemy shot connects at.
+const GRAZE_RADIUS: f32 = 28.0;
+
+/// A shot that already counted as a graze, it only counts once.
+#[derive(Component)]
+pub struct Grazed;
+
+/// Sensor of the near misses, on the player ships.
+pub fn graze_sensor() -> impl Bundle {
+    (
+        Collider::ball(GRAZE_RADIUS),
+        Sensor,
+        // the players have no rigid body, so the sensor counts as fixed
+        ActiveCollisionTypes::all(),
+        Team::Player.collision_groups(Role::Graze),
+    )
+}
+
+/// Shots that would hurt the players and leave a player's sensor without
+/// having hit anything were a near miss. The ones that hit or ran out of
+/// range inside it don't count.
+pub fn detect_grazes(
+    mut commands: Commands,
+    balance: Res<Balance>,
+    mut ultimate: ResMut<Ultimate>,
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<(&Player, &Transform)>,
+    bullet_query: Query<(&Bullet, &Team), Without<Grazed>>,
+    mut bonus_events: EventWriter<KillBonus>,
+) {
+    for collision_event in collision_events.read() {
+        let (first, second) = match collision_event {
+            Stopped(first, second, flags) if !flags.contains(CollisionEventFlags::REMOVED) => {
+                (*first, *second)
+            }
+            _ => continue,
+        };
+        // rapier doesn't order the pair, try both ways
+        for (ent, oth) in [(first, second), (second, first)] {
+            let (player, transform) = match player_query.get(ent) {
+                Ok(player) => player,
+                Err(_) => continue,
+            };
+            if !bullet_query
+                .get(oth)
+                .is_ok_and(|(bullet, team)| bullet.has_hit == 0 && team.hurts(Team::Player))
+            {
+                continue;
+            }
+            commands.entity(oth).try_insert(Grazed);
+            if ultimate.sweep.is_none() {
+                ultimate.charge = (ultimate.charge + balance.ultimate.charge_per_graze)
+                    .min(balance.ultimate.charge_max);
+            }
+            bonus_events.send(KillBonus {
+                kind: KillBonusKind::Graze,
+                position: transform.translation.truncate(),
+                by: Some(player.index),
+            });
+        }
+    }
+}
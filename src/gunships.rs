@@ -0,0 +1,110 @@
+use bevy::color::palettes::css::ORANGE_RED;
+use bevy::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::balance::GunBalance;
+use crate::enemies::EnemyDefinitions;
+use crate::feed::FeedMessage;
+use crate::gamepad::Rumble;
+use crate::teams::Team;
+use crate::{spawn_bullet, AssetHandles, Bullet, Enemy, Player, TimeScale};
+
+/// Enemy shots closer than this to a player hit it. The players only
+/// carry a graze sensor, so the hit is by distance.
+const SHOT_HIT_RADIUS: f32 = 10.0;
+/// A player hit by an enemy shot can't shoot for this long.
+const SHOT_STUN: f32 = 0.75;
+
+/// Reload of an enemy that shoots.
+#[derive(Component)]
+pub struct Gun {
+    pub cooldown: Timer,
+}
+
+impl Gun {
+    pub fn new(balance: &GunBalance) -> Gun {
+        Gun {
+            cooldown: Timer::from_seconds(balance.cooldown_seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// Gunships fire at the closest player in range once reloaded. The ones
+/// far outside the view hold their fire.
+pub fn fire_guns(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    mut gun_query: Query<(&Enemy, &mut Gun, &Transform)>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    for (enemy, mut gun, transform) in &mut gun_query {
+        gun.cooldown.tick(time_scale.delta(&time));
+        let balance = match &definitions.get(enemy.enemy_id).gun {
+            Some(balance) => balance,
+            None => continue,
+        };
+        if !gun.cooldown.finished() || enemy.offscreen {
+            continue;
+        }
+        let origin = transform.translation.truncate();
+        let target = player_query
+            .iter()
+            .map(|player_trans| player_trans.translation.truncate())
+            .filter(|position| position.distance(origin) < balance.weapon.range)
+            .min_by(|a, b| a.distance(origin).total_cmp(&b.distance(origin)));
+        let target = match target {
+            Some(target) => target,
+            None => continue,
+        };
+        gun.cooldown.reset();
+        let dir = (target - origin).normalize_or_zero();
+        spawn_bullet(
+            &mut commands,
+            &handles,
+            transform.translation + (dir * 16.0).extend(0.0),
+            dir * balance.shot_speed,
+            balance.damage,
+            &balance.weapon,
+            None,
+            Team::Enemy,
+        );
+    }
+}
+
+/// Shots that would hurt the players and reach one stun it, and are
+/// spent.
+pub fn shots_hit_players(
+    mut bullet_query: Query<(&mut Bullet, &Transform, &Team)>,
+    mut player_query: Query<(&mut Player, &Transform)>,
+    mut feed: EventWriter<FeedMessage>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut rumble_events: EventWriter<Rumble>,
+) {
+    for (mut bullet, bullet_trans, team) in &mut bullet_query {
+        if bullet.has_hit > 0 || !team.hurts(Team::Player) {
+            continue;
+        }
+        let position = bullet_trans.translation.truncate();
+        for (mut player, player_trans) in &mut player_query {
+            if player_trans.translation.truncate().distance(position) > SHOT_HIT_RADIUS {
+                continue;
+            }
+            player.stun = player.stun.max(SHOT_STUN);
+            bullet.has_hit = 1;
+            feed.send(FeedMessage::new(
+                format!("p{} hit", player.index + 1),
+                Color::from(ORANGE_RED),
+            ));
+            sound_events.send(PlaySound {
+                sound: SoundName::Impact,
+                position: Some(position),
+                caption: None,
+            });
+            rumble_events.send(Rumble::new(0.4, 120));
+            break;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+
+use crate::balance::Balance;
+use crate::conditions::{PracticeLoadout, RunModifiers};
+use crate::history::RunHistory;
+use crate::leaderboard::Leaderboard;
+use crate::level::CurrentLevel;
+use crate::profile::Profile;
+use crate::settings::Settings;
+use crate::storage;
+use crate::story::Story;
+use crate::window::WindowConfig;
+use crate::{add_game, log_plugin, AppState, Enemy, Planet, Player, RunPhase, Spawner};
+
+/// Simulated time of a headless frame.
+pub const HEADLESS_FRAME: Duration = Duration::from_nanos(16_666_667);
+/// Radians off the target within which the bot stops moving.
+const BOT_DEADZONE: f32 = 0.05;
+
+/// The game without a window or a renderer, stepped by hand with
+/// `App::update` at a fixed frame duration. It starts from the defaults
+/// and never touches the saves, the player's or its own.
+pub fn headless_app() -> App {
+    storage::disable();
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .set(log_plugin())
+            .disable::<WinitPlugin>(),
+    )
+    .insert_resource(WindowConfig::default())
+    .insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME));
+    add_game(&mut app);
+    // the recorded traces neither wait for the story nor depend on the
+    // local settings
+    app.insert_resource(Settings::default())
+        .insert_resource(Story::default())
+        .insert_resource(Profile::default())
+        .insert_resource(Leaderboard::default())
+        .insert_resource(RunHistory::default());
+    app
+}
+
+/// Skips the menu and starts a run of the given level.
+pub fn start_run(app: &mut App, level: usize) {
+    app.update();
+    app.world_mut().resource_mut::<CurrentLevel>().0 = level;
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::InGame);
+    app.update();
+}
+
+/// Runs as practice, so that simulated runs don't end up in the profile,
+/// the leaderboard or the ghosts.
+pub fn disable_records(app: &mut App) {
+    app.world_mut().resource_mut::<RunModifiers>().practice = Some(PracticeLoadout::default());
+}
+
+/// Lets a bot play instead of the keyboard.
+pub fn add_bot(app: &mut App) {
+    app.add_systems(PreUpdate, bot_input.after(bevy::input::InputSystem));
+}
+
+/// Holds fire and walks under the enemy closest to the planet, skipping
+/// the intermissions.
+fn bot_input(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+) {
+    let player = match player_query.iter().next() {
+        Some(player) => player.translation.truncate(),
+        None => return,
+    };
+    keyboard_input.press(KeyCode::KeyS);
+
+    // tapped, enter only skips on the frame it goes down
+    if enemy_query.is_empty() && !keyboard_input.pressed(KeyCode::Enter) {
+        keyboard_input.press(KeyCode::Enter);
+    } else {
+        keyboard_input.release(KeyCode::Enter);
+    }
+
+    let target = enemy_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+    let turn = target.map_or(0.0, |target| player.angle_between(target));
+    for (key, wanted) in [
+        (KeyCode::KeyA, turn > BOT_DEADZONE),
+        (KeyCode::KeyD, turn < -BOT_DEADZONE),
+    ] {
+        if wanted {
+            keyboard_input.press(key);
+        } else {
+            keyboard_input.release(key);
+        }
+    }
+}
+
+/// State of the run checked by the regression tests.
+pub struct RunSnapshot {
+    pub wave: usize,
+    pub planet_hp: f32,
+    pub enemies: usize,
+    pub max_enemies: usize,
+    pub entities: u32,
+    /// Won or lost, the outcome screen is up.
+    pub over: bool,
+}
+
+/// None when no run is in progress.
+pub fn snapshot(world: &mut World) -> Option<RunSnapshot> {
+    let wave = world
+        .query::<&Spawner>()
+        .get_single(world)
+        .ok()?
+        .current_wave;
+    let planet_hp = world.query::<&Planet>().get_single(world).ok()?.hp;
+    let enemies = world.query::<&Enemy>().iter(world).count();
+    let over = world
+        .get_resource::<State<RunPhase>>()
+        .map_or(true, |phase| *phase.get() != RunPhase::Playing);
+    Some(RunSnapshot {
+        wave,
+        planet_hp,
+        enemies,
+        max_enemies: world.resource::<Balance>().spawner.max_enemies,
+        entities: world.entities().len(),
+        over,
+    })
+}
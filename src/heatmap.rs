@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{Planet, PlanetDamaged, RunPhase};
+
+/// Slices of the circle the damage is counted in.
+const HEATMAP_BINS: usize = 24;
+/// Pixels outside the planet rim, past the damage indicators.
+const HEATMAP_OFFSET: f32 = 14.0;
+/// Rings drawn for the hottest slice, fewer for the cooler ones.
+const HEATMAP_RINGS: usize = 6;
+/// Opacity of the live overlay, the post-run one is drawn in full.
+const LIVE_ALPHA: f32 = 0.2;
+
+/// Damage taken by the planet in the current run, by angle.
+#[derive(Resource)]
+pub struct DamageHeatmap {
+    pub bins: [f32; HEATMAP_BINS],
+}
+
+impl DamageHeatmap {
+    fn bin(angle: f32) -> usize {
+        let turn = angle.rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU;
+        ((turn * HEATMAP_BINS as f32) as usize).min(HEATMAP_BINS - 1)
+    }
+
+    /// Angle at the middle of a bin.
+    fn angle(bin: usize) -> f32 {
+        (bin as f32 + 0.5) / HEATMAP_BINS as f32 * std::f32::consts::TAU
+    }
+}
+
+pub fn setup_heatmap(mut commands: Commands) {
+    commands.insert_resource(DamageHeatmap {
+        bins: [0.0; HEATMAP_BINS],
+    });
+}
+
+pub fn accumulate_heatmap(
+    mut heatmap: ResMut<DamageHeatmap>,
+    mut damage_events: EventReader<PlanetDamaged>,
+) {
+    for event in damage_events.read() {
+        heatmap.bins[DamageHeatmap::bin(event.angle)] += event.damage;
+    }
+}
+
+/// Rings around the planet, thicker and redder where more damage came
+/// from: faint while playing if the setting is on, in full once the run
+/// is over.
+pub fn draw_heatmap(
+    heatmap: Option<Res<DamageHeatmap>>,
+    settings: Res<Settings>,
+    phase: Option<Res<State<RunPhase>>>,
+    planet_query: Query<&Planet>,
+    mut gizmos: Gizmos,
+) {
+    let alpha = match phase.as_deref().map(|phase| phase.get()) {
+        Some(RunPhase::GameOver) | Some(RunPhase::Victory) => 1.0,
+        Some(RunPhase::Playing) if settings.damage_heatmap => LIVE_ALPHA,
+        _ => return,
+    };
+    let (heatmap, planet) = match (heatmap, planet_query.get_single()) {
+        (Some(heatmap), Ok(planet)) => (heatmap, planet),
+        _ => return,
+    };
+    let hottest = heatmap.bins.iter().copied().fold(0.0, f32::max);
+    if hottest <= 0.0 {
+        return;
+    }
+    let radius = planet.size * 0.5 + HEATMAP_OFFSET;
+    let arc = std::f32::consts::TAU / HEATMAP_BINS as f32;
+    for (bin, damage) in heatmap.bins.iter().enumerate() {
+        let heat = damage / hottest;
+        if heat <= 0.0 {
+            continue;
+        }
+        let color = Color::srgba(1.0, 1.0 - heat, 0.1, alpha * (0.3 + 0.7 * heat));
+        // the gizmo arcs are centered on an angle measured clockwise from +y
+        let direction = std::f32::consts::FRAC_PI_2 - DamageHeatmap::angle(bin);
+        let rings = (heat * HEATMAP_RINGS as f32).ceil() as usize;
+        for ring in 0..rings {
+            gizmos.arc_2d(
+                Vec2::ZERO,
+                direction,
+                arc * 0.9,
+                radius + ring as f32 * 2.0,
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use serde::*;
+
+use bevy::color::palettes::css::{GRAY, LIME, ORANGE_RED, YELLOW};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::checkpoints::RunCheckpoint;
+use crate::conditions::{RunModifiers, RunOutcome};
+use crate::level::{CurrentLevel, Levels};
+use crate::run_link::RunLink;
+use crate::score::{RunStats, ScoreBreakdown};
+use crate::spawner::RunSeed;
+use crate::storage;
+use crate::upgrades::RunUpgrades;
+use crate::{AssetHandles, FontName, Planet};
+
+/// Runs kept, the oldest are dropped past it.
+const HISTORY_MAX: usize = 100;
+/// Rows on the screen, newest first.
+const HISTORY_ROWS: usize = 12;
+
+/// A finished run, with what it takes to play it again.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunSummary {
+    pub level: String,
+    pub seed: u64,
+    pub victory: bool,
+    pub reason: String,
+    pub score: u32,
+    pub wave: usize,
+    pub seconds: f32,
+    /// Upgrades taken, in order.
+    pub build: Vec<String>,
+    /// Run link query of the seed and the modifiers.
+    pub link: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Resource)]
+pub struct RunHistory {
+    /// Oldest first.
+    pub runs: Vec<RunSummary>,
+}
+
+impl RunHistory {
+    pub fn load() -> RunHistory {
+        storage::load("history").unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if !storage::save("history", self) {
+            warn!("failed to save the run history");
+        }
+    }
+
+    pub fn push(&mut self, run: RunSummary) {
+        self.runs.push(run);
+        if self.runs.len() > HISTORY_MAX {
+            let excess = self.runs.len() - HISTORY_MAX;
+            self.runs.drain(..excess);
+        }
+    }
+}
+
+/// What the history screen shows, cycled by its filter buttons.
+#[derive(Default, Resource)]
+pub struct HistoryFilter {
+    /// Only victories or only defeats.
+    pub victory: Option<bool>,
+    /// Index in the levels.
+    pub level: Option<usize>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, run: &RunSummary, levels: &Levels) -> bool {
+        self.victory.map_or(true, |victory| run.victory == victory)
+            && self
+                .level
+                .map_or(true, |level| levels.list[level].name == run.level)
+    }
+}
+
+pub fn record_history(
+    outcome: Res<RunOutcome>,
+    stats: Res<RunStats>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    seed: Res<RunSeed>,
+    upgrades: Res<RunUpgrades>,
+    checkpoint: Res<RunCheckpoint>,
+    mut history: ResMut<RunHistory>,
+    planet_query: Query<&Planet>,
+) {
+    // like the links, practice and sandbox runs can't be played again
+    if modifiers.practice.is_some() || modifiers.sandbox {
+        return;
+    }
+    let level = &levels.list[current_level.0].name;
+    let planet_hp = planet_query.get_single().map(|p| p.hp).unwrap_or(0.0);
+    history.push(RunSummary {
+        level: level.clone(),
+        seed: seed.0,
+        victory: outcome.victory,
+        reason: outcome.reason.clone(),
+        score: ScoreBreakdown::compute(&stats, &outcome, planet_hp, checkpoint.score_multiplier())
+            .total(),
+        wave: outcome.wave,
+        seconds: outcome.seconds,
+        build: upgrades.taken.clone(),
+        link: RunLink::from_run(level, seed.0, &modifiers).query(),
+    });
+    history.save();
+}
+
+#[derive(Component)]
+pub struct HistoryButton;
+
+/// Shown over the menu.
+#[derive(Component)]
+pub struct HistoryScreen;
+
+#[derive(Component)]
+pub enum HistoryControl {
+    Result,
+    Level,
+    Close,
+    /// Plays the run of this index again.
+    Relaunch(usize),
+}
+
+fn spawn_history_screen(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    history: &RunHistory,
+    filter: &HistoryFilter,
+    levels: &Levels,
+) {
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    let result = match filter.victory {
+        None => "all results",
+        Some(true) => "victories",
+        Some(false) => "defeats",
+    };
+    let level = filter
+        .level
+        .map_or("all levels", |level| levels.list[level].name.as_str());
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.95).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(HistoryScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "history",
+                style(64.0, Color::WHITE),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (control, label) in [
+                        (HistoryControl::Result, result),
+                        (HistoryControl::Level, level),
+                        (HistoryControl::Close, "close"),
+                    ] {
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                                    ..default()
+                                },
+                                background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                                ..default()
+                            })
+                            .insert(control)
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    style(24.0, Color::from(YELLOW)),
+                                ));
+                            });
+                    }
+                });
+
+            let runs: Vec<(usize, &RunSummary)> = history
+                .runs
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, run)| filter.matches(run, levels))
+                .take(HISTORY_ROWS)
+                .collect();
+            if runs.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    "no runs yet",
+                    style(24.0, Color::from(GRAY)),
+                ));
+            }
+            for (index, run) in runs {
+                let color = if run.victory {
+                    Color::from(LIME)
+                } else {
+                    Color::from(ORANGE_RED)
+                };
+                let build = if run.build.is_empty() {
+                    "no upgrades".to_string()
+                } else {
+                    run.build.join(", ")
+                };
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            width: Val::Px(900.0),
+                            padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+                        ..default()
+                    })
+                    .insert(HistoryControl::Relaunch(index))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_sections([
+                            TextSection::new(
+                                format!(
+                                    "{} wave {} score {} {:.0}s seed {}\n",
+                                    run.level,
+                                    run.wave + 1,
+                                    run.score,
+                                    run.seconds,
+                                    run.seed
+                                ),
+                                style(20.0, color),
+                            ),
+                            TextSection::new(
+                                format!("{}, {}", run.reason, build),
+                                style(16.0, Color::from(GRAY)),
+                            ),
+                        ]));
+                    });
+            }
+        });
+}
+
+/// Rows relaunch their run through the run link, the same way a shared
+/// link starts it.
+pub fn history_interaction(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    history: Res<RunHistory>,
+    levels: Res<Levels>,
+    mut filter: ResMut<HistoryFilter>,
+    open_query: Query<&Interaction, (Changed<Interaction>, With<HistoryButton>)>,
+    control_query: Query<(&Interaction, &HistoryControl), Changed<Interaction>>,
+    screen_query: Query<Entity, With<HistoryScreen>>,
+) {
+    let mut open = open_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    let mut close = false;
+    for (interaction, control) in &control_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match control {
+            HistoryControl::Result => {
+                filter.victory = match filter.victory {
+                    None => Some(true),
+                    Some(true) => Some(false),
+                    Some(false) => None,
+                };
+                open = true;
+            }
+            HistoryControl::Level => {
+                filter.level = match filter.level {
+                    None if !levels.list.is_empty() => Some(0),
+                    Some(level) if level + 1 < levels.list.len() => Some(level + 1),
+                    _ => None,
+                };
+                open = true;
+            }
+            HistoryControl::Close => close = true,
+            HistoryControl::Relaunch(index) => {
+                let link = history
+                    .runs
+                    .get(*index)
+                    .and_then(|run| RunLink::parse(&run.link));
+                if let Some(link) = link {
+                    commands.insert_resource(link);
+                }
+                close = true;
+            }
+        }
+    }
+    if !open && !close {
+        return;
+    }
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if open && !close {
+        spawn_history_screen(&mut commands, &handles, &history, &filter, &levels);
+    }
+}
+
+pub fn cleanup_history(mut commands: Commands, screen_query: Query<Entity, With<HistoryScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::ShotHit;
+
+const MARKER_SECONDS: f32 = 0.25;
+/// Half the width of the X, in pixels.
+const MARKER_SIZE: f32 = 6.0;
+
+/// Impacts of the players' shots, with their age in seconds.
+#[derive(Default, Resource)]
+pub struct HitMarkers {
+    pub markers: Vec<(Vec2, f32)>,
+}
+
+pub fn spawn_hit_markers(
+    settings: Res<Settings>,
+    mut markers: ResMut<HitMarkers>,
+    mut hit_events: EventReader<ShotHit>,
+) {
+    for event in hit_events.read() {
+        if settings.hit_feedback && event.by.is_some() {
+            markers.markers.push((event.position, 0.0));
+        }
+    }
+}
+
+pub fn draw_hit_markers(time: Res<Time>, mut markers: ResMut<HitMarkers>, mut gizmos: Gizmos) {
+    for (_, age) in markers.markers.iter_mut() {
+        *age += time.delta_seconds();
+    }
+    markers.markers.retain(|(_, age)| *age < MARKER_SECONDS);
+    for (position, age) in &markers.markers {
+        let color = Color::srgba(1.0, 1.0, 1.0, 1.0 - age / MARKER_SECONDS);
+        for corner in [Vec2::ONE, Vec2::new(1.0, -1.0)] {
+            let offset = corner * MARKER_SIZE;
+            gizmos.line_2d(*position - offset, *position + offset, color);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use serde::*;
+
+use bevy::color::palettes::css::GOLD;
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::feed::FeedMessage;
+use crate::satellite::{
+    next_satellite_angle, spawn_satellite, Satellite, SATELLITE_COST, SATELLITE_MAX,
+};
+use crate::storage;
+use crate::{Spawner, Wallet};
+
+/// Fire rate of everything shooting on its own in an idle run.
+pub const IDLE_EFFICIENCY: f32 = 0.5;
+/// Offline time past this earns nothing more.
+pub const OFFLINE_CAP_SECONDS: u64 = 8 * 60 * 60;
+const OFFLINE_SCRAP_PER_MINUTE: u64 = 2;
+pub const IDLE_BANK_MAX: u32 = 1000;
+/// Seconds between two stamps of the time the game was last seen open.
+const STAMP_SECONDS: f32 = 60.0;
+
+/// Scrap earned while the game was closed, paid out at the start of the
+/// next idle run.
+#[derive(Serialize, Deserialize, Clone, Default, Resource)]
+pub struct IdleBank {
+    /// Unix seconds of the last stamp, zero if never stamped.
+    pub last_seen: u64,
+    pub scrap: u32,
+}
+
+impl IdleBank {
+    fn save(&self) {
+        if !storage::save("idle", self) {
+            warn!("failed to save the idle bank");
+        }
+    }
+
+    /// Credits the time since the last stamp and stamps now, returns the
+    /// scrap that made it into the bank.
+    pub fn catch_up(&mut self, now: u64) -> u32 {
+        let earned = if self.last_seen > 0 {
+            let seconds = now.saturating_sub(self.last_seen).min(OFFLINE_CAP_SECONDS);
+            (seconds / 60 * OFFLINE_SCRAP_PER_MINUTE) as u32
+        } else {
+            0
+        };
+        let before = self.scrap;
+        self.scrap = (self.scrap + earned).min(IDLE_BANK_MAX);
+        self.last_seen = now;
+        self.scrap - before
+    }
+}
+
+/// No clock to trust on the web, offline progress is native only.
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_now() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn unix_now() -> Option<u64> {
+    None
+}
+
+pub fn load_idle_bank(mut commands: Commands) {
+    let mut bank = storage::load::<IdleBank>("idle").unwrap_or_default();
+    if let Some(now) = unix_now() {
+        let earned = bank.catch_up(now);
+        if earned > 0 {
+            info!(earned, "offline progress");
+        }
+        bank.save();
+    }
+    commands.insert_resource(bank);
+}
+
+/// Only moves the stamp forward, the game being open isn't offline time.
+/// The scrap is credited once, when the bank is loaded.
+pub fn stamp_idle_bank(time: Res<Time>, mut bank: ResMut<IdleBank>, mut since: Local<f32>) {
+    *since += time.delta_seconds();
+    if *since < STAMP_SECONDS {
+        return;
+    }
+    *since = 0.0;
+    if let Some(now) = unix_now() {
+        bank.last_seen = now;
+        bank.save();
+    }
+}
+
+/// Pays the banked scrap into an idle run.
+pub fn setup_idle(
+    modifiers: Res<RunModifiers>,
+    mut bank: ResMut<IdleBank>,
+    mut wallet: ResMut<Wallet>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    if !modifiers.idle || bank.scrap == 0 {
+        return;
+    }
+    wallet.scrap += bank.scrap;
+    feed.send(FeedMessage::new(
+        format!("offline progress: {} scrap", bank.scrap),
+        Color::from(GOLD),
+    ));
+    bank.scrap = 0;
+    bank.save();
+}
+
+/// Which way an idle ship turns: toward the enemy closest to the planet.
+pub fn idle_steering(position: Vec2, enemies: impl Iterator<Item = Vec2>) -> f32 {
+    let target = enemies.min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+    let target = match target {
+        Some(target) => target,
+        None => return 0.0,
+    };
+    let turn = position.angle_between(target);
+    if turn.is_nan() || turn.abs() < 0.02 {
+        0.0
+    } else {
+        turn.signum()
+    }
+}
+
+/// Idle runs spend their scrap on satellites as soon as they can.
+pub fn idle_buy_satellites(
+    mut commands: Commands,
+    modifiers: Res<RunModifiers>,
+    mut wallet: ResMut<Wallet>,
+    spawner_query: Query<&Spawner>,
+    satellite_query: Query<&Satellite>,
+) {
+    if !modifiers.idle
+        || !spawner_query
+            .iter()
+            .any(|spawner| spawner.in_intermission())
+    {
+        return;
+    }
+    let purse = wallet.purse_mut(0);
+    if satellite_query.iter().count() >= SATELLITE_MAX || *purse < SATELLITE_COST {
+        return;
+    }
+    *purse -= SATELLITE_COST;
+    spawn_satellite(&mut commands, next_satellite_angle(&satellite_query));
+}
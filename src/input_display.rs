@@ -0,0 +1,158 @@
+use bevy::color::palettes::css::{GRAY, YELLOW};
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::controls::{Binding, PlayerInput, Side};
+use crate::input_trace::InputReplay;
+use crate::party::PlayerControls;
+use crate::settings::Settings;
+use crate::{AssetHandles, FontName};
+
+const KEY_SIZE: f32 = 48.0;
+
+#[derive(Component)]
+pub struct InputDisplayRoot;
+
+/// Box of one control of the first player, lit while it is held.
+#[derive(Component)]
+pub struct InputDisplayKey(usize);
+
+#[derive(Component)]
+pub struct InputDisplayFrame;
+
+/// The controls shown, in order.
+fn bindings(controls: &PlayerControls) -> [(&'static str, Binding); 5] {
+    [
+        ("left", controls.left),
+        ("right", controls.right),
+        ("fire", controls.fire),
+        ("climb", controls.climb),
+        ("parry", controls.parry),
+    ]
+}
+
+fn label(binding: Binding) -> String {
+    match binding {
+        Binding::Key(key) => format!("{:?}", key)
+            .trim_start_matches("Key")
+            .to_lowercase(),
+        Binding::Mouse(button) => format!("{:?}", button).to_lowercase(),
+        Binding::MouseZone(Side::Left) => "click l".to_string(),
+        Binding::MouseZone(Side::Right) => "click r".to_string(),
+    }
+}
+
+/// Spawns the display while a replay or a practice run is going with the
+/// setting on, and takes it down otherwise.
+pub fn show_input_display(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    settings: Res<Settings>,
+    modifiers: Res<RunModifiers>,
+    replay: Option<Res<InputReplay>>,
+    input: PlayerInput,
+    root_query: Query<Entity, With<InputDisplayRoot>>,
+) {
+    let shown = settings.input_display && (replay.is_some() || modifiers.practice.is_some());
+    if !shown {
+        for entity in &root_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    if !root_query.is_empty() {
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(15.0),
+                left: Val::Px(15.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(InputDisplayRoot)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section("", style(16.0, Color::from(GRAY))))
+                .insert(InputDisplayFrame);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, (name, binding)) in
+                        bindings(input.controls(0)).into_iter().enumerate()
+                    {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(KEY_SIZE),
+                                    height: Val::Px(KEY_SIZE),
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                },
+                                background_color: Color::srgba(0.1, 0.1, 0.1, 0.8).into(),
+                                ..default()
+                            })
+                            .insert(InputDisplayKey(index))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label(binding),
+                                    style(16.0, Color::WHITE),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    name,
+                                    style(12.0, Color::from(GRAY)),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+/// Lights the held controls, read on the same frame the game reads them,
+/// and counts the frames of the replay.
+pub fn update_input_display(
+    replay: Option<Res<InputReplay>>,
+    input: PlayerInput,
+    mut key_query: Query<(&InputDisplayKey, &mut BackgroundColor)>,
+    mut frame_query: Query<&mut Text, With<InputDisplayFrame>>,
+) {
+    let bindings = bindings(input.controls(0));
+    for (key, mut background) in &mut key_query {
+        let held = input.pressed(bindings[key.0].1);
+        *background = if held {
+            Color::from(YELLOW).with_alpha(0.8).into()
+        } else {
+            Color::srgba(0.1, 0.1, 0.1, 0.8).into()
+        };
+    }
+    if let Ok(mut text) = frame_query.get_single_mut() {
+        text.sections[0].value = match &replay {
+            Some(replay) => format!("replay frame {}/{}", replay.frame, replay.trace.frames()),
+            None => String::new(),
+        };
+    }
+}
@@ -0,0 +1,122 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::storage;
+
+/// Keys held down for a number of consecutive frames.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputSpan {
+    pub frames: u32,
+    pub keys: Vec<KeyCode>,
+}
+
+/// Keyboard state frame by frame, recorded during play and fed back to
+/// the game by the headless regression tests.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct InputTrace {
+    pub spans: Vec<InputSpan>,
+}
+
+impl InputTrace {
+    fn push(&mut self, mut keys: Vec<KeyCode>) {
+        keys.sort();
+        match self.spans.last_mut() {
+            Some(last) if last.keys == keys => last.frames += 1,
+            _ => self.spans.push(InputSpan { frames: 1, keys }),
+        }
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.spans.iter().map(|span| span.frames).sum()
+    }
+
+    /// Keys held on the given frame, none past the end of the trace.
+    pub fn keys_at(&self, mut frame: u32) -> Option<&[KeyCode]> {
+        for span in &self.spans {
+            if frame < span.frames {
+                return Some(&span.keys);
+            }
+            frame -= span.frames;
+        }
+        None
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct InputRecorder {
+    pub recording: bool,
+    pub trace: InputTrace,
+}
+
+/// Trace played back instead of the keyboard.
+#[derive(Resource)]
+pub struct InputReplay {
+    pub trace: InputTrace,
+    pub frame: u32,
+}
+
+impl InputReplay {
+    pub fn new(trace: InputTrace) -> Self {
+        InputReplay { trace, frame: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.trace.frames()
+    }
+}
+
+/// F9 starts a recording, pressing it again saves it as "trace".
+pub fn toggle_recording(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut recorder: ResMut<InputRecorder>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    recorder.recording = !recorder.recording;
+    if recorder.recording {
+        recorder.trace = InputTrace::default();
+    } else if !storage::save("trace", &recorder.trace) {
+        warn!("failed to save the input trace");
+    }
+}
+
+pub fn record_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut recorder: ResMut<InputRecorder>,
+) {
+    if !recorder.recording {
+        return;
+    }
+    let keys = keyboard_input
+        .get_pressed()
+        .copied()
+        .filter(|key| *key != KeyCode::F9)
+        .collect();
+    recorder.trace.push(keys);
+}
+
+/// Presses and releases keys to match the current frame of the trace.
+pub fn replay_input(
+    mut replay: ResMut<InputReplay>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+) {
+    let keys = replay
+        .trace
+        .keys_at(replay.frame)
+        .map(|keys| keys.to_vec())
+        .unwrap_or_default();
+    let released: Vec<KeyCode> = keyboard_input
+        .get_pressed()
+        .copied()
+        .filter(|key| !keys.contains(key))
+        .collect();
+    for key in released {
+        keyboard_input.release(key);
+    }
+    for key in keys {
+        keyboard_input.press(key);
+    }
+    replay.frame += 1;
+}
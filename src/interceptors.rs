@@ -0,0 +1,75 @@
+use bevy::color::palettes::css::{LIGHT_SKY_BLUE, ORANGE_RED};
+use bevy::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::enemies::TargetingMode;
+use crate::feed::FeedMessage;
+use crate::gamepad::Rumble;
+use crate::{Enemy, Player};
+
+/// Cruise speed of the enemies hunting players, in pixels per second.
+pub const INTERCEPTOR_SPEED: f32 = 180.0;
+/// How fast they turn toward the intercept point, per second.
+pub const INTERCEPTOR_AGILITY: f32 = 3.0;
+const INTERCEPT_RADIUS: f32 = 16.0;
+/// A player hit by an interceptor can't shoot for this long.
+const INTERCEPT_STUN: f32 = 1.5;
+/// Refinements of the predicted meeting point.
+const PREDICTION_STEPS: usize = 3;
+
+/// Where a hunter flying at `speed` from `from` meets a player orbiting
+/// the origin at `angular_velocity`: the player is moved along the orbit
+/// by the time it takes to get there, a few times over.
+pub fn intercept_point(from: Vec2, speed: f32, player: Vec2, angular_velocity: f32) -> Vec2 {
+    let radius = player.length();
+    let angle = player.y.atan2(player.x);
+    let mut aim = player;
+    for _ in 0..PREDICTION_STEPS {
+        let seconds = from.distance(aim) / speed.max(1.0);
+        let ahead = angle + angular_velocity * seconds;
+        aim = Vec2::new(ahead.cos(), ahead.sin()) * radius;
+    }
+    aim
+}
+
+/// Interceptors that reach a player knock their guns out and are spent.
+pub fn intercept_players(
+    mut enemy_query: Query<(&mut Enemy, &Transform)>,
+    mut player_query: Query<(&mut Player, &Transform), Without<Enemy>>,
+    mut feed: EventWriter<FeedMessage>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut rumble_events: EventWriter<Rumble>,
+) {
+    for (mut enemy, enemy_trans) in &mut enemy_query {
+        if enemy.has_hit > 0 || enemy.targeting != TargetingMode::Player {
+            continue;
+        }
+        let position = enemy_trans.translation.truncate();
+        for (mut player, player_trans) in &mut player_query {
+            if player_trans.translation.truncate().distance(position) > INTERCEPT_RADIUS {
+                continue;
+            }
+            enemy.has_hit = 1;
+            // a parry turns the interceptor away before it connects
+            if player.parry > 0.0 {
+                feed.send(FeedMessage::new(
+                    format!("p{} parried", player.index + 1),
+                    Color::from(LIGHT_SKY_BLUE),
+                ));
+                break;
+            }
+            player.stun = INTERCEPT_STUN;
+            feed.send(FeedMessage::new(
+                format!("p{} intercepted", player.index + 1),
+                Color::from(ORANGE_RED),
+            ));
+            sound_events.send(PlaySound {
+                sound: SoundName::Impact,
+                position: Some(position),
+                caption: Some("warning: intercepted"),
+            });
+            rumble_events.send(Rumble::new(0.8, 250));
+            break;
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::composer::{self, EnemyDefinitions};
+use crate::conditions::{PracticeLoadout, RunModifiers};
+use crate::input_trace::InputTrace;
+use crate::spawner::Challenge;
+use crate::AppState;
+
+pub const USAGE: &str = "usage: planet-td [options]
+  --seed <n>          seed of the generated waves
+  --wave <n>          first wave of every run, counted from 1
+  --challenge <file>  play a challenge file instead of the level waves
+  --windowed          start in a window (default)
+  --fullscreen        start fullscreen
+  --mute              no music and no sound effects
+  --bench             let a bot play for a minute, then print the frame times
+  --replay <file>     play back an input trace recorded with f9
+  --export <dir>      save every frame of the --replay to dir, at a fixed 60 fps
+  --validate <file>   check a challenge file and print its difficulty";
+
+/// Seconds of play measured by --bench.
+const BENCH_SECONDS: f32 = 60.0;
+
+/// Command line configuration of the game, so that test scenarios don't
+/// need code changes.
+#[derive(Default, Clone, Resource)]
+pub struct LaunchOptions {
+    pub seed: Option<u64>,
+    /// Index of the first wave.
+    pub wave: Option<usize>,
+    pub challenge: Option<Challenge>,
+    pub fullscreen: bool,
+    pub mute: bool,
+    pub bench: bool,
+    /// Played back instead of the keyboard.
+    pub replay: Option<InputTrace>,
+    /// Directory the frames of the replay are saved to.
+    pub export: Option<String>,
+}
+
+impl LaunchOptions {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<LaunchOptions, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--seed" => {
+                    options.seed = Some(value()?.parse().map_err(|_| "--seed <n>")?);
+                }
+                "--wave" => {
+                    let wave: usize = value()?.parse().map_err(|_| "--wave <n>")?;
+                    options.wave = Some(wave.max(1) - 1);
+                }
+                "--challenge" => {
+                    let path = value()?;
+                    let json = std::fs::read_to_string(&path)
+                        .map_err(|err| format!("{}: {}", path, err))?;
+                    let challenge = composer::validate(&json, &EnemyDefinitions::default())
+                        .map_err(|errors| format!("{}: {}", path, errors.join(", ")))?;
+                    options.challenge = Some(challenge);
+                }
+                "--windowed" => options.fullscreen = false,
+                "--fullscreen" => options.fullscreen = true,
+                "--mute" => options.mute = true,
+                "--bench" => options.bench = true,
+                "--replay" => {
+                    let path = value()?;
+                    let json = std::fs::read_to_string(&path)
+                        .map_err(|err| format!("{}: {}", path, err))?;
+                    let trace =
+                        serde_json::from_str(&json).map_err(|err| format!("{}: {}", path, err))?;
+                    options.replay = Some(trace);
+                }
+                "--export" => {
+                    let dir = value()?;
+                    std::fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir, err))?;
+                    options.export = Some(dir);
+                }
+                "--server" => return Err("--server: the game has no network play".to_string()),
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+        if options.export.is_some() && options.replay.is_none() {
+            return Err("--export needs a --replay".to_string());
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct Bench {
+    pub frames: u32,
+    pub seconds: f32,
+    pub worst: f32,
+}
+
+/// Skips the menu, the benchmark runs don't end up in the profile.
+pub fn start_bench(mut modifiers: ResMut<RunModifiers>, mut state: ResMut<NextState<AppState>>) {
+    modifiers.practice = Some(PracticeLoadout::default());
+    state.set(AppState::InGame);
+}
+
+pub fn measure_bench(
+    time: Res<Time<Real>>,
+    mut bench: ResMut<Bench>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let dt = time.delta_seconds();
+    bench.frames += 1;
+    bench.seconds += dt;
+    bench.worst = bench.worst.max(dt);
+    if bench.seconds < BENCH_SECONDS {
+        return;
+    }
+    println!(
+        "{} frames in {:.1}s, {:.1} fps, {:.2}ms average, {:.2}ms worst",
+        bench.frames,
+        bench.seconds,
+        bench.frames as f32 / bench.seconds,
+        bench.seconds / bench.frames as f32 * 1000.0,
+        bench.worst * 1000.0
+    );
+    exit_events.send(AppExit::Success);
+}
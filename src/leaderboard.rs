@@ -0,0 +1,114 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::checkpoints::RunCheckpoint;
+use crate::conditions::{RunClock, RunModifiers, RunOutcome};
+use crate::level::{CurrentLevel, Levels};
+use crate::score::{RunStats, ScoreBreakdown};
+use crate::settings::Settings;
+use crate::storage;
+use crate::Planet;
+
+const LEADERBOARD_SIZE: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LeaderboardEntry {
+    pub level: String,
+    pub score: u32,
+    pub victory: bool,
+    pub wave: usize,
+    pub seconds: f32,
+    pub speedrun: bool,
+    pub ng_plus: u32,
+    /// Run time at the end of each wave.
+    pub splits: Vec<f32>,
+    /// Played with the assist options.
+    #[serde(default)]
+    pub assisted: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Resource)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Leaderboard {
+        storage::load("leaderboard").unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if !storage::save("leaderboard", self) {
+            warn!("failed to save the leaderboard");
+        }
+    }
+
+    pub fn insert(&mut self, entry: LeaderboardEntry) {
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        // keep the best entries of every level
+        let mut kept: Vec<LeaderboardEntry> = vec![];
+        for entry in self.entries.drain(..) {
+            if kept.iter().filter(|e| e.level == entry.level).count() < LEADERBOARD_SIZE {
+                kept.push(entry);
+            }
+        }
+        self.entries = kept;
+    }
+
+    /// Splits to compare the current run against: the fastest run that
+    /// went at least as far, in the same mode and without assists.
+    pub fn reference_splits(&self, level: &str, speedrun: bool) -> Option<&[f32]> {
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.level == level && e.speedrun == speedrun && !e.assisted && !e.splits.is_empty()
+            })
+            .max_by(|a, b| {
+                a.splits
+                    .len()
+                    .cmp(&b.splits.len())
+                    .then(b.seconds.total_cmp(&a.seconds))
+            })
+            .map(|e| e.splits.as_slice())
+    }
+}
+
+pub fn record_run(
+    outcome: Res<RunOutcome>,
+    stats: Res<RunStats>,
+    clock: Res<RunClock>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    mut leaderboard: ResMut<Leaderboard>,
+    checkpoint: Res<RunCheckpoint>,
+    settings: Res<Settings>,
+    planet_query: Query<&Planet>,
+) {
+    let assisted = settings.assist.active();
+    if modifiers.practice.is_some() || (assisted && !settings.assist.leaderboard) {
+        return;
+    }
+    let planet_hp = planet_query.get_single().map(|p| p.hp).unwrap_or(0.0);
+    let score =
+        ScoreBreakdown::compute(&stats, &outcome, planet_hp, checkpoint.score_multiplier()).total();
+    let entry = LeaderboardEntry {
+        level: levels.list[current_level.0].name.clone(),
+        score,
+        victory: outcome.victory,
+        wave: outcome.wave,
+        seconds: outcome.seconds,
+        speedrun: modifiers.speedrun,
+        ng_plus: modifiers.ng_plus,
+        splits: clock.splits.clone(),
+        assisted,
+    };
+    // the last run's splits, for comparing runs outside of the game
+    if !storage::save("splits", &entry) {
+        warn!("failed to export the splits");
+    }
+    leaderboard.insert(entry);
+    leaderboard.save();
+}
@@ -0,0 +1,176 @@
+use serde::*;
+
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::biomes::Biome;
+use crate::conditions::{default_defeat, DefeatCondition, VictoryCondition};
+use crate::spawner::{Challenge, ScriptedEvent, SpawnBand};
+use crate::teams::{Role, Team};
+use crate::units::UNIT_RADIUS;
+use crate::{AssetHandles, MaterialName, MeshName, TimeScale};
+
+// level data is embedded so that the wasm build doesn't need to fetch it
+const LEVEL_FILES: [&str; 3] = [
+    include_str!("../assets/levels/terra.json"),
+    include_str!("../assets/levels/moonbase.json"),
+    include_str!("../assets/levels/belt.json"),
+];
+
+const CHALLENGE_FILES: [(&str, &str); 1] =
+    [("simple", include_str!("../assets/challenges/simple.json"))];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    Moon,
+    Asteroid,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HazardDefinition {
+    pub kind: HazardKind,
+    pub orbit: f32,
+    pub size: f32,
+    pub angle: f32,
+    pub speed: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelDefinition {
+    pub name: String,
+    pub planet_size: f32,
+    pub planet_image: String,
+    pub spawner_size: f32,
+    pub sky_color: [f32; 3],
+    pub hazards: Vec<HazardDefinition>,
+    /// Ids of the upgrades the drafts offer, all of them if empty.
+    #[serde(default)]
+    pub upgrades: Vec<String>,
+    #[serde(default)]
+    pub victory: VictoryCondition,
+    #[serde(default = "default_defeat")]
+    pub defeat: Vec<DefeatCondition>,
+    /// Name of a file in assets/challenges, the waves are generated if unset.
+    #[serde(default)]
+    pub challenge: Option<String>,
+    #[serde(default)]
+    pub biome: Biome,
+    /// Scripted events of the generated waves, challenge files have their own.
+    #[serde(default)]
+    pub events: Vec<ScriptedEvent>,
+}
+
+impl LevelDefinition {
+    pub fn challenge(&self, seed: u64, bands: &[SpawnBand]) -> Challenge {
+        let name = match &self.challenge {
+            Some(name) => name,
+            None => {
+                return Challenge {
+                    events: self.events.clone(),
+                    ..Challenge::new(seed, bands)
+                }
+            }
+        };
+        let (_, file) = CHALLENGE_FILES
+            .iter()
+            .find(|(file_name, _)| file_name == name)
+            .expect("unknown challenge file");
+        Challenge::from_json(file).expect("invalid challenge file")
+    }
+
+    pub fn sky_color(&self) -> Color {
+        Color::srgb(self.sky_color[0], self.sky_color[1], self.sky_color[2])
+    }
+}
+
+#[derive(Resource)]
+pub struct Levels {
+    pub list: Vec<LevelDefinition>,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Levels {
+            list: LEVEL_FILES
+                .iter()
+                .map(|file| serde_json::from_str(file).expect("invalid level file"))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct CurrentLevel(pub usize);
+
+impl Default for CurrentLevel {
+    fn default() -> Self {
+        CurrentLevel(0)
+    }
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    pub orbit: f32,
+    pub angle: f32,
+    pub speed: f32,
+}
+
+pub fn spawn_hazards(commands: &mut Commands, handles: &AssetHandles, level: &LevelDefinition) {
+    for hazard in level.hazards.iter() {
+        spawn_hazard(commands, handles, hazard);
+    }
+}
+
+pub fn spawn_hazard(commands: &mut Commands, handles: &AssetHandles, hazard: &HazardDefinition) {
+    let material = match hazard.kind {
+        HazardKind::Moon => MaterialName::Moon,
+        HazardKind::Asteroid => MaterialName::Asteroid,
+    };
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: handles
+                .meshes
+                .get(&MeshName::Circle)
+                .unwrap()
+                .clone_weak()
+                .into(),
+            transform: Transform {
+                translation: Vec3::new(
+                    f32::cos(hazard.angle) * hazard.orbit,
+                    f32::sin(hazard.angle) * hazard.orbit,
+                    1.0,
+                ),
+                scale: Vec3::new(hazard.size, hazard.size, 1.0),
+                ..default()
+            },
+            material: handles.materials.get(&material).unwrap().clone_weak(),
+            ..default()
+        })
+        .insert(RigidBody::KinematicPositionBased)
+        // the mesh is scaled, so the collider is expressed in unit size
+        .insert(Collider::ball(UNIT_RADIUS))
+        .insert(Team::Neutral)
+        .insert(Team::Neutral.collision_groups(Role::Body))
+        .insert(Hazard {
+            orbit: hazard.orbit,
+            angle: hazard.angle,
+            speed: hazard.speed,
+        });
+}
+
+pub fn move_hazards(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut hazard_query: Query<(&mut Hazard, &mut Transform)>,
+) {
+    for (mut hazard, mut transform) in &mut hazard_query {
+        hazard.angle += hazard.speed * time_scale.delta_seconds(&time);
+        transform.translation = Vec3::new(
+            f32::cos(hazard.angle) * hazard.orbit,
+            f32::sin(hazard.angle) * hazard.orbit,
+            transform.translation.z,
+        );
+    }
+}
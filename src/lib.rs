@@ -0,0 +1,2251 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use serde::*;
+
+use bevy::color::palettes::css::{BLUE, GRAY, PURPLE, RED};
+use bevy::ecs::batching::BatchingStrategy;
+use bevy::ecs::world::Command;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use bevy::time::TimeUpdateStrategy;
+
+use bevy_rapier2d::{pipeline::CollisionEvent::*, prelude::*};
+
+mod allies;
+mod attachments;
+mod audio;
+mod autosave;
+pub mod balance;
+mod beam;
+mod biomes;
+mod bonuses;
+mod boss;
+mod bug_report;
+mod bullet_time;
+mod captions;
+mod celebration;
+mod checkpoints;
+mod codex;
+pub mod composer;
+mod conditions;
+mod construction;
+pub mod controls;
+mod culling;
+mod damage_indicators;
+mod daynight;
+mod debug;
+mod director;
+mod draw_calls;
+mod enemies;
+mod enemy_grid;
+mod enemy_looks;
+mod feed;
+mod focus;
+mod gamepad;
+mod ghost;
+mod graze;
+mod gunships;
+pub mod headless;
+mod heatmap;
+mod history;
+mod hit_markers;
+pub mod idle;
+mod input_display;
+pub mod input_trace;
+mod interceptors;
+pub mod launch;
+mod leaderboard;
+mod level;
+mod loadouts;
+mod loot;
+mod menu;
+mod mines;
+mod parry;
+mod party;
+mod profile;
+mod random_events;
+mod repair;
+mod reticle;
+mod retreat;
+mod revival;
+mod rewind;
+pub mod run_link;
+mod run_timer;
+mod sandbox;
+mod satellite;
+mod score;
+mod settings;
+mod shields;
+mod shop_alert;
+mod shutdown;
+mod snapshot;
+pub mod spawner;
+mod storage;
+mod story;
+mod swarm;
+mod targeting;
+mod teams;
+mod textures;
+mod ultimate;
+mod units;
+mod upgrades;
+mod victory;
+mod video_export;
+mod viewport;
+mod window;
+mod zones;
+
+use allies::*;
+use attachments::*;
+use audio::*;
+use autosave::*;
+use balance::*;
+use beam::*;
+use biomes::*;
+use bonuses::*;
+use boss::*;
+use bug_report::*;
+use bullet_time::*;
+use captions::*;
+use celebration::*;
+use checkpoints::*;
+use codex::*;
+use conditions::*;
+use construction::*;
+use controls::*;
+use culling::*;
+use damage_indicators::*;
+use daynight::*;
+use debug::*;
+use director::*;
+use draw_calls::*;
+use enemies::*;
+use enemy_grid::*;
+use enemy_looks::*;
+use feed::*;
+use focus::*;
+use gamepad::*;
+use ghost::*;
+use graze::*;
+use gunships::*;
+use heatmap::*;
+use history::*;
+use hit_markers::*;
+use idle::*;
+use input_display::*;
+use input_trace::*;
+use interceptors::*;
+use launch::*;
+use leaderboard::*;
+use level::*;
+use loadouts::*;
+use loot::*;
+use menu::*;
+use mines::*;
+use parry::*;
+use party::*;
+use profile::*;
+use random_events::*;
+use repair::*;
+use reticle::*;
+use retreat::*;
+use revival::*;
+use rewind::*;
+use run_link::*;
+use run_timer::*;
+use sandbox::*;
+use satellite::*;
+use score::*;
+use settings::*;
+use shields::*;
+use shop_alert::*;
+use shutdown::*;
+use spawner::*;
+use story::*;
+use swarm::*;
+use targeting::*;
+use teams::*;
+use textures::*;
+use ultimate::*;
+use units::*;
+use upgrades::*;
+use victory::*;
+use video_export::*;
+use viewport::*;
+use window::*;
+use zones::*;
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AppState {
+    #[default]
+    Menu,
+    InGame,
+    /// Passed through by a reset, so that the setup of InGame runs again.
+    Restart,
+}
+
+/// Phase of a run, the outcome screens are shown over the paused run
+/// so that an endless continuation can resume it.
+#[derive(SubStates, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[source(AppState = AppState::InGame)]
+enum RunPhase {
+    #[default]
+    Playing,
+    Victory,
+    GameOver,
+}
+
+/// Runs the game in a window.
+pub fn run(options: LaunchOptions) {
+    let window_config = WindowConfig {
+        fullscreen: options.fullscreen,
+        ..default()
+    };
+    let bench = options.bench;
+    let replay = options.replay.clone();
+    let export = options.export.clone();
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(window_config.window()),
+                ..default()
+            })
+            .set(log_plugin()),
+    )
+    .insert_resource(window_config)
+    .insert_resource(options);
+    add_game(&mut app);
+    add_draw_call_counter(&mut app);
+    if bench {
+        app.init_resource::<Bench>()
+            .add_systems(Startup, start_bench)
+            .add_systems(Update, measure_bench);
+        headless::add_bot(&mut app);
+    }
+    if let Some(trace) = replay {
+        app.insert_resource(InputReplay::new(trace))
+            .add_systems(Startup, start_replay);
+        if let Some(dir) = export {
+            // stepped like the headless app, the frames don't depend on
+            // how long they take to render
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(headless::HEADLESS_FRAME))
+                .insert_resource(FrameExport { dir, frame: 0 })
+                .add_systems(Last, export_frames);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(Startup, set_window_icon);
+
+    app.run();
+}
+
+/// Default log filter, RUST_LOG replaces it. The damage pipeline logs
+/// every hit at debug level on the planet_td::damage target, e.g.
+/// RUST_LOG=planet_td=info,planet_td::damage=debug
+const LOG_FILTER: &str = "wgpu=error,naga=warn,planet_td=info,planet_td::damage=warn";
+
+fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        filter: LOG_FILTER.to_string(),
+        ..default()
+    }
+}
+
+/// Everything but the window and the platform plugins, shared with the
+/// headless app.
+fn add_game(app: &mut App) {
+    // The plugin is added before the balance resource is inserted.
+    let scale = Balance::default().physics.pixels_per_meter;
+    app.add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(scale))
+        .insert_resource(ClearColor(Color::srgb(0.02, 0.02, 0.02)))
+        .init_state::<AppState>()
+        .add_sub_state::<RunPhase>()
+        .add_systems(
+            Startup,
+            (
+                load_assets,
+                load_music,
+                load_sfx,
+                configure_physics,
+                capture_game_keys,
+                flush_on_unload,
+                setup_click_to_start.after(load_assets),
+                setup_letterbox,
+                setup_captions,
+                read_run_link,
+                load_autosave,
+                load_idle_bank,
+            ),
+        )
+        .add_systems(
+            PreUpdate,
+            replay_input
+                .after(bevy::input::InputSystem)
+                .run_if(resource_exists::<InputReplay>),
+        )
+        .add_systems(
+            Update,
+            (
+                (frame_camera, update_letterbox, anchor_to_safe_area).chain(),
+                resolve_textures,
+                click_to_start,
+                start_music,
+                fade_music,
+                play_sounds,
+                play_rumble,
+                reset_run,
+                toggle_recording,
+                record_input,
+                log_report_events,
+                request_bug_report,
+                show_bug_report_form,
+                bug_report_input,
+                stamp_idle_bank,
+                (push_captions, fade_captions),
+                (draw_heatmap, pan_camera).run_if(in_state(AppState::InGame)),
+                (show_input_display, update_input_display)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            ),
+        )
+        .add_systems(
+            OnEnter(AppState::Menu),
+            (
+                setup_menu,
+                setup_resume_button.after(setup_menu),
+                setup_loadout_buttons.after(setup_menu),
+            ),
+        )
+        .add_systems(
+            Update,
+            launch_run_link
+                .run_if(in_state(AppState::Menu))
+                .run_if(resource_exists::<RunLink>),
+        )
+        .add_systems(
+            Update,
+            (
+                menu_interaction,
+                menu_options_interaction,
+                codex_interaction,
+                history_interaction,
+                resume_interaction,
+                loadout_interaction,
+                name_loadout,
+                show_loadouts,
+                update_option_labels,
+            )
+                .run_if(in_state(AppState::Menu)),
+        )
+        .add_systems(
+            OnExit(AppState::Menu),
+            (cleanup_menu, cleanup_codex, cleanup_history, clear_autosave),
+        )
+        .add_systems(OnEnter(AppState::Restart), restart_run)
+        .add_systems(
+            OnEnter(AppState::InGame),
+            (
+                setup,
+                setup_daynight,
+                setup_random_events,
+                setup_construction,
+                (
+                    setup_ultimate,
+                    setup_bullet_time,
+                    setup_heatmap,
+                    setup_boss_encounter,
+                    setup_attachments,
+                    setup_revival,
+                    setup_loot.after(setup),
+                    setup_shop_alert,
+                ),
+                setup_celebration,
+                setup_feed,
+                setup_conditions,
+                setup_run_timer,
+                setup_sandbox,
+                setup_ghost,
+                setup_party,
+                setup_checkpoint.after(setup),
+                setup_biome,
+                write_run_link.after(setup),
+                setup_story.after(setup),
+                discover_run_codex,
+                setup_director,
+                (
+                    setup_rewind,
+                    setup_enemy_looks,
+                    setup_upgrades,
+                    setup_repair,
+                    setup_idle.after(setup),
+                ),
+                restore_autosave.after(setup).after(setup_upgrades),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (parry_input, movement).chain(),
+                assign_targets.before(move_enemies),
+                (move_enemies, flock_swarms.after(rebuild_enemy_grid)),
+                leash_enemies,
+                move_hazards,
+                shooting,
+                move_sensor_bullets,
+                bullet_clean,
+                enemy_clean,
+                spawn_enemies,
+                release_spawns,
+                (
+                    sound_retreat.after(spawn_enemies).before(release_spawns),
+                    retreat_enemies,
+                ),
+                skip_intermission,
+                update_ui_wave,
+                update_daynight,
+                update_random_events,
+                build_menu_input,
+                update_build_menu,
+                update_ui_scrap,
+            )
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                buy_satellite,
+                orbit_satellites,
+                satellite_shooting,
+                charge_ultimate,
+                activate_ultimate,
+                update_ultimate,
+                update_ui_ultimate,
+                track_wave_stats,
+                start_celebration,
+                update_celebration,
+                (
+                    update_bullet_time.before(apply_time_scale),
+                    update_ui_bullet_time,
+                ),
+                apply_time_scale,
+                feed_from_events,
+                push_feed_entries,
+                fade_feed_entries,
+                track_combo,
+                music_intensity,
+                evaluate_conditions,
+                track_run_stats,
+            )
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                record_splits,
+                toggle_run_timer,
+                update_run_timer,
+                sandbox_input,
+                sandbox_spawns,
+                toggle_debug_overlay,
+                draw_enemy_debug,
+                cull_enemies,
+                record_ghost,
+                move_ghost,
+                friendly_fire,
+                track_party_stats,
+                update_party_hud,
+                damage_zones,
+                reach_checkpoint,
+                (detect_grazes, award_kill_bonuses).chain(),
+                (
+                    drop_enemy_mines,
+                    update_mines,
+                    fire_beams.after(rebuild_enemy_grid),
+                    fire_guns,
+                ),
+                animate_bonus_popups,
+                regen_shields,
+                draw_enemy_bars,
+            )
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(
+            Update,
+            rebuild_enemy_grid
+                .before(shooting)
+                .before(satellite_shooting)
+                .before(ally_shooting)
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                icy_slide.run_if(biome_is(Biome::Icy)),
+                volcanic_eruptions.run_if(biome_is(Biome::Volcanic)),
+                atmosphere_drag.run_if(biome_is(Biome::GasGiant)),
+            )
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                (direct_events, queue_vignettes, update_cutscene).chain(),
+                discover_codex,
+                spawn_hit_markers,
+                draw_hit_markers,
+                draw_trajectory_preview,
+                buy_ally,
+                move_allies,
+                ally_shooting,
+                take_snapshots,
+                rewind_on_death.before(evaluate_conditions),
+                autosave_wave,
+                (
+                    spawn_damage_indicators,
+                    accumulate_heatmap,
+                    (
+                        watch_planet_while_shopping,
+                        return_to_battle,
+                        show_shop_alert,
+                    )
+                        .chain(),
+                ),
+                (start_boss_encounter, update_boss_encounter).chain(),
+                (
+                    record_deaths,
+                    start_channels,
+                    update_channels,
+                    strip_revived_bounty,
+                    draw_channels,
+                ),
+                (
+                    drop_attachments,
+                    collect_attachments,
+                    (
+                        toggle_attachment_screen,
+                        attachment_interaction,
+                        show_attachment_screen,
+                    )
+                        .chain(),
+                ),
+                draw_damage_indicators,
+                (
+                    (
+                        offer_upgrades,
+                        draft_interaction,
+                        evaluate_synergies,
+                        show_draft,
+                    )
+                        .chain(),
+                    toggle_synergy_browser,
+                    (repair_minigame, draw_repair_minigame).chain(),
+                    idle_buy_satellites,
+                ),
+            )
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(OnEnter(RunPhase::Playing), resume_physics)
+        .add_systems(OnExit(RunPhase::Playing), silence_alarm)
+        .add_systems(
+            OnEnter(RunPhase::Victory),
+            (
+                pause_physics,
+                record_run,
+                record_history,
+                save_ghost,
+                setup_victory_screen,
+                clear_autosave,
+            ),
+        )
+        .add_systems(
+            Update,
+            victory_interaction.run_if(in_state(RunPhase::Victory)),
+        )
+        .add_systems(OnExit(RunPhase::Victory), cleanup_victory_screen)
+        .add_systems(
+            OnEnter(RunPhase::GameOver),
+            (
+                pause_physics,
+                record_run,
+                record_history,
+                save_ghost,
+                setup_game_over_screen,
+                clear_autosave,
+                play_game_over_sting,
+            ),
+        )
+        .add_systems(Update, game_over_input.run_if(in_state(RunPhase::GameOver)))
+        .add_systems(Update, quick_restart.run_if(in_state(RunPhase::Playing)))
+        .add_systems(
+            PostUpdate,
+            (
+                reflect_shots.before(collision_resolve),
+                collision_resolve,
+                shots_hit_players.after(reflect_shots),
+                structure_collisions,
+                satellite_collisions,
+                ally_collisions,
+                intercept_players,
+            )
+                .after(PhysicsSet::Writeback)
+                .run_if(in_state(RunPhase::Playing)),
+        )
+        .add_systems(PostUpdate, vary_enemies.run_if(in_state(AppState::InGame)))
+        .add_systems(Last, (stage_saves, flush_on_exit).chain())
+        .init_resource::<AssetHandles>()
+        .init_resource::<Levels>()
+        .init_resource::<CurrentLevel>()
+        .init_resource::<EnemyDefinitions>()
+        .init_resource::<Balance>()
+        .init_resource::<CodexLore>()
+        .init_resource::<HitMarkers>()
+        .init_resource::<DamageIndicators>()
+        .init_resource::<UpgradeDefinitions>()
+        .init_resource::<RunUpgrades>()
+        .init_resource::<UpgradeDraft>()
+        .init_resource::<RepairMinigame>()
+        .add_event::<EnemyKilled>()
+        .add_event::<RetreatSounded>()
+        .add_event::<WaveEnded>()
+        .add_event::<ShotFired>()
+        .add_event::<ShotHit>()
+        .add_event::<PlanetDamaged>()
+        .add_event::<KillBonus>()
+        .add_event::<FeedMessage>()
+        .add_event::<PlaySound>()
+        .add_event::<Rumble>()
+        .add_event::<ResetRun>()
+        .add_event::<SpawnEnemy>()
+        .add_event::<SpawnHazard>()
+        .add_event::<GrantPowerup>()
+        .insert_resource(Settings::load())
+        .insert_resource(Story::load())
+        .init_resource::<RunModifiers>()
+        .init_resource::<LaunchOptions>()
+        .insert_resource(Profile::load())
+        .insert_resource(Leaderboard::load())
+        .insert_resource(RunHistory::load())
+        .init_resource::<HistoryFilter>()
+        .init_resource::<MenuOptions>()
+        .init_resource::<LoadoutNaming>()
+        .init_resource::<DebugOverlay>()
+        .init_resource::<CullingStats>()
+        .init_resource::<EnemyGrid>()
+        .init_resource::<TimeScale>()
+        .init_resource::<CameraZoom>()
+        .init_resource::<SafeArea>()
+        .init_resource::<Mixer>()
+        .init_resource::<InputCapture>()
+        .init_resource::<PendingSaves>()
+        .init_resource::<InputRecorder>()
+        .init_resource::<ReportLog>()
+        .init_resource::<BugReportForm>();
+}
+
+fn configure_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.gravity = Vec2::ZERO;
+}
+
+/// Index of the wave in progress, recorded on the tracing spans.
+fn current_wave(spawner_query: &Query<&Spawner>) -> usize {
+    spawner_query
+        .iter()
+        .next()
+        .map_or(0, |spawner| spawner.current_wave)
+}
+
+// dynamic asset storage
+
+#[derive(Eq, Hash, PartialEq)]
+enum MeshName {
+    Circle,
+    Triangle,
+    Capsule,
+}
+
+#[derive(Eq, Hash, PartialEq)]
+enum MaterialName {
+    Sky,
+    Planet,
+    Player,
+    Enemy,
+    Moon,
+    Asteroid,
+    NightTint,
+    Zone,
+}
+
+#[derive(Eq, Hash, PartialEq)]
+enum FontName {
+    IosevkaRegular,
+}
+
+#[derive(Eq, Hash, PartialEq, Clone, Copy)]
+enum ImageName {
+    Planet,
+    Player,
+    /// Enemies and bullets, packed together so that they draw in one batch.
+    Sprites,
+}
+
+const SPRITE_ENEMY: usize = 0;
+const SPRITE_BULLET: usize = 1;
+/// Side of a cell of the sprite sheet, in pixels.
+const SPRITE_CELL: f32 = 128.0;
+/// Shared depth of the enemies and the bullets, anything drawn in between
+/// would split their batch.
+const BATCH_LAYER: f32 = 3.0;
+
+#[derive(Default, Resource)]
+struct AssetHandles {
+    meshes: HashMap<MeshName, Handle<Mesh>>,
+    materials: HashMap<MaterialName, Handle<ColorMaterial>>,
+    fonts: HashMap<FontName, Handle<Font>>,
+    images: HashMap<ImageName, Handle<Image>>,
+    sprite_layout: Handle<TextureAtlasLayout>,
+    /// Resolution of the images, see the textures module.
+    texture_variant: TextureVariant,
+    /// A @2x file failed to load, only the @1x set is used from then on.
+    hd_missing: bool,
+}
+
+// game components
+
+#[derive(Component)]
+struct Planet {
+    size: f32,
+    hp: f32,
+    max_hp: f32,
+}
+
+#[derive(Component)]
+struct Player {
+    /// Slot in the party, picks the controls and the color.
+    index: usize,
+    speed: f32,
+    timer: Timer,
+    /// Seconds left on a buffered fire press.
+    fire_buffer: f32,
+    /// Seconds left before the player can shoot again after friendly fire.
+    stun: f32,
+    /// Orbit picked, an index of ORBIT_ALTITUDES.
+    altitude: usize,
+    /// Current height above the rim, climbing toward the altitude.
+    height: f32,
+    /// Radians per second along the orbit, for the enemies leading it.
+    angular_velocity: f32,
+    /// Seconds left of the parry window, see the parry module.
+    parry: f32,
+    /// Seconds before the player can parry again.
+    parry_cooldown: f32,
+}
+
+#[derive(Component)]
+struct Bullet {
+    damage: f32,
+    /// Where it was fired from, the damage falls off with the distance.
+    origin: Vec2,
+    range: f32,
+    falloff: f32,
+    has_hit: u8,
+    /// Integrated by hand for sensor projectiles.
+    velocity: Option<Vec2>,
+    /// Player who fired it, none for satellites and enemies.
+    owner: Option<usize>,
+    zone: Option<ZoneBalance>,
+    /// Enemies left to pass through.
+    pierce: u8,
+}
+
+impl Bullet {
+    fn damage_at(&self, pos: Vec2) -> f32 {
+        let distance = pos.distance(self.origin);
+        if distance <= self.range {
+            self.damage
+        } else if self.falloff <= 0.0 {
+            0.0
+        } else {
+            self.damage * (1.0 - (distance - self.range) / self.falloff).max(0.0)
+        }
+    }
+
+    /// Turns a shot into one of `owner`'s, flying back the way it came
+    /// with its damage multiplied.
+    fn reflect(&mut self, owner: usize, position: Vec2, damage_multiplier: f32) {
+        self.owner = Some(owner);
+        self.damage *= damage_multiplier;
+        self.origin = position;
+        self.velocity = self.velocity.map(|velocity| -velocity);
+    }
+}
+
+const INTERMISSION_MS: u64 = 15000;
+
+/// Angles tried on the ring before deferring a spawn to the next tick.
+const SPAWN_CANDIDATES: usize = 4;
+/// Radius that must be free of other colliders around a new enemy.
+const SPAWN_CLEARANCE: f32 = 24.0;
+/// Radians around an authored spawn angle tried when it is taken.
+const SPAWN_ANGLE_JITTER: f32 = 0.2;
+
+/// Distance past the spawner ring where the arena ends.
+const ARENA_MARGIN: f32 = 64.0;
+/// Distance past the arena where leashed enemies are teleported back.
+const ARENA_TELEPORT_MARGIN: f32 = 256.0;
+/// Inward velocity change per reference frame, see the units module.
+const ARENA_LEASH_STRENGTH: f32 = 4.0;
+
+/// Pixels per second per unit of enemy speed, along the orbit.
+const KINEMATIC_TANGENTIAL_SPEED: f32 = 30.0;
+/// Pixels per second towards the planet.
+const KINEMATIC_INWARD_SPEED: f32 = 40.0;
+/// Half-angle in radians around the shot in which aim assist looks for enemies.
+const AIM_ASSIST_CONE: f32 = 0.35;
+/// Farthest enemy aim assist bends a shot toward.
+const AIM_ASSIST_RANGE: f32 = 800.0;
+const PLAYER_BULLET_SPEED: f32 = 500.0;
+/// Orbits a player can climb to: surface, low and high. Height above the
+/// rim and the fire rate kept up there.
+const ORBIT_ALTITUDES: [(f32, f32); 3] = [(8.0, 1.0), (48.0, 0.85), (96.0, 0.7)];
+/// Pixels per second while changing orbit.
+const CLIMB_SPEED: f32 = 240.0;
+
+#[derive(Component)]
+struct Enemy {
+    enemy_id: u32,
+    speed: f32,
+    has_hit: u8,
+    damage: f32,
+    hp: f32,
+    max_hp: f32,
+    shield: Option<Shield>,
+    /// Can be executed when low, see the bonuses module.
+    elite: bool,
+    scrap: u32,
+    /// Velocity change applied by the last movement step.
+    steering: Vec2,
+    movement: MovementMode,
+    /// Far outside the view, see the culling module.
+    offscreen: bool,
+    /// Player whose shot hit it last, credited with the kill.
+    last_hit_by: Option<usize>,
+    targeting: TargetingMode,
+    /// Structure it flies toward instead of the planet.
+    target: Option<Entity>,
+    /// Ignores all damage, like a boss during its introduction.
+    invulnerable: bool,
+}
+
+impl Enemy {
+    /// Name of what the enemy is currently doing.
+    fn behavior(&self) -> &'static str {
+        if self.has_hit > 0 {
+            "hit"
+        } else if self.speed <= 0.0 {
+            "dive"
+        } else {
+            "spiral"
+        }
+    }
+
+    /// Applies damage to the shield first, then to the hp.
+    fn take_damage(&mut self, damage: f32, kind: DamageKind) {
+        if self.invulnerable {
+            return;
+        }
+        let damage = match &mut self.shield {
+            Some(shield) => shield.absorb(damage, kind),
+            None => damage,
+        };
+        self.hp -= damage;
+    }
+}
+
+#[derive(Event)]
+struct EnemyKilled {
+    enemy_id: u32,
+    position: Vec2,
+    /// The bounty and the scrap of the loot.
+    scrap: u32,
+    by: Option<usize>,
+    loot: Loot,
+}
+
+/// Sent once the last enemy of a wave dies, before the intermission.
+#[derive(Event)]
+struct WaveEnded {
+    wave: usize,
+    kills: u32,
+    scrap: u32,
+    /// Shots of the players during the wave, satellites don't count.
+    shots_fired: u32,
+    shots_hit: u32,
+}
+
+/// A player or a satellite fired, `by` is the player.
+#[derive(Event)]
+struct ShotFired {
+    by: Option<usize>,
+}
+
+/// A projectile hit an enemy.
+#[derive(Event)]
+struct ShotHit {
+    position: Vec2,
+    by: Option<usize>,
+}
+
+/// The planet lost hp to a hit landing at `angle`, counterclockwise from
+/// +x around the planet.
+#[derive(Event)]
+struct PlanetDamaged {
+    damage: f32,
+    angle: f32,
+}
+
+/// Kills chained within a short window of each other.
+#[derive(Resource)]
+struct Combo {
+    count: u32,
+    timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo {
+            count: 0,
+            timer: Timer::new(Duration::from_millis(2000), TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+struct WaveStats {
+    kills: u32,
+    scrap: u32,
+    shots_fired: u32,
+    shots_hit: u32,
+}
+
+/// Multiplier applied to the delta of the gameplay systems and the physics.
+#[derive(Resource)]
+struct TimeScale {
+    scale: f32,
+    /// Slows everything but the players, see the bullet time module.
+    bullet_time: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale {
+            scale: 1.0,
+            bullet_time: 1.0,
+        }
+    }
+}
+
+impl TimeScale {
+    fn world(&self) -> f32 {
+        self.scale * self.bullet_time
+    }
+
+    fn delta(&self, time: &Time) -> Duration {
+        time.delta().mul_f32(self.world())
+    }
+
+    fn delta_seconds(&self, time: &Time) -> f32 {
+        time.delta_seconds() * self.world()
+    }
+
+    /// The players' own input keeps its pace during the bullet time.
+    fn player_delta(&self, time: &Time) -> Duration {
+        time.delta().mul_f32(self.scale)
+    }
+
+    fn player_delta_seconds(&self, time: &Time) -> f32 {
+        time.delta_seconds() * self.scale
+    }
+}
+
+/// Multiplier on top of the projection scale fitted to the window.
+#[derive(Resource)]
+struct CameraZoom(f32);
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        CameraZoom(1.0)
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct Wallet {
+    scrap: u32,
+    /// Scrap of the other players when the party doesn't share it, the
+    /// first player keeps using `scrap`.
+    purses: Vec<u32>,
+}
+
+impl Wallet {
+    fn purse(&self, player: usize) -> u32 {
+        match player {
+            0 => self.scrap,
+            _ => self.purses.get(player - 1).copied().unwrap_or(self.scrap),
+        }
+    }
+
+    fn purse_mut(&mut self, player: usize) -> &mut u32 {
+        if player == 0 || self.purses.len() < player {
+            &mut self.scrap
+        } else {
+            &mut self.purses[player - 1]
+        }
+    }
+}
+
+#[derive(Component)]
+struct UiTextWave;
+
+#[derive(Component)]
+struct UiTextScrap;
+
+fn update_ui_wave(
+    query_spawner: Query<&Spawner>,
+    challenge: Res<Challenge>,
+    mut text_query: Query<&mut Text, With<UiTextWave>>,
+) {
+    let spawner = query_spawner.single();
+
+    let value = if let Some(intermission) = &spawner.intermission {
+        let left = intermission.duration().as_secs_f32() - intermission.elapsed_secs();
+        format!("next wave in {:.0} [enter]", left.ceil())
+    } else if spawner.current_wave < challenge.waves.len() {
+        format!(
+            "wave {}/{}",
+            spawner.current_wave + 1,
+            challenge.waves.len()
+        )
+    } else {
+        format!("wave {}/{}", challenge.waves.len(), challenge.waves.len())
+    };
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn update_ui_scrap(wallet: Res<Wallet>, mut text_query: Query<&mut Text, With<UiTextScrap>>) {
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = format!("scrap {}", wallet.scrap);
+    }
+}
+
+fn load_assets(
+    mut commands: Commands,
+    mut handles: ResMut<AssetHandles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    window_query: Query<&Window, With<bevy::window::PrimaryWindow>>,
+) {
+    let camera_bundle = Camera2dBundle::new_with_far(100.0);
+    commands.spawn(camera_bundle).insert(Persistent);
+
+    handles.fonts.insert(
+        FontName::IosevkaRegular,
+        asset_server.load("fonts/iosevka-term-regular.ttf"),
+    );
+
+    let variant = settings
+        .texture_quality
+        .variant(primary_scale_factor(&window_query));
+    handles.load_textures(variant, &asset_server, &mut layouts);
+
+    handles
+        .meshes
+        .insert(MeshName::Circle, meshes.add(Mesh::from(Circle::new(0.5))));
+    handles.meshes.insert(
+        MeshName::Triangle,
+        meshes.add(Mesh::from(RegularPolygon::new(8.0, 3))),
+    );
+    handles.meshes.insert(
+        MeshName::Capsule,
+        meshes.add(Mesh::from(Capsule2d::new(0.5, 1.0))),
+    );
+
+    handles.materials.insert(
+        MaterialName::Planet,
+        materials.add(ColorMaterial::from(Color::from(PURPLE))),
+    );
+    handles.materials.insert(
+        MaterialName::Sky,
+        materials.add(ColorMaterial::from(Color::BLACK)),
+    );
+    handles.materials.insert(
+        MaterialName::Player,
+        materials.add(ColorMaterial::from(Color::from(BLUE))),
+    );
+    handles.materials.insert(
+        MaterialName::Enemy,
+        materials.add(ColorMaterial::from(Color::from(RED))),
+    );
+    handles.materials.insert(
+        MaterialName::Moon,
+        materials.add(ColorMaterial::from(Color::from(GRAY))),
+    );
+    handles.materials.insert(
+        MaterialName::Asteroid,
+        materials.add(ColorMaterial::from(Color::srgb(0.4, 0.3, 0.2))),
+    );
+    handles.materials.insert(
+        MaterialName::NightTint,
+        materials.add(ColorMaterial::from(Color::srgba(0.0, 0.02, 0.1, 0.0))),
+    );
+    handles.materials.insert(
+        MaterialName::Zone,
+        materials.add(ColorMaterial::from(Color::srgba(1.0, 0.4, 0.0, 0.3))),
+    );
+}
+
+fn setup(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    modifiers: Res<RunModifiers>,
+    launch: Res<LaunchOptions>,
+    balance: Res<Balance>,
+    settings: Res<Settings>,
+    previous_seed: Option<Res<RunSeed>>,
+) {
+    let level = &levels.list[current_level.0];
+    let loadout = modifiers.practice.clone().unwrap_or_default();
+
+    // a continued run keeps the waves it was playing
+    let seed = modifiers
+        .resume
+        .as_ref()
+        .and(previous_seed.map(|seed| seed.0))
+        .or(modifiers.seed)
+        .or(launch.seed)
+        .unwrap_or_else(|| thread_rng().gen());
+    commands.insert_resource(RunSeed(seed));
+    commands.insert_resource(
+        launch
+            .challenge
+            .clone()
+            .unwrap_or_else(|| level.challenge(seed, &balance.spawner.bands)),
+    );
+    let party = &modifiers.party;
+    let purses = if party.shared_scrap {
+        vec![]
+    } else {
+        vec![loadout.scrap; party.players.saturating_sub(1)]
+    };
+    commands.insert_resource(Wallet {
+        scrap: loadout.scrap,
+        purses,
+    });
+    commands.insert_resource(WaveStats::default());
+    commands.insert_resource(Combo::default());
+
+    if let Some(sky) = materials.get_mut(handles.materials.get(&MaterialName::Sky).unwrap()) {
+        sky.color = level.sky_color();
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(5.0),
+                right: Val::Px(15.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        "wave 1/?",
+                        TextStyle {
+                            font: handles
+                                .fonts
+                                .get(&FontName::IosevkaRegular)
+                                .unwrap()
+                                .clone_weak(),
+                            font_size: 48.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center)
+                    .with_style(Style { ..default() }),
+                )
+                .insert(UiTextWave);
+        });
+
+    if settings.assist.active() {
+        commands.spawn(
+            TextBundle::from_section(
+                "assist",
+                TextStyle {
+                    font: handles
+                        .fonts
+                        .get(&FontName::IosevkaRegular)
+                        .unwrap()
+                        .clone_weak(),
+                    font_size: 20.0,
+                    color: Color::srgb(0.4, 0.8, 1.0),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(64.0),
+                right: Val::Px(15.0),
+                ..default()
+            }),
+        );
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(5.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "scrap 0",
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(UiTextScrap);
+        });
+
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: handles
+                .meshes
+                .get(&MeshName::Circle)
+                .unwrap()
+                .clone_weak()
+                .into(),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 0.0),
+                scale: Vec3::new(level.spawner_size, level.spawner_size, 1.0),
+                ..default()
+            },
+            material: handles
+                .materials
+                .get(&MaterialName::Sky)
+                .unwrap()
+                .clone_weak(),
+            ..default()
+        })
+        .insert(Spawner::new(
+            level.spawner_size,
+            modifiers
+                .resume
+                .as_ref()
+                .map_or(launch.wave.unwrap_or(loadout.wave), |checkpoint| {
+                    checkpoint.wave
+                }),
+            Duration::from_millis(2000),
+        ));
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(level.planet_size, level.planet_size)),
+                ..default()
+            },
+            texture: asset_server.load(handles.texture_path(&level.planet_image)),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                scale: Vec3::new(1.0, 1.0, 1.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Collider::ball(level.planet_size * 0.5))
+        .insert(Team::Player)
+        .insert(Team::Player.collision_groups(Role::Body))
+        .insert(Planet {
+            size: level.planet_size,
+            hp: loadout.planet_hp,
+            max_hp: loadout.planet_hp,
+        });
+
+    for i in 0..loadout.satellites {
+        spawn_satellite(
+            &mut commands,
+            i as f32 * 2.0 * std::f32::consts::PI / SATELLITE_MAX as f32,
+        );
+    }
+
+    spawn_hazards(&mut commands, &handles, level);
+
+    // the party starts spread evenly around the planet
+    for index in 0..party.players.clamp(1, PARTY_MAX) {
+        let angle = std::f32::consts::FRAC_PI_2
+            + index as f32 * 2.0 * std::f32::consts::PI / party.players as f32;
+        let radius = level.planet_size * 0.5 + ORBIT_ALTITUDES[0].0;
+        commands
+            .spawn(SpriteBundle {
+                texture: handles.images.get(&ImageName::Player).unwrap().clone_weak(),
+                sprite: Sprite {
+                    color: PARTY_COLORS[index],
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(angle.cos() * radius, angle.sin() * radius, 2.0),
+                    rotation: Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2),
+                    scale: Vec3::new(1.0, 1.0, 1.0),
+                },
+                ..default()
+            })
+            .insert(Player {
+                index,
+                speed: 300.0,
+                timer: Timer::new(Duration::from_millis(200), TimerMode::Once),
+                fire_buffer: 0.0,
+                stun: 0.0,
+                altitude: 0,
+                height: ORBIT_ALTITUDES[0].0,
+                angular_velocity: 0.0,
+                parry: 0.0,
+                parry_cooldown: 0.0,
+            })
+            .insert(graze_sensor());
+    }
+}
+
+fn skip_intermission(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    cutscene: Res<Cutscene>,
+    mut spawner_query: Query<&mut Spawner>,
+) {
+    // enter goes to the cutscene while there is one
+    if !keyboard_input.just_pressed(KeyCode::Enter) || cutscene.active() {
+        return;
+    }
+    for mut spawner in &mut spawner_query {
+        if let Some(intermission) = &mut spawner.intermission {
+            let duration = intermission.duration();
+            intermission.set_elapsed(duration);
+        }
+    }
+}
+
+fn track_wave_stats(
+    mut stats: ResMut<WaveStats>,
+    mut killed_events: EventReader<EnemyKilled>,
+    mut fired_events: EventReader<ShotFired>,
+    mut hit_events: EventReader<ShotHit>,
+) {
+    for event in killed_events.read() {
+        stats.kills += 1;
+        stats.scrap += event.scrap;
+    }
+    stats.shots_fired += fired_events
+        .read()
+        .filter(|event| event.by.is_some())
+        .count() as u32;
+    stats.shots_hit += hit_events.read().filter(|event| event.by.is_some()).count() as u32;
+}
+
+fn track_combo(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut combo: ResMut<Combo>,
+    mut killed_events: EventReader<EnemyKilled>,
+) {
+    combo.timer.tick(time_scale.delta(&time));
+    if combo.timer.finished() {
+        combo.count = 0;
+    }
+    for _ in killed_events.read() {
+        combo.count += 1;
+        combo.timer.reset();
+    }
+}
+
+fn apply_time_scale(time_scale: Res<TimeScale>, mut timestep_mode: ResMut<TimestepMode>) {
+    if let TimestepMode::Variable {
+        time_scale: rapier_scale,
+        ..
+    } = timestep_mode.as_mut()
+    {
+        *rapier_scale = time_scale.world();
+    }
+}
+
+fn spawn_enemies(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    challenge: Res<Challenge>,
+    daynight: Res<DayNight>,
+    celebration: Res<WaveCelebration>,
+    cutscene: Res<Cutscene>,
+    draft: Res<UpgradeDraft>,
+    modifiers: Res<RunModifiers>,
+    settings: Res<Settings>,
+    encounter: Res<BossEncounter>,
+    mut stats: ResMut<WaveStats>,
+    mut wave_events: EventWriter<WaveEnded>,
+    mut retreat_events: EventWriter<RetreatSounded>,
+    mut spawner_query: Query<&mut Spawner>,
+    enemy_query: Query<&Enemy>,
+) {
+    // scripted spawns still get placed, only the waves wait on the boss
+    if modifiers.sandbox || cutscene.active() || encounter.locked {
+        return;
+    }
+    let intermission = Duration::from_millis(if modifiers.speedrun {
+        0
+    } else {
+        INTERMISSION_MS
+    });
+    let hold = (celebration.active || draft.is_open()) && !modifiers.speedrun;
+    let mut rng = thread_rng();
+    for mut spawner in &mut spawner_query {
+        let _span = info_span!("spawn_enemies", wave = spawner.current_wave).entered();
+        // the assist thins the kills out as much as the spawns
+        let kills = (stats.kills as f32 / settings.assist.enemy_count) as u32;
+        if spawner.retreat(&challenge, kills) {
+            retreat_events.send(RetreatSounded);
+        }
+        let field_clear = enemy_query.is_empty() && spawner.queue.is_empty();
+        match spawner.tick(
+            &challenge,
+            time_scale.delta(&time),
+            field_clear,
+            hold,
+            intermission,
+        ) {
+            Some(SpawnerStep::Spawn(enemy_id, angle)) => {
+                let enemy_id =
+                    if enemy_id == ENEMY_BASIC && rng.gen::<f32>() < daynight.stealth_chance() {
+                        ENEMY_STEALTH
+                    } else {
+                        enemy_id
+                    };
+                // the assist thins the waves out, rounding at random
+                let count = settings.assist.enemy_count;
+                let copies = count.floor() as usize + (rng.gen::<f32>() < count.fract()) as usize;
+                for _ in 0..copies {
+                    spawner.queue.push_back((enemy_id, angle));
+                }
+            }
+            Some(SpawnerStep::WaveEnded(wave)) => {
+                info!(kills = stats.kills, scrap = stats.scrap, "wave ended");
+                if !challenge.waves[wave].spawns.is_empty() {
+                    wave_events.send(WaveEnded {
+                        wave,
+                        kills: stats.kills,
+                        scrap: stats.scrap,
+                        shots_fired: stats.shots_fired,
+                        shots_hit: stats.shots_hit,
+                    });
+                }
+                *stats = WaveStats::default();
+            }
+            None => {}
+        }
+    }
+}
+
+/// Spawns the queued enemies on free spots of the ring, a spawn whose
+/// candidate spots all overlap something, or that would exceed the
+/// enemy cap, waits for the next tick.
+fn release_spawns(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    balance: Res<Balance>,
+    modifiers: Res<RunModifiers>,
+    rapier_context: Res<RapierContext>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut spawner_query: Query<(&mut Spawner, &Transform)>,
+    enemy_query: Query<&Enemy>,
+) {
+    let mut rng = thread_rng();
+    let shape = Collider::ball(SPAWN_CLEARANCE);
+    let alive = enemy_query.iter().count();
+    // spawned this tick, not yet known to rapier
+    let mut placed: Vec<Vec2> = vec![];
+    for (mut spawner, transform) in &mut spawner_query {
+        while let Some((enemy_id, authored)) = spawner.queue.front().copied() {
+            if alive + placed.len() >= balance.spawner.max_enemies {
+                return;
+            }
+            let free = (0..SPAWN_CANDIDATES).find_map(|candidate| {
+                let angle: f32 = match authored {
+                    Some(angle) if candidate == 0 => angle,
+                    Some(angle) => angle + rng.gen_range(-SPAWN_ANGLE_JITTER..SPAWN_ANGLE_JITTER),
+                    None => rng.gen_range(0.0..(2.0 * std::f32::consts::PI)),
+                };
+                let pos = Vec2::new(f32::cos(angle), f32::sin(angle)) * (spawner.size * 0.5)
+                    + transform.translation.truncate();
+                let overlaps_placed = placed
+                    .iter()
+                    .any(|other| other.distance(pos) < SPAWN_CLEARANCE * 2.0);
+                let overlaps_world = rapier_context
+                    .intersection_with_shape(
+                        pos,
+                        0.0,
+                        &shape,
+                        QueryFilter::default().exclude_sensors(),
+                    )
+                    .is_some();
+                (!overlaps_placed && !overlaps_world).then(|| pos)
+            });
+            let pos = match free {
+                Some(pos) => pos,
+                None => break,
+            };
+            spawner.queue.pop_front();
+            placed.push(pos);
+
+            sound_events.send(PlaySound {
+                sound: SoundName::Warp,
+                position: Some(pos),
+                caption: None,
+            });
+            let acc = pos.perp().normalize();
+            spawn_enemy(
+                &mut commands,
+                &handles,
+                &definitions,
+                enemy_id,
+                pos.extend(3.0),
+                acc * 120.0,
+                modifiers.enemy_hp_multiplier(),
+            );
+        }
+    }
+}
+
+fn spawn_enemy(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    definitions: &EnemyDefinitions,
+    enemy_id: u32,
+    translation: Vec3,
+    velocity: Vec2,
+    hp_multiplier: f32,
+) -> Entity {
+    let definition = definitions.get(enemy_id);
+    let alpha = if definition.stealth { 0.2 } else { 1.0 };
+    let angle = translation.y.atan2(translation.x);
+
+    let mut entity = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::srgba(1.0, 1.0, 1.0, alpha),
+            ..default()
+        },
+        texture: handles
+            .images
+            .get(&ImageName::Sprites)
+            .unwrap()
+            .clone_weak(),
+        transform: Transform {
+            translation: translation.truncate().extend(BATCH_LAYER),
+            rotation: Quat::from_rotation_z(angle),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+            ..default()
+        },
+        ..default()
+    });
+    entity.insert(TextureAtlas {
+        layout: handles.sprite_layout.clone_weak(),
+        index: SPRITE_ENEMY,
+    });
+    match definition.movement {
+        MovementMode::Dynamic => {
+            entity
+                .insert(RigidBody::Dynamic)
+                .insert(Restitution::coefficient(0.0))
+                .insert(Damping {
+                    linear_damping: 1.0,
+                    angular_damping: 10.0,
+                });
+        }
+        MovementMode::Kinematic => {
+            // kinematic bodies don't report contacts with static or
+            // kinematic colliders, like the planet, unless asked to
+            entity.insert(RigidBody::KinematicPositionBased).insert(
+                ActiveCollisionTypes::default()
+                    | ActiveCollisionTypes::KINEMATIC_STATIC
+                    | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
+            );
+        }
+    }
+    entity
+        .insert(Collider::capsule(
+            Vec2::new(0.0, -ENEMY_HALF_LENGTH),
+            Vec2::new(0.0, ENEMY_HALF_LENGTH),
+            ENEMY_RADIUS,
+        ))
+        .insert(Velocity::linear(velocity))
+        .insert(Team::Enemy)
+        .insert(Team::Enemy.collision_groups(Role::Body))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Enemy {
+            enemy_id,
+            speed: definition.speed,
+            has_hit: 0,
+            damage: definition.damage,
+            hp: definition.hp * hp_multiplier,
+            max_hp: definition.hp * hp_multiplier,
+            elite: definition.elite,
+            shield: (definition.shield > 0.0)
+                .then(|| Shield::new(definition.shield * hp_multiplier)),
+            scrap: definition.scrap,
+            steering: Vec2::ZERO,
+            movement: definition.movement,
+            offscreen: false,
+            last_hit_by: None,
+            targeting: definition.targeting,
+            target: None,
+            invulnerable: false,
+        });
+    if let Some(gun) = &definition.gun {
+        entity.insert(Gun::new(gun));
+    }
+    entity.id()
+}
+
+fn shooting(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: ResMut<AssetHandles>,
+    balance: Res<Balance>,
+    upgrades: Res<RunUpgrades>,
+    attachments: Res<Attachments>,
+    modifiers: Res<RunModifiers>,
+    random_events: Res<RandomEvents>,
+    settings: Res<Settings>,
+    mut player_query: Query<(&mut Player, &Transform)>,
+    enemy_grid: Res<EnemyGrid>,
+    input: PlayerInput,
+    mut shot_events: EventWriter<ShotFired>,
+    mut rumble_events: EventWriter<Rumble>,
+) {
+    let dt = time_scale.player_delta(&time);
+    let weapon = attachments.weapon(
+        WeaponSlot::Player,
+        &upgrades.weapon(&balance.weapons.player),
+    );
+    let damage_multiplier =
+        upgrades.damage_multiplier() * attachments.damage_multiplier(WeaponSlot::Player);
+    let fire_rate_multiplier =
+        upgrades.fire_rate_multiplier() * attachments.fire_rate_multiplier(WeaponSlot::Player);
+    // the beam module fires the beam
+    if weapon.beam.is_some() {
+        return;
+    }
+    let efficiency = if modifiers.idle { IDLE_EFFICIENCY } else { 1.0 };
+    for (mut player, player_trans) in &mut player_query {
+        let controls = input.controls(player.index);
+        player.timer.tick(
+            dt.mul_f32(fire_rate_multiplier * efficiency * ORBIT_ALTITUDES[player.altitude].1),
+        );
+        if input.just_pressed(controls.fire) {
+            player.fire_buffer = balance.input.fire_buffer_seconds;
+        } else {
+            player.fire_buffer = (player.fire_buffer - dt.as_secs_f32()).max(0.0);
+        }
+
+        let shooting = (input.pressed(controls.fire) || player.fire_buffer > 0.0 || modifiers.idle)
+            && !random_events.weapon_disabled()
+            && player.stun <= 0.0;
+        if shooting && player.timer.finished() {
+            player.timer.reset();
+            player.fire_buffer = 0.0;
+
+            if let Some(mines) = &weapon.mines {
+                lay_mine(
+                    &mut commands,
+                    player_trans.translation.truncate(),
+                    mines,
+                    mines.damage * damage_multiplier,
+                    Team::Player,
+                    Some(player.index),
+                );
+            } else {
+                let acc = fire_direction(player_trans, settings.aim_assist, &enemy_grid);
+                spawn_bullet(
+                    &mut commands,
+                    &handles,
+                    player_trans.translation,
+                    acc * PLAYER_BULLET_SPEED,
+                    25.0 * damage_multiplier,
+                    &weapon,
+                    Some(player.index),
+                    Team::Player,
+                );
+            }
+            rumble_events.send(Rumble::new(0.15, 60));
+            shot_events.send(ShotFired {
+                by: Some(player.index),
+            });
+        }
+    }
+}
+
+/// Where a player standing at `transform` shoots: straight up from the
+/// surface, bent by the aim assist.
+fn fire_direction(transform: &Transform, assist: f32, enemy_grid: &EnemyGrid) -> Vec2 {
+    let acc = transform.translation.normalize();
+    let acc = Vec2::new(acc.x, acc.y);
+    let origin = transform.translation.truncate();
+    aim_assist(
+        origin,
+        acc,
+        assist,
+        enemy_grid
+            .within(origin, AIM_ASSIST_RANGE)
+            .map(|(_, pos)| pos),
+    )
+}
+
+/// Bends the shot direction toward the closest enemy inside the assist
+/// cone, by the given strength.
+fn aim_assist(
+    origin: Vec2,
+    direction: Vec2,
+    strength: f32,
+    targets: impl Iterator<Item = Vec2>,
+) -> Vec2 {
+    if strength <= 0.0 {
+        return direction;
+    }
+    let closest = targets
+        .map(|target| target - origin)
+        .filter(|offset| offset.angle_between(direction).abs() < AIM_ASSIST_CONE)
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+    match closest {
+        Some(offset) => direction
+            .lerp(offset.normalize_or_zero(), strength.min(1.0))
+            .normalize(),
+        None => direction,
+    }
+}
+
+fn spawn_bullet(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    translation: Vec3,
+    velocity: Vec2,
+    damage: f32,
+    weapon: &WeaponBalance,
+    owner: Option<usize>,
+    team: Team,
+) {
+    let mode = weapon.projectile;
+    let mut angle = Vec2::angle_between(Vec2::Y, velocity);
+    if angle.is_nan() {
+        angle = 0.0;
+    }
+
+    let mut entity = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: match team {
+                Team::Player => Color::WHITE,
+                _ => Color::from(RED),
+            },
+            ..default()
+        },
+        texture: handles
+            .images
+            .get(&ImageName::Sprites)
+            .unwrap()
+            .clone_weak(),
+        transform: Transform {
+            translation: translation.truncate().extend(BATCH_LAYER),
+            rotation: Quat::from_rotation_z(angle),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+            ..default()
+        },
+        ..default()
+    });
+    entity.insert(TextureAtlas {
+        layout: handles.sprite_layout.clone_weak(),
+        index: SPRITE_BULLET,
+    });
+    match mode {
+        ProjectileMode::Physical => {
+            entity
+                .insert(RigidBody::Dynamic)
+                .insert(Restitution::coefficient(0.0))
+                .insert(LockedAxes::ROTATION_LOCKED)
+                .insert(Damping {
+                    linear_damping: 0.2,
+                    angular_damping: 10.0,
+                })
+                .insert(Ccd::enabled())
+                .insert(Velocity::linear(velocity))
+                .insert(ColliderMassProperties::Density(1.0));
+        }
+        ProjectileMode::Sensor => {
+            entity
+                .insert(RigidBody::KinematicPositionBased)
+                .insert(Sensor)
+                .insert(
+                    ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
+                );
+        }
+    }
+    entity
+        .insert(Collider::ball(BULLET_RADIUS))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(team)
+        .insert(team.collision_groups(Role::Shot))
+        .insert(Bullet {
+            damage,
+            origin: translation.truncate(),
+            range: weapon.range,
+            falloff: weapon.falloff,
+            has_hit: 0,
+            velocity: (mode == ProjectileMode::Sensor).then(|| velocity),
+            owner,
+            zone: weapon.zone.clone(),
+            pierce: weapon.pierce,
+        });
+}
+
+fn move_sensor_bullets(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut bullet_query: Query<(&Bullet, &mut Transform)>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    for (bullet, mut transform) in &mut bullet_query {
+        if let Some(velocity) = bullet.velocity {
+            transform.translation += (velocity * dt).extend(0.0);
+        }
+    }
+}
+
+/// Bullets per task when ticking them in parallel.
+const BULLET_BATCH_SIZE: usize = 256;
+
+/// Despawns many entities with a single command.
+struct DespawnBatch(Vec<Entity>);
+
+impl Command for DespawnBatch {
+    fn apply(self, world: &mut World) {
+        for entity in self.0 {
+            world.despawn(entity);
+        }
+    }
+}
+
+fn bullet_clean(
+    mut commands: Commands,
+    mut bullet_query: Query<(Entity, &mut Bullet, &Transform)>,
+    spawner_query: Query<&Spawner>,
+) {
+    let arena_radius = spawner_query
+        .get_single()
+        .map(|spawner| spawner.size * 0.5 + ARENA_MARGIN)
+        .unwrap_or(f32::INFINITY);
+
+    bullet_query
+        .par_iter_mut()
+        .batching_strategy(BatchingStrategy::fixed(BULLET_BATCH_SIZE))
+        .for_each(|(_, mut bullet, _)| {
+            // a bullet lingers two frames after the hit, see collision_resolve
+            if bullet.has_hit > 0 {
+                bullet.has_hit += 1;
+            }
+        });
+
+    let expired: Vec<Entity> = bullet_query
+        .iter()
+        .filter(|(_, bullet, transform)| {
+            let pos = transform.translation.truncate();
+            pos.distance(bullet.origin) > bullet.range + bullet.falloff
+                || bullet.has_hit > 2
+                || pos.length() > arena_radius
+        })
+        .map(|(entity, _, _)| entity)
+        .collect();
+    if !expired.is_empty() {
+        commands.add(DespawnBatch(expired));
+    }
+}
+
+fn collision_resolve(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut bullet_query: Query<(&mut Bullet, &Transform, &Team)>,
+    mut enemy_query: Query<(&mut Enemy, &Transform, &Team)>,
+    mut planet_query: Query<(&mut Planet, &Team)>,
+    player_query: Query<(), With<Player>>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut rumble_events: EventWriter<Rumble>,
+    mut hit_events: EventWriter<ShotHit>,
+    mut damage_events: EventWriter<PlanetDamaged>,
+    mut bonus_events: EventWriter<KillBonus>,
+    settings: Res<Settings>,
+    spawner_query: Query<&Spawner>,
+) {
+    let _span = info_span!("collision_resolve", wave = current_wave(&spawner_query)).entered();
+    for collision_event in collision_events.read() {
+        let (first, second) = match collision_event {
+            Started(first, second, _) => (*first, *second),
+            _ => continue,
+        };
+        // rapier doesn't order the pair, try both ways
+        for (ent, oth) in [(first, second), (second, first)] {
+            if let Ok((mut bullet, bullet_trans, bullet_team)) = bullet_query.get_mut(ent) {
+                // the sensors on the players only count the near misses
+                if bullet.has_hit == 0 && !player_query.contains(oth) {
+                    if let Some((mut planet, _)) = planet_query
+                        .get_mut(oth)
+                        .ok()
+                        .filter(|(_, team)| bullet_team.hurts(**team))
+                    {
+                        let from = bullet_trans.translation.truncate();
+                        let damage = bullet.damage_at(from) * settings.assist.planet_damage;
+                        planet.hp -= damage;
+                        debug!(target: "planet_td::damage", damage, hp = planet.hp, "planet shot");
+                        damage_events.send(PlanetDamaged {
+                            damage,
+                            angle: from.y.atan2(from.x),
+                        });
+                        rumble_events.send(Rumble::new(0.3, 100));
+                    }
+                    if let Some((mut enemy, _, _)) = enemy_query
+                        .get_mut(oth)
+                        .ok()
+                        .filter(|(_, _, team)| bullet_team.hurts(**team))
+                    {
+                        let position = bullet_trans.translation.truncate();
+                        let damage = bullet.damage_at(position);
+                        let hp_before = enemy.hp;
+                        enemy.take_damage(damage, DamageKind::Kinetic);
+                        let distance = position.distance(bullet.origin);
+                        if let Some(kind) = KillBonusKind::of_blow(&enemy, hp_before, distance) {
+                            bonus_events.send(KillBonus {
+                                kind,
+                                position,
+                                by: bullet.owner,
+                            });
+                        }
+                        debug!(target: "planet_td::damage", enemy = enemy.enemy_id, damage, hp = enemy.hp, "bullet hit");
+                        if bullet.owner.is_some() {
+                            enemy.last_hit_by = bullet.owner;
+                        }
+                        hit_events.send(ShotHit {
+                            position,
+                            by: bullet.owner,
+                        });
+                        sound_events.send(PlaySound {
+                            sound: SoundName::Impact,
+                            position: Some(bullet_trans.translation.truncate()),
+                            caption: None,
+                        });
+                        if let Some(zone) = &bullet.zone {
+                            spawn_zone(
+                                &mut commands,
+                                &handles,
+                                bullet_trans.translation.truncate(),
+                                zone,
+                                *bullet_team,
+                                bullet.owner,
+                            );
+                        }
+                        if bullet.pierce > 0 {
+                            bullet.pierce -= 1;
+                            continue;
+                        }
+                    }
+                    bullet.has_hit = 1;
+                }
+            }
+            if let Ok((mut enemy, enemy_trans, enemy_team)) = enemy_query.get_mut(ent) {
+                if enemy.has_hit == 0 {
+                    if let Some((mut planet, _)) = planet_query
+                        .get_mut(oth)
+                        .ok()
+                        .filter(|(_, team)| enemy_team.hurts(**team))
+                    {
+                        let damage = enemy.damage * settings.assist.planet_damage;
+                        planet.hp -= damage;
+                        debug!(target: "planet_td::damage", enemy = enemy.enemy_id, damage, hp = planet.hp, "planet hit");
+                        enemy.has_hit = 1;
+                        let from = enemy_trans.translation.truncate();
+                        damage_events.send(PlanetDamaged {
+                            damage,
+                            angle: from.y.atan2(from.x),
+                        });
+                        rumble_events.send(Rumble::new(0.6, 200));
+                        sound_events.send(PlaySound {
+                            sound: SoundName::Impact,
+                            position: Some(enemy_trans.translation.truncate()),
+                            caption: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn enemy_clean(
+    mut commands: Commands,
+    definitions: Res<EnemyDefinitions>,
+    mut loot_rng: ResMut<LootRng>,
+    mut wallet: ResMut<Wallet>,
+    mut killed_events: EventWriter<EnemyKilled>,
+    life_query: Query<(Entity, &Enemy, &Transform, Has<Revived>)>,
+    spawner_query: Query<&Spawner>,
+) {
+    let _span = info_span!("enemy_clean", wave = current_wave(&spawner_query)).entered();
+    let mut dead = vec![];
+    for (entity, enemy, transform, revived) in &life_query {
+        if enemy.hp <= 0.0 {
+            debug!(target: "planet_td::damage", enemy = enemy.enemy_id, by = ?enemy.last_hit_by, "enemy killed");
+            // the revived already dropped their loot the first time
+            let loot = if revived {
+                Loot::default()
+            } else {
+                definitions.get(enemy.enemy_id).loot.roll(&mut loot_rng.0)
+            };
+            let scrap = enemy.scrap + loot.scrap;
+            *wallet.purse_mut(enemy.last_hit_by.unwrap_or(0)) += scrap;
+            killed_events.send(EnemyKilled {
+                enemy_id: enemy.enemy_id,
+                position: transform.translation.truncate(),
+                scrap,
+                by: enemy.last_hit_by,
+                loot,
+            });
+        }
+        if enemy.hp <= 0.0 || enemy.has_hit > 0 {
+            dead.push(entity);
+        }
+    }
+    if !dead.is_empty() {
+        commands.add(DespawnBatch(dead));
+    }
+}
+
+fn movement(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    modifiers: Res<RunModifiers>,
+    mut player_query: Query<(&mut Player, &mut Transform), (With<Player>, Without<Planet>)>,
+    planet_query: Query<(&Planet, &Transform), (With<Planet>, Without<Player>)>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    input: PlayerInput,
+) {
+    let (planet, _planet_trans) = planet_query.single();
+
+    let dt = time_scale.player_delta_seconds(&time);
+    for (mut player, mut player_trans) in &mut player_query {
+        let controls = input.controls(player.index);
+        if input.just_pressed(controls.climb) {
+            player.altitude = (player.altitude + 1) % ORBIT_ALTITUDES.len();
+        }
+        let target = ORBIT_ALTITUDES[player.altitude].0;
+        player.height += (target - player.height).clamp(-CLIMB_SPEED * dt, CLIMB_SPEED * dt);
+
+        let direction = if input.pressed(controls.left) {
+            1.0
+        } else if input.pressed(controls.right) {
+            -1.0
+        } else if modifiers.idle {
+            idle_steering(
+                player_trans.translation.truncate(),
+                enemy_query
+                    .iter()
+                    .map(|transform| transform.translation.truncate()),
+            )
+        } else {
+            0.0
+        };
+
+        let mut angle_past = Vec2::angle_between(
+            Vec2::X,
+            Vec2::new(player_trans.translation.x, player_trans.translation.y),
+        );
+        if angle_past.is_nan() {
+            angle_past = 0.0;
+        }
+
+        player.angular_velocity = direction * player.speed * (1.0 / planet.size);
+        let angle = angle_past + player.angular_velocity * dt;
+
+        let radius = planet.size * 0.5 + player.height;
+        player_trans.translation = Vec3::new(
+            f32::cos(angle) * radius,
+            f32::sin(angle) * radius,
+            player_trans.translation.z,
+        );
+        player_trans.rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
+    }
+}
+
+/// Brings back the enemies knocked outside of the arena: pulled inward
+/// while close to the edge, teleported onto the spawner ring when far.
+fn leash_enemies(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    spawner_query: Query<&Spawner>,
+    mut enemies_query: Query<(&mut Transform, &mut Velocity), (With<Enemy>, Without<Retreating>)>,
+) {
+    let ring = match spawner_query.get_single() {
+        Ok(spawner) => spawner.size * 0.5,
+        Err(_) => return,
+    };
+    let arena_radius = ring + ARENA_MARGIN;
+    for (mut transform, mut velocity) in &mut enemies_query {
+        let position = transform.translation.truncate();
+        let distance = position.length();
+        if distance <= arena_radius {
+            continue;
+        }
+        let outward = position / distance;
+        if distance > arena_radius + ARENA_TELEPORT_MARGIN {
+            let inside = outward * ring;
+            transform.translation = inside.extend(transform.translation.z);
+            velocity.linvel = Vec2::ZERO;
+            continue;
+        }
+        let escaping = velocity.linvel.dot(outward).max(0.0);
+        let leash = ARENA_LEASH_STRENGTH * per_reference_frame(time_scale.delta_seconds(&time));
+        velocity.linvel -= outward * (escaping + leash);
+    }
+}
+
+fn move_enemies(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    daynight: Res<DayNight>,
+    settings: Res<Settings>,
+    mut culling: ResMut<CullingStats>,
+    mut frame: Local<u32>,
+    mut enemies_query: Query<
+        (Entity, &mut Enemy, &mut Transform, &mut Velocity),
+        Without<Retreating>,
+    >,
+    target_query: Query<&Transform, (With<Threat>, Without<Enemy>)>,
+    player_query: Query<(&Player, &Transform), Without<Enemy>>,
+) {
+    *frame = frame.wrapping_add(1);
+    culling.steering_updates = 0;
+    culling.steering_skipped = 0;
+    let speed_multiplier = daynight.enemy_speed_multiplier() * settings.assist.enemy_speed;
+    for (entity, mut enemy, mut enemy_tr, mut rb_vel) in &mut enemies_query {
+        // the swarm flocks instead, see the swarm module
+        if enemy.targeting == TargetingMode::Swarm {
+            continue;
+        }
+        // off-screen enemies steer less often, catching up on the skipped frames;
+        // the entity id spreads their updates over the interval
+        let ticks = if enemy.offscreen {
+            OFFSCREEN_STEERING_INTERVAL
+        } else {
+            1
+        };
+        if frame.wrapping_add(entity.index()) % ticks != 0 {
+            culling.steering_skipped += 1;
+            continue;
+        }
+        culling.steering_updates += 1;
+        let dt = time_scale.delta_seconds(&time) * ticks as f32;
+
+        if enemy.targeting == TargetingMode::Player {
+            let position = enemy_tr.translation.truncate();
+            let closest = player_query.iter().min_by(|(_, a), (_, b)| {
+                let a = a.translation.truncate().distance_squared(position);
+                let b = b.translation.truncate().distance_squared(position);
+                a.total_cmp(&b)
+            });
+            if let Some((player, player_tr)) = closest {
+                let speed = INTERCEPTOR_SPEED * speed_multiplier;
+                let aim = intercept_point(
+                    position,
+                    speed,
+                    player_tr.translation.truncate(),
+                    player.angular_velocity,
+                );
+                let desired = (aim - position).normalize_or_zero() * speed;
+                enemy.steering = (desired - rb_vel.linvel) * (INTERCEPTOR_AGILITY * dt).min(1.0);
+                rb_vel.linvel += enemy.steering;
+                if enemy.movement == MovementMode::Kinematic {
+                    enemy_tr.translation += (rb_vel.linvel * dt).extend(0.0);
+                }
+                enemy_tr.rotation = Quat::from_rotation_z(rb_vel.linvel.y.atan2(rb_vel.linvel.x));
+                continue;
+            }
+        }
+
+        if enemy.speed > 0.0 {
+            enemy.speed -= dt * 0.1;
+        }
+
+        // the spiral is centered on the target, the planet sits at the origin
+        let center = enemy
+            .target
+            .and_then(|target| target_query.get(target).ok())
+            .map(|target_tr| target_tr.translation.truncate())
+            .unwrap_or(Vec2::ZERO);
+        let delta = enemy_tr.translation.truncate() - center;
+        let tan = delta.normalize();
+        let norm = tan.perp() * enemy.speed;
+        enemy.steering = (norm - tan) * speed_multiplier;
+        match enemy.movement {
+            MovementMode::Dynamic => rb_vel.linvel += enemy.steering * per_reference_frame(dt),
+            MovementMode::Kinematic => {
+                // polar integration of the spiral: the same path at any frame rate
+                let radius = delta.length();
+                if dt <= 0.0 || radius <= 0.0 {
+                    continue;
+                }
+                let tangential =
+                    enemy.speed.max(0.0) * KINEMATIC_TANGENTIAL_SPEED * speed_multiplier;
+                let angle = delta.y.atan2(delta.x) + tangential * dt / radius;
+                let radius = (radius - KINEMATIC_INWARD_SPEED * speed_multiplier * dt).max(0.0);
+                let next = Vec2::new(f32::cos(angle), f32::sin(angle)) * radius;
+                rb_vel.linvel = (next - delta) / dt;
+                enemy_tr.translation = (center + next).extend(enemy_tr.translation.z);
+            }
+        }
+
+        let mut angle = Vec2::angle_between(Vec2::X, enemy_tr.translation.truncate() - center);
+        if angle.is_nan() {
+            angle = 0.0;
+        }
+        enemy_tr.rotation = Quat::from_rotation_z(angle);
+    }
+}
@@ -0,0 +1,252 @@
+use serde::*;
+
+use bevy::color::palettes::css::{CYAN, GRAY};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::conditions::PracticeLoadout;
+use crate::menu::{MenuOptions, MenuRoot};
+use crate::party::PartyOptions;
+use crate::profile::Profile;
+use crate::{AssetHandles, FontName};
+
+/// Presets kept in the profile, saving past this replaces the oldest.
+pub const LOADOUT_MAX: usize = 4;
+const LOADOUT_NAME_MAX: usize = 16;
+
+const BUTTON_IDLE: Color = Color::srgb(0.05, 0.05, 0.05);
+const BUTTON_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
+
+/// Run options saved under a name, picked in one click from the menu.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Loadout {
+    pub name: String,
+    pub speedrun: bool,
+    pub sandbox: bool,
+    #[serde(default)]
+    pub idle: bool,
+    pub practice: bool,
+    pub loadout: PracticeLoadout,
+    pub party: PartyOptions,
+}
+
+impl Loadout {
+    pub fn from_options(name: String, options: &MenuOptions) -> Loadout {
+        Loadout {
+            name,
+            speedrun: options.speedrun,
+            sandbox: options.sandbox,
+            idle: options.idle,
+            practice: options.practice,
+            loadout: options.loadout.clone(),
+            party: options.party.clone(),
+        }
+    }
+
+    pub fn apply(&self, options: &mut MenuOptions) {
+        options.speedrun = self.speedrun;
+        options.sandbox = self.sandbox;
+        options.idle = self.idle;
+        options.practice = self.practice;
+        options.loadout = self.loadout.clone();
+        options.party = self.party.clone();
+    }
+}
+
+/// Name being typed for the preset about to be saved.
+#[derive(Default, Resource)]
+pub struct LoadoutNaming(pub Option<String>);
+
+#[derive(Component)]
+pub struct LoadoutButton(usize);
+
+#[derive(Component)]
+pub struct SaveLoadoutButton;
+
+fn save_label(naming: &LoadoutNaming) -> String {
+    match &naming.0 {
+        Some(name) => format!("name: {}_", name),
+        None => "save loadout".to_string(),
+    }
+}
+
+#[derive(Component)]
+pub struct LoadoutRow;
+
+pub fn setup_loadout_buttons(
+    mut commands: Commands,
+    mut naming: ResMut<LoadoutNaming>,
+    root_query: Query<Entity, With<MenuRoot>>,
+) {
+    let root = match root_query.get_single() {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+    naming.0 = None;
+    commands.entity(root).with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    margin: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(LoadoutRow);
+    });
+}
+
+/// Refills the row of presets when one is saved or a name is typed.
+pub fn show_loadouts(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    profile: Res<Profile>,
+    naming: Res<LoadoutNaming>,
+    row_query: Query<(Entity, Ref<LoadoutRow>)>,
+) {
+    let changed = profile.is_changed() || naming.is_changed();
+    let rows: Vec<Entity> = row_query
+        .iter()
+        .filter(|(_, row)| changed || row.is_added())
+        .map(|(entity, _)| entity)
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let button = |width: f32| ButtonBundle {
+        style: Style {
+            width: Val::Px(width),
+            height: Val::Px(48.0),
+            margin: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: BUTTON_IDLE.into(),
+        ..default()
+    };
+    let style = |color: Color| TextStyle {
+        font: font.clone(),
+        font_size: 24.0,
+        color,
+    };
+    for row in rows {
+        commands
+            .entity(row)
+            .despawn_descendants()
+            .with_children(|parent| {
+                for (i, loadout) in profile.loadouts.iter().enumerate() {
+                    parent
+                        .spawn(button(180.0))
+                        .insert(LoadoutButton(i))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                loadout.name.clone(),
+                                style(Color::from(CYAN)),
+                            ));
+                        });
+                }
+                parent
+                    .spawn(button(260.0))
+                    .insert(SaveLoadoutButton)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            save_label(&naming),
+                            style(Color::from(GRAY)),
+                        ));
+                    });
+            });
+    }
+}
+
+pub fn loadout_interaction(
+    profile: Res<Profile>,
+    mut options: ResMut<MenuOptions>,
+    mut naming: ResMut<LoadoutNaming>,
+    mut loadout_query: Query<
+        (&Interaction, &LoadoutButton, &mut BackgroundColor),
+        (Changed<Interaction>, Without<SaveLoadoutButton>),
+    >,
+    mut save_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<SaveLoadoutButton>),
+    >,
+) {
+    for (interaction, button, mut color) in &mut loadout_query {
+        match *interaction {
+            Interaction::Pressed => {
+                if let Some(loadout) = profile.loadouts.get(button.0) {
+                    loadout.apply(&mut options);
+                }
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+        }
+    }
+    for (interaction, mut color) in &mut save_query {
+        match *interaction {
+            Interaction::Pressed if naming.0.is_none() => naming.0 = Some(String::new()),
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+            _ => {}
+        }
+    }
+}
+
+/// Types the name of the preset, [enter] saves it, [escape] gives up.
+pub fn name_loadout(
+    options: Res<MenuOptions>,
+    mut profile: ResMut<Profile>,
+    mut naming: ResMut<LoadoutNaming>,
+    mut key_events: EventReader<KeyboardInput>,
+) {
+    let mut name = match &naming.0 {
+        Some(name) => name.clone(),
+        None => {
+            key_events.clear();
+            return;
+        }
+    };
+    let mut done = None;
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(text) if name.len() < LOADOUT_NAME_MAX => name.push_str(text),
+            Key::Space if name.len() < LOADOUT_NAME_MAX => name.push(' '),
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Enter => done = Some(true),
+            Key::Escape => done = Some(false),
+            _ => {}
+        }
+    }
+
+    match done {
+        Some(true) if !name.trim().is_empty() => {
+            let name = name.trim().to_string();
+            profile.loadouts.retain(|loadout| loadout.name != name);
+            if profile.loadouts.len() >= LOADOUT_MAX {
+                profile.loadouts.remove(0);
+            }
+            profile.loadouts.push(Loadout::from_options(name, &options));
+            profile.save();
+            naming.0 = None;
+        }
+        Some(_) => naming.0 = None,
+        // untouched otherwise, the row is rebuilt on every change
+        None if naming.0.as_ref() != Some(&name) => naming.0 = Some(name),
+        None => {}
+    }
+}
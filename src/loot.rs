@@ -0,0 +1,57 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::attachments::AttachmentKind;
+use crate::spawner::RunSeed;
+
+/// Keeps the loot rolls apart from the waves generated from the same seed.
+const LOOT_STREAM: u64 = 0x6c6f6f74;
+
+/// What an enemy drops when killed, on top of its bounty.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct LootTable {
+    /// Extra scrap, rolled between the two inclusive.
+    #[serde(default)]
+    pub scrap: (u32, u32),
+    /// Chance of a random attachment.
+    #[serde(default)]
+    pub attachment_chance: f32,
+    /// Dropped on every kill, for the bosses.
+    #[serde(default)]
+    pub guaranteed: Vec<AttachmentKind>,
+}
+
+/// A rolled loot table.
+#[derive(Default, Clone)]
+pub struct Loot {
+    pub scrap: u32,
+    pub attachments: Vec<AttachmentKind>,
+}
+
+impl LootTable {
+    pub fn roll(&self, rng: &mut impl Rng) -> Loot {
+        let (min, max) = self.scrap;
+        let mut loot = Loot {
+            scrap: rng.gen_range(min..=max.max(min)),
+            attachments: self.guaranteed.clone(),
+        };
+        if rng.gen::<f32>() < self.attachment_chance {
+            loot.attachments
+                .push(*AttachmentKind::ALL.choose(rng).unwrap());
+        }
+        loot
+    }
+}
+
+/// Draws the loot of the run, seeded by the run seed so that the same
+/// kills drop the same things.
+#[derive(Resource)]
+pub struct LootRng(pub StdRng);
+
+pub fn setup_loot(mut commands: Commands, seed: Res<RunSeed>) {
+    commands.insert_resource(LootRng(StdRng::seed_from_u64(seed.0 ^ LOOT_STREAM)));
+}
@@ -0,0 +1,514 @@
+use bevy::color::palettes::css::GRAY;
+use bevy::prelude::*;
+
+use crate::codex::CodexButton;
+use crate::conditions::{PracticeLoadout, RunModifiers};
+use crate::controls::ControlScheme;
+use crate::history::HistoryButton;
+use crate::level::{CurrentLevel, Levels};
+use crate::party::{PartyOptions, PARTY_MAX};
+use crate::profile::Profile;
+use crate::satellite::SATELLITE_MAX;
+use crate::settings::{Settings, ASSIST_ENEMY_COUNT};
+use crate::textures::TextureQuality;
+use crate::viewport::FitStrategy;
+use crate::{AppState, AssetHandles, FontName};
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component)]
+pub struct LevelButton(usize);
+
+/// Buttons cycling through the values of a run option.
+#[derive(Component, Clone, Copy)]
+pub enum MenuOption {
+    Speedrun,
+    Sandbox,
+    Idle,
+    Practice,
+    PracticeWave,
+    PracticePlanetHp,
+    PracticeScrap,
+    PracticeSatellites,
+    AimAssist,
+    HitFeedback,
+    TrajectoryPreview,
+    AutoRepair,
+    Heatmap,
+    InputDisplay,
+    AssistEnemies,
+    AssistSpeed,
+    AssistDamage,
+    AssistLeaderboard,
+    Controls,
+    Captions,
+    Fit,
+    SafeMargin,
+    Textures,
+    Players,
+    FriendlyFire,
+    SharedScrap,
+}
+
+const PRACTICE_WAVE_STEP: usize = 5;
+const PRACTICE_WAVE_MAX: usize = 50;
+const PRACTICE_PLANET_HP: [f32; 4] = [100.0, 50.0, 25.0, 10.0];
+const PRACTICE_SCRAP: [u32; 4] = [0, 100, 250, 500];
+const AIM_ASSIST: [f32; 4] = [0.0, 0.25, 0.5, 1.0];
+const ASSIST_ENEMY_SPEED: [f32; 3] = [1.0, 0.8, 0.6];
+const ASSIST_PLANET_DAMAGE: [f32; 3] = [1.0, 0.5, 0.25];
+const SAFE_MARGIN: [f32; 4] = [0.0, 0.02, 0.04, 0.06];
+
+/// Run options picked in the menu before choosing a level.
+#[derive(Default, Resource)]
+pub struct MenuOptions {
+    pub speedrun: bool,
+    pub sandbox: bool,
+    pub idle: bool,
+    pub practice: bool,
+    pub loadout: PracticeLoadout,
+    pub party: PartyOptions,
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Next value of a fixed list, wrapping around.
+fn cycle<T: PartialEq + Copy>(values: &[T], current: T) -> T {
+    let index = values.iter().position(|v| *v == current).unwrap_or(0);
+    values[(index + 1) % values.len()]
+}
+
+impl MenuOption {
+    const ALL: [MenuOption; 26] = [
+        MenuOption::Speedrun,
+        MenuOption::Sandbox,
+        MenuOption::Idle,
+        MenuOption::Practice,
+        MenuOption::PracticeWave,
+        MenuOption::PracticePlanetHp,
+        MenuOption::PracticeScrap,
+        MenuOption::PracticeSatellites,
+        MenuOption::AimAssist,
+        MenuOption::HitFeedback,
+        MenuOption::TrajectoryPreview,
+        MenuOption::AutoRepair,
+        MenuOption::Heatmap,
+        MenuOption::InputDisplay,
+        MenuOption::AssistEnemies,
+        MenuOption::AssistSpeed,
+        MenuOption::AssistDamage,
+        MenuOption::AssistLeaderboard,
+        MenuOption::Controls,
+        MenuOption::Captions,
+        MenuOption::Fit,
+        MenuOption::SafeMargin,
+        MenuOption::Textures,
+        MenuOption::Players,
+        MenuOption::FriendlyFire,
+        MenuOption::SharedScrap,
+    ];
+
+    fn label(&self, options: &MenuOptions, settings: &Settings) -> String {
+        let loadout = &options.loadout;
+        match self {
+            MenuOption::Speedrun => format!("speedrun {}", on_off(options.speedrun)),
+            MenuOption::Sandbox => format!("sandbox {}", on_off(options.sandbox)),
+            MenuOption::Idle => format!("idle {}", on_off(options.idle)),
+            MenuOption::Practice => format!("practice {}", on_off(options.practice)),
+            MenuOption::PracticeWave => format!("wave {}", loadout.wave + 1),
+            MenuOption::PracticePlanetHp => format!("hp {}", loadout.planet_hp),
+            MenuOption::PracticeScrap => format!("scrap {}", loadout.scrap),
+            MenuOption::PracticeSatellites => format!("satellites {}", loadout.satellites),
+            MenuOption::AimAssist => match settings.aim_assist {
+                strength if strength > 0.0 => format!("aim assist {:.0}%", strength * 100.0),
+                _ => "aim assist off".to_string(),
+            },
+            MenuOption::HitFeedback => format!("hit markers {}", on_off(settings.hit_feedback)),
+            MenuOption::TrajectoryPreview => {
+                format!("aim preview {}", on_off(settings.trajectory_preview))
+            }
+            MenuOption::AutoRepair => format!("auto repair {}", on_off(settings.auto_repair)),
+            MenuOption::Heatmap => format!("damage heatmap {}", on_off(settings.damage_heatmap)),
+            MenuOption::InputDisplay => format!("input display {}", on_off(settings.input_display)),
+            MenuOption::AssistEnemies => {
+                format!("assist enemies {:.0}%", settings.assist.enemy_count * 100.0)
+            }
+            MenuOption::AssistSpeed => {
+                format!("assist speed {:.0}%", settings.assist.enemy_speed * 100.0)
+            }
+            MenuOption::AssistDamage => {
+                format!(
+                    "assist damage {:.0}%",
+                    settings.assist.planet_damage * 100.0
+                )
+            }
+            MenuOption::AssistLeaderboard => {
+                format!("assist scores {}", on_off(settings.assist.leaderboard))
+            }
+            MenuOption::Controls => format!("controls {}", settings.control_scheme.name()),
+            MenuOption::Captions => format!("captions {}", on_off(settings.captions)),
+            MenuOption::Fit => format!("view {}", settings.fit.name()),
+            MenuOption::SafeMargin => format!("safe margin {:.0}%", settings.safe_margin * 100.0),
+            MenuOption::Textures => format!("textures {}", settings.texture_quality.name()),
+            MenuOption::Players => format!("players {}", options.party.players),
+            MenuOption::FriendlyFire => {
+                format!("friendly fire {}", on_off(options.party.friendly_fire))
+            }
+            MenuOption::SharedScrap => {
+                format!("shared scrap {}", on_off(options.party.shared_scrap))
+            }
+        }
+    }
+
+    fn is_practice_field(&self) -> bool {
+        !matches!(
+            self,
+            MenuOption::Speedrun
+                | MenuOption::Sandbox
+                | MenuOption::Idle
+                | MenuOption::Practice
+                | MenuOption::AimAssist
+                | MenuOption::HitFeedback
+                | MenuOption::TrajectoryPreview
+                | MenuOption::AutoRepair
+                | MenuOption::Heatmap
+                | MenuOption::InputDisplay
+                | MenuOption::AssistEnemies
+                | MenuOption::AssistSpeed
+                | MenuOption::AssistDamage
+                | MenuOption::AssistLeaderboard
+                | MenuOption::Controls
+                | MenuOption::Captions
+                | MenuOption::Fit
+                | MenuOption::SafeMargin
+                | MenuOption::Textures
+                | MenuOption::Players
+                | MenuOption::FriendlyFire
+                | MenuOption::SharedScrap
+        )
+    }
+
+    fn cycle(&self, options: &mut MenuOptions, settings: &mut Settings) {
+        let loadout = &mut options.loadout;
+        match self {
+            MenuOption::Speedrun => options.speedrun = !options.speedrun,
+            MenuOption::Sandbox => options.sandbox = !options.sandbox,
+            MenuOption::Idle => options.idle = !options.idle,
+            MenuOption::Practice => options.practice = !options.practice,
+            MenuOption::PracticeWave => {
+                loadout.wave = (loadout.wave + PRACTICE_WAVE_STEP) % PRACTICE_WAVE_MAX
+            }
+            MenuOption::PracticePlanetHp => {
+                loadout.planet_hp = cycle(&PRACTICE_PLANET_HP, loadout.planet_hp)
+            }
+            MenuOption::PracticeScrap => loadout.scrap = cycle(&PRACTICE_SCRAP, loadout.scrap),
+            MenuOption::PracticeSatellites => {
+                loadout.satellites = (loadout.satellites + 1) % (SATELLITE_MAX + 1)
+            }
+            MenuOption::AimAssist => settings.aim_assist = cycle(&AIM_ASSIST, settings.aim_assist),
+            MenuOption::HitFeedback => settings.hit_feedback = !settings.hit_feedback,
+            MenuOption::TrajectoryPreview => {
+                settings.trajectory_preview = !settings.trajectory_preview
+            }
+            MenuOption::AutoRepair => settings.auto_repair = !settings.auto_repair,
+            MenuOption::Heatmap => settings.damage_heatmap = !settings.damage_heatmap,
+            MenuOption::InputDisplay => settings.input_display = !settings.input_display,
+            MenuOption::AssistEnemies => {
+                let assist = &mut settings.assist;
+                assist.enemy_count = cycle(&ASSIST_ENEMY_COUNT, assist.enemy_count)
+            }
+            MenuOption::AssistSpeed => {
+                let assist = &mut settings.assist;
+                assist.enemy_speed = cycle(&ASSIST_ENEMY_SPEED, assist.enemy_speed)
+            }
+            MenuOption::AssistDamage => {
+                let assist = &mut settings.assist;
+                assist.planet_damage = cycle(&ASSIST_PLANET_DAMAGE, assist.planet_damage)
+            }
+            MenuOption::AssistLeaderboard => {
+                settings.assist.leaderboard = !settings.assist.leaderboard
+            }
+            MenuOption::Controls => {
+                settings.control_scheme = cycle(&ControlScheme::ALL, settings.control_scheme)
+            }
+            MenuOption::Captions => settings.captions = !settings.captions,
+            MenuOption::Fit => settings.fit = cycle(&FitStrategy::ALL, settings.fit),
+            MenuOption::SafeMargin => {
+                settings.safe_margin = cycle(&SAFE_MARGIN, settings.safe_margin)
+            }
+            MenuOption::Textures => {
+                settings.texture_quality = cycle(&TextureQuality::ALL, settings.texture_quality)
+            }
+            MenuOption::Players => options.party.players = options.party.players % PARTY_MAX + 1,
+            MenuOption::FriendlyFire => options.party.friendly_fire = !options.party.friendly_fire,
+            MenuOption::SharedScrap => options.party.shared_scrap = !options.party.shared_scrap,
+        }
+    }
+}
+
+const BUTTON_IDLE: Color = Color::srgb(0.05, 0.05, 0.05);
+const BUTTON_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
+const BUTTON_LOCKED: Color = Color::srgb(0.02, 0.02, 0.02);
+
+pub fn setup_menu(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    levels: Res<Levels>,
+    profile: Res<Profile>,
+    options: Res<MenuOptions>,
+    settings: Res<Settings>,
+) {
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "planet",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 96.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+
+            for (i, level) in levels.list.iter().enumerate() {
+                let locked = i >= profile.unlocked_levels;
+                let mut button = parent.spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Px(320.0),
+                        height: Val::Px(64.0),
+                        margin: UiRect::all(Val::Px(8.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: if locked { BUTTON_LOCKED } else { BUTTON_IDLE }.into(),
+                    ..default()
+                });
+                if !locked {
+                    button.insert(LevelButton(i));
+                }
+                button.with_children(|parent| {
+                    let best = profile.best_score(i);
+                    let label = if locked {
+                        format!("{} locked", i + 1)
+                    } else if best > 0 {
+                        format!("{} {} {}", i + 1, level.name, best)
+                    } else {
+                        format!("{} {}", i + 1, level.name)
+                    };
+                    parent.spawn(TextBundle::from_section(
+                        label,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 40.0,
+                            color: if locked {
+                                Color::from(GRAY)
+                            } else {
+                                Color::WHITE
+                            },
+                        },
+                    ));
+                });
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(760.0),
+                        height: Val::Auto,
+                        margin: UiRect::all(Val::Px(20.0)),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for option in MenuOption::ALL {
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(180.0),
+                                    height: Val::Px(48.0),
+                                    margin: UiRect::all(Val::Px(4.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: BUTTON_IDLE.into(),
+                                ..default()
+                            })
+                            .insert(option)
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    option.label(&options, &settings),
+                                    TextStyle {
+                                        font: font.clone(),
+                                        font_size: 24.0,
+                                        color: Color::from(GRAY),
+                                    },
+                                ));
+                            });
+                    }
+                });
+
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Px(180.0),
+                        height: Val::Px(48.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_IDLE.into(),
+                    ..default()
+                })
+                .insert(CodexButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "codex",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Px(180.0),
+                        height: Val::Px(48.0),
+                        margin: UiRect::top(Val::Px(4.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_IDLE.into(),
+                    ..default()
+                })
+                .insert(HistoryButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "history",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+        });
+}
+
+pub fn menu_interaction(
+    mut state: ResMut<NextState<AppState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut modifiers: ResMut<RunModifiers>,
+    options: Res<MenuOptions>,
+    mut button_query: Query<
+        (&Interaction, &LevelButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, button, mut color) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => {
+                current_level.0 = button.0;
+                *modifiers = RunModifiers {
+                    speedrun: options.speedrun,
+                    practice: options.practice.then(|| options.loadout.clone()),
+                    sandbox: options.sandbox,
+                    idle: options.idle,
+                    party: options.party.clone(),
+                    ..default()
+                };
+                state.set(AppState::InGame);
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+        }
+    }
+}
+
+pub fn menu_options_interaction(
+    mut options: ResMut<MenuOptions>,
+    mut settings: ResMut<Settings>,
+    mut button_query: Query<
+        (&Interaction, &MenuOption, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, option, mut color) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => {
+                option.cycle(&mut options, &mut settings);
+                // tweaking the loadout implies practicing with it
+                if option.is_practice_field() {
+                    options.practice = true;
+                }
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+        }
+    }
+}
+
+/// Options change from their buttons and from the loadout presets.
+pub fn update_option_labels(
+    options: Res<MenuOptions>,
+    settings: Res<Settings>,
+    label_query: Query<(&MenuOption, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !options.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for (option, children) in &label_query {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = option.label(&options, &settings);
+            }
+        }
+    }
+}
+
+pub fn cleanup_menu(mut commands: Commands, menu_query: Query<Entity, With<MenuRoot>>) {
+    for entity in &menu_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
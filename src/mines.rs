@@ -0,0 +1,225 @@
+use bevy::color::palettes::css::{MEDIUM_PURPLE, YELLOW};
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::balance::MineBalance;
+use crate::enemies::EnemyDefinitions;
+use crate::gamepad::Rumble;
+use crate::party::PARTY_MAX;
+use crate::shields::DamageKind;
+use crate::teams::{Role, Team};
+use crate::{Enemy, Planet, Player, TimeScale, ORBIT_ALTITUDES};
+
+/// Seconds between a mine being set off and its blast.
+const MINE_FUSE: f32 = 0.4;
+/// Blinks per second while a mine arms, slow, and once it is set off, fast.
+const ARMING_BLINK: f32 = 2.0;
+const FUSE_BLINK: f32 = 12.0;
+/// Players caught in a blast of the swarm can't shoot for this long.
+const MINE_STUN: f32 = 1.0;
+
+#[derive(Component)]
+pub struct Mine {
+    /// Side that laid it, it goes off on the teams that side hurts.
+    pub team: Team,
+    /// Player credited for the kills, none for the swarm's.
+    pub owner: Option<usize>,
+    pub damage: f32,
+    pub balance: MineBalance,
+    pub age: f32,
+    /// Seconds left before the blast once set off.
+    pub fuse: Option<f32>,
+}
+
+/// An enemy that already dropped its mine.
+#[derive(Component)]
+pub struct MineDropped;
+
+pub fn lay_mine(
+    commands: &mut Commands,
+    position: Vec2,
+    balance: &MineBalance,
+    damage: f32,
+    team: Team,
+    owner: Option<usize>,
+) {
+    let color = match team {
+        Team::Player => Color::from(YELLOW),
+        _ => Color::from(MEDIUM_PURPLE),
+    };
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(8.0, 8.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(1.5))
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ..default()
+        })
+        .insert(Collider::ball(balance.trigger_radius))
+        .insert(Sensor)
+        // mines don't move, without this the pairs with kinematic enemies
+        // would never be checked
+        .insert(ActiveCollisionTypes::all())
+        // they overlap their targets the way the team's projectiles would
+        .insert(team.collision_groups(Role::Shot))
+        .insert(Mine {
+            team,
+            owner,
+            damage,
+            balance: balance.clone(),
+            age: 0.0,
+            fuse: None,
+        });
+}
+
+/// Enemies that carry mines drop one on the orbit lane as they cross it,
+/// on the orbit of a random altitude.
+pub fn drop_enemy_mines(
+    mut commands: Commands,
+    definitions: Res<EnemyDefinitions>,
+    planet_query: Query<&Planet>,
+    enemy_query: Query<(Entity, &Enemy, &Transform), Without<MineDropped>>,
+) {
+    let planet = match planet_query.get_single() {
+        Ok(planet) => planet,
+        Err(_) => return,
+    };
+    let rim = planet.size * 0.5;
+    let lane = rim + ORBIT_ALTITUDES[ORBIT_ALTITUDES.len() - 1].0;
+    let mut rng = thread_rng();
+    for (entity, enemy, transform) in &enemy_query {
+        let balance = match &definitions.get(enemy.enemy_id).mines {
+            Some(balance) => balance,
+            None => continue,
+        };
+        let position = transform.translation.truncate();
+        if position.length() > lane {
+            continue;
+        }
+        let altitude = ORBIT_ALTITUDES[rng.gen_range(0..ORBIT_ALTITUDES.len())].0;
+        lay_mine(
+            &mut commands,
+            position.normalize_or_zero() * (rim + altitude),
+            balance,
+            balance.damage,
+            Team::Enemy,
+            None,
+        );
+        commands.entity(entity).insert(MineDropped);
+    }
+}
+
+/// Arms, triggers and sets off the mines. Armed mines go off on the
+/// enemies through their sensor, and on the players by distance since
+/// the players only carry a graze sensor.
+pub fn update_mines(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut mine_query: Query<(Entity, &mut Mine, &Transform, &mut Sprite)>,
+    mut enemy_query: Query<(&mut Enemy, &Team, &Transform)>,
+    mut player_query: Query<(&mut Player, &Transform)>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut rumble_events: EventWriter<Rumble>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+
+    // the oldest mines of a player fizzle past the limit
+    let mut fizzled = vec![];
+    for owner in 0..PARTY_MAX {
+        let mut laid: Vec<(f32, usize, Entity)> = mine_query
+            .iter()
+            .filter(|(_, mine, _, _)| mine.owner == Some(owner))
+            .map(|(entity, mine, _, _)| (mine.age, mine.balance.limit, entity))
+            .collect();
+        laid.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let limit = laid.first().map_or(0, |(_, limit, _)| *limit);
+        fizzled.extend(laid.iter().skip(limit).map(|(_, _, entity)| *entity));
+    }
+
+    for (entity, mut mine, transform, mut sprite) in &mut mine_query {
+        if fizzled.contains(&entity) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        mine.age += dt;
+        let position = transform.translation.truncate();
+        let armed = mine.age >= mine.balance.arm_seconds;
+        if mine.fuse.is_none() && mine.age >= mine.balance.lifetime_seconds {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if armed && mine.fuse.is_none() {
+            let enemy_near = rapier_context
+                .intersection_pairs_with(entity)
+                .filter(|(_, _, intersecting)| *intersecting)
+                .map(|(first, second, _)| if first == entity { second } else { first })
+                .any(|other| {
+                    enemy_query
+                        .get(other)
+                        .is_ok_and(|(_, team, _)| mine.team.hurts(*team))
+                });
+            let player_near = mine.team.hurts(Team::Player)
+                && player_query.iter().any(|(_, player_trans)| {
+                    player_trans.translation.truncate().distance(position)
+                        < mine.balance.trigger_radius
+                });
+            if enemy_near || player_near {
+                mine.fuse = Some(MINE_FUSE);
+            }
+        }
+
+        // the telegraph: a slow blink while arming, a fast one once set off
+        let blink = match mine.fuse {
+            Some(_) => Some(FUSE_BLINK),
+            None if !armed => Some(ARMING_BLINK),
+            None => None,
+        };
+        let lit = blink.map_or(true, |rate| (mine.age * rate).fract() < 0.5);
+        sprite.color.set_alpha(if lit { 1.0 } else { 0.25 });
+
+        let fuse = match &mut mine.fuse {
+            Some(fuse) => fuse,
+            None => continue,
+        };
+        *fuse -= dt;
+        if *fuse > 0.0 {
+            continue;
+        }
+
+        let blast = mine.balance.blast_radius;
+        for (mut enemy, team, enemy_trans) in &mut enemy_query {
+            if mine.team.hurts(*team)
+                && enemy_trans.translation.truncate().distance(position) < blast
+            {
+                enemy.take_damage(mine.damage, DamageKind::Kinetic);
+                debug!(target: "planet_td::damage", enemy = enemy.enemy_id, damage = mine.damage, hp = enemy.hp, "mine blast");
+                if mine.owner.is_some() {
+                    enemy.last_hit_by = mine.owner;
+                }
+            }
+        }
+        if mine.team.hurts(Team::Player) {
+            for (mut player, player_trans) in &mut player_query {
+                if player_trans.translation.truncate().distance(position) < blast {
+                    player.stun = player.stun.max(MINE_STUN);
+                }
+            }
+        }
+        sound_events.send(PlaySound {
+            sound: SoundName::Impact,
+            position: Some(position),
+            caption: Some("mine detonated"),
+        });
+        rumble_events.send(Rumble::new(0.5, 150));
+        commands.entity(entity).despawn();
+    }
+}
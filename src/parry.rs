@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::controls::PlayerInput;
+use crate::teams::{Role, Team};
+use crate::upgrades::RunUpgrades;
+use crate::{Bullet, Player, TimeScale};
+
+/// The parry binding only does something once the upgrade is taken.
+pub fn parry_input(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    upgrades: Res<RunUpgrades>,
+    input: PlayerInput,
+    mut player_query: Query<&mut Player>,
+) {
+    let dt = time_scale.player_delta_seconds(&time);
+    for mut player in &mut player_query {
+        player.parry = (player.parry - dt).max(0.0);
+        player.parry_cooldown = (player.parry_cooldown - dt).max(0.0);
+        let balance = match &upgrades.parry {
+            Some(balance) => balance,
+            None => continue,
+        };
+        if player.parry_cooldown <= 0.0 && input.just_pressed(input.controls(player.index).parry) {
+            player.parry = balance.window_seconds;
+            player.parry_cooldown = balance.cooldown_seconds;
+        }
+    }
+}
+
+/// Shots that would hurt the players and reach one in its parry window
+/// change hands: they fly back as that player's, hitting harder.
+pub fn reflect_shots(
+    mut commands: Commands,
+    upgrades: Res<RunUpgrades>,
+    player_query: Query<(&Player, &Transform)>,
+    mut bullet_query: Query<
+        (
+            Entity,
+            &mut Bullet,
+            &mut Transform,
+            &mut Team,
+            &mut Sprite,
+            Option<&mut Velocity>,
+        ),
+        Without<Player>,
+    >,
+    mut sound_events: EventWriter<PlaySound>,
+) {
+    let balance = match &upgrades.parry {
+        Some(balance) => balance,
+        None => return,
+    };
+    for (entity, mut bullet, mut transform, mut team, mut sprite, velocity) in &mut bullet_query {
+        if bullet.has_hit > 0 || !team.hurts(Team::Player) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let parried = player_query.iter().find(|(player, player_trans)| {
+            player.parry > 0.0
+                && player_trans.translation.truncate().distance(position) < balance.radius
+        });
+        let player = match parried {
+            Some((player, _)) => player,
+            None => continue,
+        };
+        bullet.reflect(player.index, position, balance.damage_multiplier);
+        if let Some(mut velocity) = velocity {
+            velocity.linvel = -velocity.linvel;
+        }
+        transform.rotate_z(std::f32::consts::PI);
+        *team = Team::Player;
+        sprite.color = Color::WHITE;
+        commands
+            .entity(entity)
+            .insert(Team::Player.collision_groups(Role::Shot));
+        sound_events.send(PlaySound {
+            sound: SoundName::Impact,
+            position: Some(position),
+            caption: Some("parry"),
+        });
+    }
+}
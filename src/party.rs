@@ -0,0 +1,217 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::controls::Binding;
+use crate::{AssetHandles, Bullet, EnemyKilled, FontName, Player, Wallet};
+
+/// Local players sharing the keyboard.
+pub const PARTY_MAX: usize = 4;
+
+/// A player hit by a teammate can't shoot for this long.
+const FRIENDLY_FIRE_STUN: f32 = 1.0;
+const FRIENDLY_FIRE_RADIUS: f32 = 12.0;
+
+pub struct PlayerControls {
+    pub left: Binding,
+    pub right: Binding,
+    pub fire: Binding,
+    /// Buys a satellite during the intermission.
+    pub buy: Binding,
+    /// Calls in an ally ship.
+    pub call: Binding,
+    /// Climbs to the next orbit, wrapping back to the surface.
+    pub climb: Binding,
+    /// Opens a short window that reflects incoming shots, with the
+    /// deflector upgrade.
+    pub parry: Binding,
+}
+
+impl PlayerControls {
+    pub fn all(&self) -> [Binding; 7] {
+        [
+            self.left, self.right, self.fire, self.buy, self.call, self.climb, self.parry,
+        ]
+    }
+}
+
+pub const PARTY_CONTROLS: [PlayerControls; PARTY_MAX] = [
+    PlayerControls {
+        left: Binding::Key(KeyCode::KeyA),
+        right: Binding::Key(KeyCode::KeyD),
+        fire: Binding::Key(KeyCode::KeyS),
+        buy: Binding::Key(KeyCode::KeyP),
+        call: Binding::Key(KeyCode::KeyW),
+        climb: Binding::Key(KeyCode::KeyZ),
+        parry: Binding::Key(KeyCode::KeyC),
+    },
+    PlayerControls {
+        left: Binding::Key(KeyCode::KeyJ),
+        right: Binding::Key(KeyCode::KeyL),
+        fire: Binding::Key(KeyCode::KeyK),
+        buy: Binding::Key(KeyCode::KeyI),
+        call: Binding::Key(KeyCode::KeyO),
+        climb: Binding::Key(KeyCode::Semicolon),
+        parry: Binding::Key(KeyCode::Comma),
+    },
+    PlayerControls {
+        left: Binding::Key(KeyCode::ArrowLeft),
+        right: Binding::Key(KeyCode::ArrowRight),
+        fire: Binding::Key(KeyCode::ArrowDown),
+        buy: Binding::Key(KeyCode::ArrowUp),
+        call: Binding::Key(KeyCode::ShiftRight),
+        climb: Binding::Key(KeyCode::ControlRight),
+        parry: Binding::Key(KeyCode::AltRight),
+    },
+    PlayerControls {
+        left: Binding::Key(KeyCode::Numpad4),
+        right: Binding::Key(KeyCode::Numpad6),
+        fire: Binding::Key(KeyCode::Numpad5),
+        buy: Binding::Key(KeyCode::Numpad8),
+        call: Binding::Key(KeyCode::Numpad2),
+        climb: Binding::Key(KeyCode::Numpad0),
+        parry: Binding::Key(KeyCode::NumpadDecimal),
+    },
+];
+
+pub const PARTY_COLORS: [Color; PARTY_MAX] = [
+    Color::WHITE,
+    Color::srgb(0.4, 0.8, 1.0),
+    Color::srgb(1.0, 0.6, 0.2),
+    Color::srgb(0.5, 1.0, 0.4),
+];
+
+/// How a party run is played, picked in the menu.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartyOptions {
+    pub players: usize,
+    /// Shots from a teammate standing too close stun the player they hit.
+    pub friendly_fire: bool,
+    /// Everyone spends from the same wallet, otherwise each player keeps
+    /// the scrap of their kills.
+    pub shared_scrap: bool,
+}
+
+impl Default for PartyOptions {
+    fn default() -> Self {
+        PartyOptions {
+            players: 1,
+            friendly_fire: false,
+            shared_scrap: true,
+        }
+    }
+}
+
+/// Kills of each player in the current run.
+#[derive(Default, Resource)]
+pub struct PartyStats {
+    pub kills: Vec<u32>,
+}
+
+#[derive(Component)]
+pub struct UiTextPartySlot(pub usize);
+
+pub fn setup_party(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    modifiers: Res<RunModifiers>,
+) {
+    let players = modifiers.party.players;
+    commands.insert_resource(PartyStats {
+        kills: vec![0; players],
+    });
+    if players < 2 {
+        return;
+    }
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(5.0),
+                left: Val::Px(15.0),
+                column_gap: Val::Px(20.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for (index, color) in PARTY_COLORS.iter().enumerate().take(players) {
+                parent
+                    .spawn(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: *color,
+                        },
+                    ))
+                    .insert(UiTextPartySlot(index));
+            }
+        });
+}
+
+pub fn track_party_stats(
+    mut stats: ResMut<PartyStats>,
+    mut killed_events: EventReader<EnemyKilled>,
+) {
+    for event in killed_events.read() {
+        if let Some(kills) = event.by.and_then(|by| stats.kills.get_mut(by)) {
+            *kills += 1;
+        }
+    }
+}
+
+pub fn update_party_hud(
+    stats: Res<PartyStats>,
+    wallet: Res<Wallet>,
+    modifiers: Res<RunModifiers>,
+    mut text_query: Query<(&mut Text, &UiTextPartySlot)>,
+) {
+    for (mut text, slot) in &mut text_query {
+        let kills = stats.kills.get(slot.0).copied().unwrap_or(0);
+        text.sections[0].value = if modifiers.party.shared_scrap {
+            format!("p{} {}", slot.0 + 1, kills)
+        } else {
+            format!("p{} {} ${}", slot.0 + 1, kills, wallet.purse(slot.0))
+        };
+    }
+}
+
+/// Teammates' shots stop on the player they hit instead of going through.
+pub fn friendly_fire(
+    time: Res<Time>,
+    modifiers: Res<RunModifiers>,
+    mut player_query: Query<(&mut Player, &Transform)>,
+    mut bullet_query: Query<(&mut Bullet, &Transform)>,
+) {
+    let dt = time.delta_seconds();
+    for (mut player, _) in &mut player_query {
+        player.stun = (player.stun - dt).max(0.0);
+    }
+    if !modifiers.party.friendly_fire {
+        return;
+    }
+    for (mut bullet, bullet_trans) in &mut bullet_query {
+        let owner = match bullet.owner {
+            Some(owner) if bullet.has_hit == 0 => owner,
+            _ => continue,
+        };
+        for (mut player, player_trans) in &mut player_query {
+            if player.index != owner
+                && player_trans.translation.distance(bullet_trans.translation)
+                    < FRIENDLY_FIRE_RADIUS
+            {
+                player.stun = FRIENDLY_FIRE_STUN;
+                bullet.has_hit = 1;
+                break;
+            }
+        }
+    }
+}
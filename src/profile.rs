@@ -0,0 +1,75 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::loadouts::Loadout;
+use crate::storage;
+
+/// Progress kept across runs.
+#[derive(Serialize, Deserialize, Clone, Resource)]
+pub struct Profile {
+    pub unlocked_levels: usize,
+    pub best_scores: Vec<u32>,
+    /// Keys of the codex entries met so far.
+    #[serde(default)]
+    pub codex: Vec<String>,
+    /// Meta upgrade, a destroyed planet goes back in time once per run.
+    #[serde(default)]
+    pub rewind: bool,
+    /// Named run options, newest last.
+    #[serde(default)]
+    pub loadouts: Vec<Loadout>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            unlocked_levels: 1,
+            best_scores: vec![],
+            codex: vec![],
+            rewind: false,
+            loadouts: vec![],
+        }
+    }
+}
+
+impl Profile {
+    pub fn load() -> Profile {
+        storage::load("profile").unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if !storage::save("profile", self) {
+            bevy::log::warn!("failed to save the profile");
+        }
+    }
+
+    pub fn best_score(&self, level: usize) -> u32 {
+        self.best_scores.get(level).copied().unwrap_or(0)
+    }
+
+    /// Records a score, returns true if it beats the previous best.
+    pub fn record_score(&mut self, level: usize, score: u32) -> bool {
+        if self.best_scores.len() <= level {
+            self.best_scores.resize(level + 1, 0);
+        }
+        let best = score > self.best_scores[level];
+        if best {
+            self.best_scores[level] = score;
+        }
+        best
+    }
+
+    pub fn discovered(&self, key: &str) -> bool {
+        self.codex.iter().any(|entry| entry == key)
+    }
+
+    /// Unlocks a codex entry, returns true if it wasn't already.
+    pub fn discover(&mut self, key: &str) -> bool {
+        let new = !self.discovered(key);
+        if new {
+            self.codex.push(key.to_string());
+        }
+        new
+    }
+}
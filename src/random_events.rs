@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use bevy::color::palettes::css::ORANGE;
+use bevy::prelude::*;
+
+use crate::feed::FeedMessage;
+use crate::shields::DamageKind;
+use crate::{AssetHandles, Enemy, FontName, TimeScale};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RandomEventKind {
+    SolarFlare,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RandomEventPhase {
+    Warning,
+    Active,
+}
+
+pub struct ActiveRandomEvent {
+    pub kind: RandomEventKind,
+    pub phase: RandomEventPhase,
+    pub timer: Timer,
+}
+
+#[derive(Resource)]
+pub struct RandomEvents {
+    pub next: Timer,
+    pub current: Option<ActiveRandomEvent>,
+}
+
+const FLARE_WARNING_MS: u64 = 3000;
+const FLARE_ACTIVE_MS: u64 = 4000;
+const FLARE_DAMAGE: f32 = 15.0;
+
+impl Default for RandomEvents {
+    fn default() -> Self {
+        RandomEvents {
+            next: Timer::new(RandomEvents::random_interval(), TimerMode::Once),
+            current: None,
+        }
+    }
+}
+
+impl RandomEvents {
+    fn random_interval() -> Duration {
+        Duration::from_millis(thread_rng().gen_range(40000..70000))
+    }
+
+    pub fn is_active(&self, kind: RandomEventKind) -> bool {
+        matches!(
+            &self.current,
+            Some(ActiveRandomEvent { kind: k, phase: RandomEventPhase::Active, .. }) if *k == kind
+        )
+    }
+
+    /// The solar flare overcharges the weapon for the whole active phase.
+    pub fn weapon_disabled(&self) -> bool {
+        self.is_active(RandomEventKind::SolarFlare)
+    }
+}
+
+#[derive(Component)]
+pub struct UiTextRandomEvent;
+
+pub fn setup_random_events(mut commands: Commands, handles: Res<AssetHandles>) {
+    commands.insert_resource(RandomEvents::default());
+
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: handles
+                        .fonts
+                        .get(&FontName::IosevkaRegular)
+                        .unwrap()
+                        .clone_weak(),
+                    font_size: 40.0,
+                    color: Color::from(ORANGE),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Px(15.0),
+                ..default()
+            }),
+        )
+        .insert(UiTextRandomEvent);
+}
+
+pub fn update_random_events(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut events: ResMut<RandomEvents>,
+    mut enemy_query: Query<&mut Enemy>,
+    mut text_query: Query<&mut Text, With<UiTextRandomEvent>>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    let mut finished = false;
+    if let Some(current) = &mut events.current {
+        current.timer.tick(time_scale.delta(&time));
+        if current.timer.finished() {
+            match current.phase {
+                RandomEventPhase::Warning => {
+                    current.phase = RandomEventPhase::Active;
+                    current.timer =
+                        Timer::new(Duration::from_millis(FLARE_ACTIVE_MS), TimerMode::Once);
+                    for mut enemy in &mut enemy_query {
+                        enemy.take_damage(FLARE_DAMAGE, DamageKind::Energy);
+                    }
+                    feed.send(FeedMessage::new("solar flare hit", Color::from(ORANGE)));
+                }
+                RandomEventPhase::Active => finished = true,
+            }
+        }
+    } else {
+        events.next.tick(time_scale.delta(&time));
+        if events.next.finished() {
+            events.current = Some(ActiveRandomEvent {
+                kind: RandomEventKind::SolarFlare,
+                phase: RandomEventPhase::Warning,
+                timer: Timer::new(Duration::from_millis(FLARE_WARNING_MS), TimerMode::Once),
+            });
+        }
+    }
+
+    if finished {
+        events.current = None;
+        events.next = Timer::new(RandomEvents::random_interval(), TimerMode::Once);
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = match &events.current {
+            Some(ActiveRandomEvent {
+                phase: RandomEventPhase::Warning,
+                timer,
+                ..
+            }) => {
+                let left = timer.duration().as_secs_f32() - timer.elapsed_secs();
+                format!("solar flare in {:.1}", left)
+            }
+            Some(ActiveRandomEvent {
+                phase: RandomEventPhase::Active,
+                ..
+            }) => "solar flare! weapon overcharged".to_string(),
+            None => "".to_string(),
+        };
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::color::palettes::css::{LIME, ORANGE_RED};
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::feed::FeedMessage;
+use crate::settings::Settings;
+use crate::{Planet, Spawner, WaveEnded};
+
+/// Planet hp repaired by a press on the mark.
+const REPAIR_BONUS: f32 = 10.0;
+/// Full sweeps of the needle per second.
+const NEEDLE_SPEED: f32 = 0.8;
+/// Half width of the mark, as a fraction of the bar.
+const MARK_HALF_WIDTH: f32 = 0.08;
+const BAR_WIDTH: f32 = 160.0;
+/// Pixels below the planet rim.
+const BAR_OFFSET: f32 = 80.0;
+
+/// Timing minigame of the intermission: a needle sweeps a bar and [f]
+/// repairs the planet if it stops on the mark. One try per intermission.
+#[derive(Default, Resource)]
+pub struct RepairMinigame {
+    pub active: bool,
+    /// Seconds since the bar showed up.
+    pub elapsed: f32,
+}
+
+impl RepairMinigame {
+    /// Needle position along the bar, from 0.0 to 1.0 and back.
+    pub fn needle(&self) -> f32 {
+        let t = (self.elapsed * NEEDLE_SPEED).fract() * 2.0;
+        if t > 1.0 {
+            2.0 - t
+        } else {
+            t
+        }
+    }
+
+    pub fn on_mark(&self) -> bool {
+        (self.needle() - 0.5).abs() <= MARK_HALF_WIDTH
+    }
+}
+
+pub fn setup_repair(mut minigame: ResMut<RepairMinigame>) {
+    *minigame = RepairMinigame::default();
+}
+
+pub fn repair_minigame(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    modifiers: Res<RunModifiers>,
+    mut minigame: ResMut<RepairMinigame>,
+    mut wave_events: EventReader<WaveEnded>,
+    mut planet_query: Query<&mut Planet>,
+    spawner_query: Query<&Spawner>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    // speedruns have no intermission to play it in
+    if wave_events.read().last().is_some() && !modifiers.speedrun {
+        *minigame = RepairMinigame {
+            active: true,
+            elapsed: 0.0,
+        };
+        if !settings.auto_repair {
+            feed.send(FeedMessage::new(
+                "[f] on the mark to repair",
+                Color::from(LIME),
+            ));
+        }
+    }
+    if !minigame.active {
+        return;
+    }
+    if !spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission())
+    {
+        minigame.active = false;
+        return;
+    }
+    minigame.elapsed += time.delta_seconds();
+
+    // accessibility: the press always lands
+    let landed = if settings.auto_repair {
+        true
+    } else if keyboard_input.just_pressed(KeyCode::KeyF) {
+        minigame.on_mark()
+    } else {
+        return;
+    };
+    minigame.active = false;
+    if landed {
+        for mut planet in &mut planet_query {
+            planet.hp = (planet.hp + REPAIR_BONUS).min(planet.max_hp);
+        }
+        feed.send(FeedMessage::new(
+            format!("repaired {} hp", REPAIR_BONUS),
+            Color::from(LIME),
+        ));
+    } else {
+        feed.send(FeedMessage::new("repair missed", Color::from(ORANGE_RED)));
+    }
+}
+
+pub fn draw_repair_minigame(
+    minigame: Res<RepairMinigame>,
+    planet_query: Query<&Planet>,
+    mut gizmos: Gizmos,
+) {
+    let planet = match planet_query.get_single() {
+        Ok(planet) if minigame.active => planet,
+        _ => return,
+    };
+    let y = -(planet.size * 0.5 + BAR_OFFSET);
+    let at = |fraction: f32| Vec2::new((fraction - 0.5) * BAR_WIDTH, y);
+    gizmos.line_2d(at(0.0), at(1.0), Color::srgba(1.0, 1.0, 1.0, 0.4));
+    gizmos.line_2d(
+        at(0.5 - MARK_HALF_WIDTH),
+        at(0.5 + MARK_HALF_WIDTH),
+        Color::from(LIME),
+    );
+    let needle = at(minigame.needle());
+    gizmos.line_2d(needle - Vec2::Y * 8.0, needle + Vec2::Y * 8.0, Color::WHITE);
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use crate::balance::Balance;
+use crate::biomes::{atmosphere_keep, Biome};
+use crate::enemy_grid::EnemyGrid;
+use crate::level::{CurrentLevel, Levels};
+use crate::settings::Settings;
+use crate::upgrades::RunUpgrades;
+use crate::{fire_direction, Planet, Player, PLAYER_BULLET_SPEED};
+
+/// Seconds of flight between two dots of the preview.
+const PREVIEW_STEP: f32 = 0.05;
+/// Dots drawn at most, a shot slowed down by the atmosphere could
+/// otherwise take forever to reach the end of its range.
+const PREVIEW_MAX_DOTS: usize = 100;
+const DOT_RADIUS: f32 = 2.0;
+const DOT_ALPHA: f32 = 0.5;
+const RETICLE_RADIUS: f32 = 10.0;
+
+/// Dots where the next shot of each player will be every few hundredths
+/// of a second, fading over the damage falloff, and a reticle where it
+/// stops dealing full damage.
+pub fn draw_trajectory_preview(
+    settings: Res<Settings>,
+    balance: Res<Balance>,
+    upgrades: Res<RunUpgrades>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    enemy_grid: Res<EnemyGrid>,
+    planet_query: Query<&Planet>,
+    player_query: Query<&Transform, With<Player>>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.trajectory_preview {
+        return;
+    }
+    let weapon = upgrades.weapon(&balance.weapons.player);
+    let atmosphere = match planet_query.get_single() {
+        Ok(planet) if levels.list[current_level.0].biome == Biome::GasGiant => Some(planet),
+        _ => None,
+    };
+
+    for transform in &player_query {
+        let origin = transform.translation.truncate();
+        let direction = fire_direction(transform, settings.aim_assist, &enemy_grid);
+        let mut position = origin;
+        let mut speed = PLAYER_BULLET_SPEED;
+        let mut reticle = origin;
+        for _ in 0..PREVIEW_MAX_DOTS {
+            position += direction * speed * PREVIEW_STEP;
+            if let Some(planet) = atmosphere {
+                speed *= atmosphere_keep(planet, position, PREVIEW_STEP);
+            }
+            let distance = position.distance(origin);
+            if distance <= weapon.range {
+                reticle = position;
+            }
+            let fade = ((distance - weapon.range) / weapon.falloff.max(1.0)).max(0.0);
+            if fade >= 1.0 {
+                break;
+            }
+            gizmos.circle_2d(
+                position,
+                DOT_RADIUS,
+                Color::srgba(1.0, 1.0, 1.0, DOT_ALPHA * (1.0 - fade)),
+            );
+        }
+        gizmos.circle_2d(
+            reticle,
+            RETICLE_RADIUS,
+            Color::srgba(1.0, 1.0, 1.0, DOT_ALPHA),
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::color::palettes::css::ORANGE;
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::enemies::{EnemyDefinitions, MovementMode};
+use crate::feed::FeedMessage;
+use crate::spawner::Spawner;
+use crate::{Enemy, TimeScale, ARENA_MARGIN};
+
+/// Pixels per second of the enemies fleeing the field.
+const RETREAT_SPEED: f32 = 160.0;
+
+/// Sent by the spawner when a wave retreats for its reinforcements.
+#[derive(Event)]
+pub struct RetreatSounded;
+
+/// Flees off the field, it counts neither as a kill nor as a leak.
+#[derive(Component)]
+pub struct Retreating;
+
+/// Turns the enemies on the field around, bosses hold their ground.
+pub fn sound_retreat(
+    mut commands: Commands,
+    definitions: Res<EnemyDefinitions>,
+    enemy_query: Query<(Entity, &Enemy), Without<Retreating>>,
+    mut retreat_events: EventReader<RetreatSounded>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    if retreat_events.read().count() == 0 {
+        return;
+    }
+    for (entity, enemy) in &enemy_query {
+        if !definitions.get(enemy.enemy_id).boss {
+            commands.entity(entity).insert(Retreating);
+        }
+    }
+    feed.send(FeedMessage::new(
+        "the wave retreats, reinforcements incoming".to_string(),
+        Color::from(ORANGE),
+    ));
+}
+
+/// Flies the retreating enemies straight out, they are gone past the
+/// edge of the arena. They can still be shot on the way out.
+pub fn retreat_enemies(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    spawner_query: Query<&Spawner>,
+    mut enemy_query: Query<(Entity, &Enemy, &mut Transform, &mut Velocity), With<Retreating>>,
+) {
+    let ring = match spawner_query.get_single() {
+        Ok(spawner) => spawner.size * 0.5,
+        Err(_) => return,
+    };
+    let dt = time_scale.delta_seconds(&time);
+    for (entity, enemy, mut transform, mut velocity) in &mut enemy_query {
+        let position = transform.translation.truncate();
+        if position.length() > ring + ARENA_MARGIN {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let outward = position.normalize_or_zero();
+        velocity.linvel = outward * RETREAT_SPEED;
+        if enemy.movement == MovementMode::Kinematic {
+            transform.translation += (velocity.linvel * dt).extend(0.0);
+        }
+        transform.rotation = Quat::from_rotation_z(outward.y.atan2(outward.x));
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+use bevy::color::palettes::css::MEDIUM_SPRING_GREEN;
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::enemies::EnemyDefinitions;
+use crate::{spawn_enemy, AssetHandles, Enemy, EnemyKilled, TimeScale};
+
+/// Seconds a death can be revived for.
+const DEATH_RECORD_SECONDS: f32 = 10.0;
+/// Pixels from a reviver a death has to be.
+const REVIVE_RANGE: f32 = 200.0;
+/// Seconds of channeling to bring an enemy back.
+const CHANNEL_SECONDS: f32 = 3.0;
+/// Share of the hp the revived come back with.
+const REVIVE_HP: f32 = 0.5;
+const RING_RADIUS: f32 = 14.0;
+
+#[derive(Clone, Copy)]
+pub struct DeathRecord {
+    pub enemy_id: u32,
+    pub position: Vec2,
+    /// Seconds since the death.
+    pub age: f32,
+}
+
+/// Recent deaths, the ones the revivers can bring back.
+#[derive(Default, Resource)]
+pub struct DeathRecords {
+    pub deaths: VecDeque<DeathRecord>,
+}
+
+/// A reviver and the death it is channeling on.
+#[derive(Component, Default)]
+pub struct Channel {
+    pub corpse: Option<DeathRecord>,
+    pub progress: f32,
+    /// Hp when the channel started, any damage interrupts it.
+    pub hp: f32,
+}
+
+/// Brought back by a reviver, without a bounty the second time.
+#[derive(Component)]
+pub struct Revived;
+
+pub fn setup_revival(mut commands: Commands) {
+    commands.insert_resource(DeathRecords::default());
+}
+
+pub fn record_deaths(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    definitions: Res<EnemyDefinitions>,
+    mut records: ResMut<DeathRecords>,
+    mut killed_events: EventReader<EnemyKilled>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    for record in &mut records.deaths {
+        record.age += dt;
+    }
+    while records
+        .deaths
+        .front()
+        .is_some_and(|record| record.age > DEATH_RECORD_SECONDS)
+    {
+        records.deaths.pop_front();
+    }
+    for event in killed_events.read() {
+        let definition = definitions.get(event.enemy_id);
+        // bosses stay dead, and revivers don't revive each other
+        if definition.boss || definition.revives {
+            continue;
+        }
+        records.deaths.push_back(DeathRecord {
+            enemy_id: event.enemy_id,
+            position: event.position,
+            age: 0.0,
+        });
+    }
+}
+
+pub fn start_channels(
+    mut commands: Commands,
+    definitions: Res<EnemyDefinitions>,
+    enemy_query: Query<(Entity, &Enemy), Added<Enemy>>,
+) {
+    for (entity, enemy) in &enemy_query {
+        if definitions.get(enemy.enemy_id).revives {
+            commands.entity(entity).insert(Channel::default());
+        }
+    }
+}
+
+/// Revivers pick the closest recent death in range and channel on it.
+/// Any damage interrupts them and the death is lost, so they are worth
+/// shooting first.
+pub fn update_channels(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    modifiers: Res<RunModifiers>,
+    mut records: ResMut<DeathRecords>,
+    mut reviver_query: Query<(&Enemy, &Transform, &mut Channel)>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    for (enemy, transform, mut channel) in &mut reviver_query {
+        let position = transform.translation.truncate();
+        let corpse = match channel.corpse {
+            Some(corpse) => corpse,
+            None => {
+                let closest = records
+                    .deaths
+                    .iter()
+                    .enumerate()
+                    .map(|(index, record)| (index, record.position.distance(position)))
+                    .filter(|(_, distance)| *distance < REVIVE_RANGE)
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((index, _)) = closest {
+                    *channel = Channel {
+                        corpse: records.deaths.remove(index),
+                        progress: 0.0,
+                        hp: enemy.hp,
+                    };
+                }
+                continue;
+            }
+        };
+        if enemy.hp < channel.hp || enemy.has_hit > 0 {
+            *channel = Channel::default();
+            continue;
+        }
+        channel.progress += dt / CHANNEL_SECONDS;
+        if channel.progress < 1.0 {
+            continue;
+        }
+        let revived = spawn_enemy(
+            &mut commands,
+            &handles,
+            &definitions,
+            corpse.enemy_id,
+            corpse.position.extend(3.0),
+            Vec2::ZERO,
+            modifiers.enemy_hp_multiplier() * REVIVE_HP,
+        );
+        commands.entity(revived).insert(Revived);
+        *channel = Channel::default();
+    }
+}
+
+pub fn strip_revived_bounty(mut enemy_query: Query<&mut Enemy, Added<Revived>>) {
+    for mut enemy in &mut enemy_query {
+        enemy.scrap = 0;
+    }
+}
+
+/// A beam from the reviver to the death, and a ring filling up on it.
+pub fn draw_channels(reviver_query: Query<(&Transform, &Channel)>, mut gizmos: Gizmos) {
+    let color = Color::from(MEDIUM_SPRING_GREEN);
+    for (transform, channel) in &reviver_query {
+        let corpse = match channel.corpse {
+            Some(corpse) => corpse,
+            None => continue,
+        };
+        gizmos.line_2d(transform.translation.truncate(), corpse.position, color);
+        let arc = channel.progress.min(1.0) * std::f32::consts::TAU;
+        // the gizmo arcs are centered on their direction, this one grows
+        // clockwise from the top
+        gizmos.arc_2d(corpse.position, arc * 0.5, arc, RING_RADIUS, color);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::color::palettes::css::CYAN;
+use bevy::prelude::*;
+
+use crate::audio::{PlaySound, SoundName};
+use crate::conditions::{defeat_reason, RunModifiers};
+use crate::feed::FeedMessage;
+use crate::level::{CurrentLevel, Levels};
+use crate::profile::Profile;
+use crate::snapshot::{RunSnapshot, RunState};
+use crate::TimeScale;
+
+/// How far back a rewind goes.
+pub const REWIND_SECONDS: usize = 10;
+
+/// Snapshots of the last seconds of the run, a defeat goes back to the
+/// oldest one, once per run.
+#[derive(Resource)]
+pub struct Rewind {
+    pub snapshots: VecDeque<RunSnapshot>,
+    pub timer: Timer,
+    pub used: bool,
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Rewind {
+            snapshots: VecDeque::new(),
+            timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating),
+            used: false,
+        }
+    }
+}
+
+/// The rewind is a meta upgrade, unlocked by the first level cleared.
+pub fn setup_rewind(mut commands: Commands, profile: Res<Profile>, modifiers: Res<RunModifiers>) {
+    let available = profile.rewind && modifiers.practice.is_none() && !modifiers.sandbox;
+    commands.insert_resource(Rewind {
+        used: !available,
+        ..default()
+    });
+}
+
+pub fn take_snapshots(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut rewind: ResMut<Rewind>,
+    state: RunState,
+) {
+    if rewind.used {
+        return;
+    }
+    rewind.timer.tick(time_scale.delta(&time));
+    if !rewind.timer.just_finished() {
+        return;
+    }
+    if let Some(snapshot) = state.capture() {
+        if rewind.snapshots.len() > REWIND_SECONDS {
+            rewind.snapshots.pop_front();
+        }
+        rewind.snapshots.push_back(snapshot);
+    }
+}
+
+/// Runs before the defeat is evaluated, on the same conditions of the
+/// level, so that the game over never shows.
+pub fn rewind_on_death(
+    mut commands: Commands,
+    mut rewind: ResMut<Rewind>,
+    mut state: RunState,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    modifiers: Res<RunModifiers>,
+    mut feed: EventWriter<FeedMessage>,
+    mut sound_events: EventWriter<PlaySound>,
+) {
+    let defeat = &levels.list[current_level.0].defeat;
+    let defeated = state
+        .planet_hp()
+        .is_some_and(|hp| defeat_reason(defeat, hp).is_some());
+    if rewind.used || !defeated {
+        return;
+    }
+    let snapshot = match rewind.snapshots.pop_front() {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+    rewind.used = true;
+    rewind.snapshots.clear();
+    state.restore(&mut commands, &snapshot, modifiers.enemy_hp_multiplier());
+    sound_events.send(PlaySound {
+        sound: SoundName::Warp,
+        position: None,
+        caption: Some("time rewound"),
+    });
+    feed.send(FeedMessage::new(
+        format!("rewound {} seconds, no more this run", REWIND_SECONDS),
+        Color::from(CYAN),
+    ));
+}
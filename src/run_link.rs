@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::focus::InputCapture;
+use crate::level::{CurrentLevel, Levels};
+use crate::party::PARTY_MAX;
+use crate::spawner::RunSeed;
+use crate::AppState;
+
+/// Run configuration carried in the query of the page url, so that a
+/// shared link starts the same run: `?level=belt&seed=42&ng=1&speedrun=1`.
+#[derive(Default, Resource)]
+pub struct RunLink {
+    pub level: String,
+    pub seed: Option<u64>,
+    pub ng_plus: u32,
+    pub speedrun: bool,
+    pub endless: bool,
+    pub players: usize,
+    pub friendly_fire: bool,
+    pub shared_scrap: bool,
+}
+
+impl RunLink {
+    /// None unless the query names a level.
+    pub fn parse(query: &str) -> Option<RunLink> {
+        let mut link = RunLink {
+            players: 1,
+            shared_scrap: true,
+            ..default()
+        };
+        let mut level = None;
+        for pair in query.trim_start_matches('?').split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            // a pair that doesn't decode is left out, like an unknown key
+            let (key, value) = match (decode(key), decode(value)) {
+                (Some(key), Some(value)) => (key, value),
+                _ => continue,
+            };
+            let value = value.as_str();
+            let flag = value == "1";
+            match key.as_str() {
+                "level" => level = Some(value.to_string()),
+                "seed" => link.seed = value.parse().ok(),
+                "ng" => link.ng_plus = value.parse().unwrap_or(0),
+                "speedrun" => link.speedrun = flag,
+                "endless" => link.endless = flag,
+                "players" => link.players = value.parse().unwrap_or(1).clamp(1, PARTY_MAX),
+                "ff" => link.friendly_fire = flag,
+                "shared" => link.shared_scrap = flag,
+                _ => {}
+            }
+        }
+        link.level = level?;
+        Some(link)
+    }
+
+    pub fn query(&self) -> String {
+        let mut query = format!("?level={}", encode(&self.level));
+        if let Some(seed) = self.seed {
+            query += &format!("&seed={}", seed);
+        }
+        query += &format!(
+            "&ng={}&speedrun={}&endless={}&players={}&ff={}&shared={}",
+            self.ng_plus,
+            self.speedrun as u8,
+            self.endless as u8,
+            self.players,
+            self.friendly_fire as u8,
+            self.shared_scrap as u8
+        );
+        query
+    }
+
+    pub fn from_run(level: &str, seed: u64, modifiers: &RunModifiers) -> RunLink {
+        RunLink {
+            level: level.to_string(),
+            seed: Some(seed),
+            ng_plus: modifiers.ng_plus,
+            speedrun: modifiers.speedrun,
+            endless: modifiers.endless,
+            players: modifiers.party.players,
+            friendly_fire: modifiers.party.friendly_fire,
+            shared_scrap: modifiers.party.shared_scrap,
+        }
+    }
+
+    /// Modifiers of the linked run, with its level index.
+    pub fn modifiers(&self, levels: &Levels) -> Option<(usize, RunModifiers)> {
+        let level = levels
+            .list
+            .iter()
+            .position(|level| level.name == self.level)?;
+        let mut modifiers = RunModifiers {
+            ng_plus: self.ng_plus,
+            speedrun: self.speedrun,
+            endless: self.endless,
+            seed: self.seed,
+            ..default()
+        };
+        modifiers.party.players = self.players;
+        modifiers.party.friendly_fire = self.friendly_fire;
+        modifiers.party.shared_scrap = self.shared_scrap;
+        Some((level, modifiers))
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of a url.
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded += &format!("%{:02X}", byte),
+        }
+    }
+    encoded
+}
+
+/// None on a truncated escape, one that isn't hex or bytes that aren't
+/// utf-8. A plus is a space, as browsers write forms.
+fn decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = value.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'%' => {
+                let high = (rest.next()? as char).to_digit(16)?;
+                let low = (rest.next()? as char).to_digit(16)?;
+                (high * 16 + low) as u8
+            }
+            b'+' => b' ',
+            _ => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The link comes from the page url on the web, and from the
+/// PLANET_LINK environment variable on native.
+pub fn read_run_link(mut commands: Commands) {
+    if let Some(link) = link_query().as_deref().and_then(RunLink::parse) {
+        commands.insert_resource(link);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn link_query() -> Option<String> {
+    web_sys::window()?.location().search().ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn link_query() -> Option<String> {
+    std::env::var("PLANET_LINK").ok()
+}
+
+/// Starts the linked run straight from the menu, once the page has focus.
+pub fn launch_run_link(
+    mut commands: Commands,
+    link: Res<RunLink>,
+    capture: Res<InputCapture>,
+    levels: Res<Levels>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if !capture.started {
+        return;
+    }
+    commands.remove_resource::<RunLink>();
+    let (level, linked) = match link.modifiers(&levels) {
+        Some(run) => run,
+        None => {
+            warn!("the link names an unknown level {}", link.level);
+            return;
+        }
+    };
+    current_level.0 = level;
+    *modifiers = linked;
+    state.set(AppState::InGame);
+}
+
+/// Keeps the page url pointing at the current run, ready to be shared.
+pub fn write_run_link(
+    seed: Res<RunSeed>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+) {
+    // practice and sandbox runs can't be set up from a link
+    if modifiers.practice.is_some() || modifiers.sandbox {
+        return;
+    }
+    let link = RunLink::from_run(&levels.list[current_level.0].name, seed.0, &modifiers);
+    replace_page_query(&link.query());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn replace_page_query(query: &str) {
+    let history = web_sys::window().and_then(|window| window.history().ok());
+    let replaced = history.map_or(false, |history| {
+        history
+            .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(query))
+            .is_ok()
+    });
+    if !replaced {
+        warn!("failed to update the run link");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn replace_page_query(query: &str) {
+    info!(query, "run link");
+}
@@ -0,0 +1,142 @@
+use bevy::color::palettes::css::{GRAY, GREEN, RED};
+use bevy::prelude::*;
+
+use crate::conditions::{RunClock, RunModifiers};
+use crate::leaderboard::Leaderboard;
+use crate::level::{CurrentLevel, Levels};
+use crate::settings::Settings;
+use crate::{AssetHandles, FontName, WaveEnded};
+
+#[derive(Component)]
+pub struct UiRunTimer;
+
+#[derive(Component)]
+pub struct UiTextRunTimer;
+
+#[derive(Component)]
+pub struct UiTextRunSplit;
+
+pub fn format_run_time(seconds: f32) -> String {
+    let minutes = (seconds / 60.0) as u32;
+    format!("{:02}:{:05.2}", minutes, seconds - minutes as f32 * 60.0)
+}
+
+pub fn setup_run_timer(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    settings: Res<Settings>,
+) {
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                right: Val::Px(15.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            visibility: timer_visibility(settings.show_run_timer),
+            ..default()
+        })
+        .insert(UiRunTimer)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    format_run_time(0.0),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(UiTextRunTimer);
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: 20.0,
+                        color: Color::from(GRAY),
+                    },
+                ))
+                .insert(UiTextRunSplit);
+        });
+}
+
+fn timer_visibility(shown: bool) -> Visibility {
+    if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+pub fn record_splits(mut clock: ResMut<RunClock>, mut wave_events: EventReader<WaveEnded>) {
+    for _ in wave_events.read() {
+        let seconds = clock.seconds;
+        clock.splits.push(seconds);
+    }
+}
+
+pub fn toggle_run_timer(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut root_query: Query<&mut Visibility, With<UiRunTimer>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        settings.show_run_timer = !settings.show_run_timer;
+        for mut visibility in &mut root_query {
+            *visibility = timer_visibility(settings.show_run_timer);
+        }
+    }
+}
+
+/// Shows the run time and how the last split compares with the best
+/// recorded run of the same level and mode.
+pub fn update_run_timer(
+    clock: Res<RunClock>,
+    leaderboard: Res<Leaderboard>,
+    modifiers: Res<RunModifiers>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    mut timer_query: Query<&mut Text, (With<UiTextRunTimer>, Without<UiTextRunSplit>)>,
+    mut split_query: Query<&mut Text, (With<UiTextRunSplit>, Without<UiTextRunTimer>)>,
+) {
+    for mut text in &mut timer_query {
+        text.sections[0].value = format_run_time(clock.seconds);
+    }
+
+    let (index, split) = match clock.splits.iter().enumerate().last() {
+        Some((index, split)) => (index, *split),
+        None => return,
+    };
+    let reference = leaderboard
+        .reference_splits(&levels.list[current_level.0].name, modifiers.speedrun)
+        .and_then(|splits| splits.get(index));
+    for mut text in &mut split_query {
+        let section = &mut text.sections[0];
+        match reference {
+            Some(reference) => {
+                let delta = split - reference;
+                section.value = format!("wave {} {:+.2}", index + 1, delta);
+                section.style.color = if delta <= 0.0 {
+                    Color::from(GREEN)
+                } else {
+                    Color::from(RED)
+                };
+            }
+            None => {
+                section.value = format!("wave {} {}", index + 1, format_run_time(split));
+                section.style.color = Color::from(GRAY);
+            }
+        }
+    }
+}
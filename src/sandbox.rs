@@ -0,0 +1,194 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::color::palettes::css::GRAY;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::allies::spawn_ally;
+use crate::balance::Balance;
+use crate::conditions::{ResetRun, RunModifiers};
+use crate::enemies::EnemyDefinitions;
+use crate::level::{spawn_hazard, HazardDefinition, HazardKind};
+use crate::ultimate::Ultimate;
+use crate::{spawn_enemy, AppState, AssetHandles, FontName, Wallet};
+
+const SANDBOX_SCRAP: u32 = 100;
+
+/// Spawns an enemy at the given position, standing still.
+#[derive(Event)]
+pub struct SpawnEnemy {
+    pub enemy_id: u32,
+    pub position: Vec2,
+}
+
+#[derive(Event)]
+pub struct SpawnHazard(pub HazardDefinition);
+
+#[derive(Clone, Copy)]
+pub enum Powerup {
+    Scrap(u32),
+    UltimateCharge,
+    /// An ally ship warps in.
+    Ally,
+}
+
+#[derive(Event)]
+pub struct GrantPowerup(pub Powerup);
+
+#[derive(Component)]
+pub struct UiSandboxHint;
+
+pub fn setup_sandbox(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    modifiers: Res<RunModifiers>,
+) {
+    if !modifiers.sandbox {
+        return;
+    }
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(50.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "[1-9] enemy [h] moon [j] asteroid [m] scrap [u] ultimate [n] ally [esc] menu",
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 20.0,
+                        color: Color::from(GRAY),
+                    },
+                ))
+                .insert(UiSandboxHint);
+        });
+}
+
+fn cursor_world_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(transform, cursor)
+}
+
+/// Keyboard shortcuts that place things under the cursor.
+pub fn sandbox_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    modifiers: Res<RunModifiers>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut enemy_events: EventWriter<SpawnEnemy>,
+    mut hazard_events: EventWriter<SpawnHazard>,
+    mut powerup_events: EventWriter<GrantPowerup>,
+    mut reset_events: EventWriter<ResetRun>,
+) {
+    if !modifiers.sandbox {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        reset_events.send(ResetRun { to: AppState::Menu });
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        powerup_events.send(GrantPowerup(Powerup::Scrap(SANDBOX_SCRAP)));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyU) {
+        powerup_events.send(GrantPowerup(Powerup::UltimateCharge));
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        powerup_events.send(GrantPowerup(Powerup::Ally));
+    }
+
+    let position = match cursor_world_position(&window_query, &camera_query) {
+        Some(position) => position,
+        None => return,
+    };
+
+    let digits = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    for (enemy_id, key) in digits.iter().enumerate() {
+        if keyboard_input.just_pressed(*key) {
+            enemy_events.send(SpawnEnemy {
+                enemy_id: enemy_id as u32,
+                position,
+            });
+        }
+    }
+
+    for (key, kind, size) in [
+        (KeyCode::KeyH, HazardKind::Moon, 96.0),
+        (KeyCode::KeyJ, HazardKind::Asteroid, 32.0),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            hazard_events.send(SpawnHazard(HazardDefinition {
+                kind,
+                orbit: position.length(),
+                size,
+                angle: position.y.atan2(position.x),
+                speed: 0.2,
+            }));
+        }
+    }
+}
+
+/// Applies the spawn requests, from the sandbox shortcuts or elsewhere.
+pub fn sandbox_spawns(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<EnemyDefinitions>,
+    balance: Res<Balance>,
+    modifiers: Res<RunModifiers>,
+    mut wallet: ResMut<Wallet>,
+    mut ultimate: ResMut<Ultimate>,
+    mut enemy_events: EventReader<SpawnEnemy>,
+    mut hazard_events: EventReader<SpawnHazard>,
+    mut powerup_events: EventReader<GrantPowerup>,
+) {
+    for event in enemy_events.read() {
+        if event.enemy_id as usize >= definitions.list.len() {
+            warn!("no enemy with id {}", event.enemy_id);
+            continue;
+        }
+        spawn_enemy(
+            &mut commands,
+            &handles,
+            &definitions,
+            event.enemy_id,
+            event.position.extend(3.0),
+            Vec2::ZERO,
+            modifiers.enemy_hp_multiplier(),
+        );
+    }
+    for SpawnHazard(hazard) in hazard_events.read() {
+        spawn_hazard(&mut commands, &handles, hazard);
+    }
+    for GrantPowerup(powerup) in powerup_events.read() {
+        match powerup {
+            Powerup::Scrap(scrap) => wallet.scrap += scrap,
+            Powerup::UltimateCharge => ultimate.charge = balance.ultimate.charge_max,
+            Powerup::Ally => spawn_ally(&mut commands, &handles, FRAC_PI_2),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use bevy::color::palettes::css::{CYAN, ORANGE_RED};
+use bevy::prelude::*;
+
+use bevy_rapier2d::{pipeline::CollisionEvent::*, prelude::*};
+
+use crate::attachments::{Attachments, WeaponSlot};
+use crate::balance::Balance;
+use crate::conditions::RunModifiers;
+use crate::controls::PlayerInput;
+use crate::enemy_grid::EnemyGrid;
+use crate::feed::FeedMessage;
+use crate::idle::IDLE_EFFICIENCY;
+use crate::targeting::Threat;
+use crate::teams::{Role, Team};
+use crate::units::SATELLITE_RADIUS;
+use crate::{spawn_bullet, AssetHandles, Enemy, Planet, ShotFired, Spawner, TimeScale, Wallet};
+
+pub const SATELLITE_COST: u32 = 50;
+pub const SATELLITE_MAX: usize = 3;
+
+const SATELLITE_HP: f32 = 3.0;
+const SATELLITE_ORBIT: f32 = 80.0;
+const SATELLITE_ANGULAR_SPEED: f32 = 0.6;
+const SATELLITE_RANGE: f32 = 400.0;
+/// Satellites shoot back, so they draw more attention than walls.
+const SATELLITE_THREAT: f32 = 3.0;
+
+#[derive(Component)]
+pub struct Satellite {
+    pub angle: f32,
+    pub hp: f32,
+    pub cooldown: Timer,
+}
+
+/// Satellites are bought with scrap during the intermission, each party
+/// member pays from their own purse unless the scrap is shared.
+pub fn buy_satellite(
+    mut commands: Commands,
+    input: PlayerInput,
+    mut wallet: ResMut<Wallet>,
+    spawner_query: Query<&Spawner>,
+    player_query: Query<&Player>,
+    satellite_query: Query<&Satellite>,
+) {
+    let buyer = match player_query
+        .iter()
+        .find(|player| input.just_pressed(input.controls(player.index).buy))
+    {
+        Some(player) => player.index,
+        None => return,
+    };
+    if !spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission())
+    {
+        return;
+    }
+    let count = satellite_query.iter().count();
+    let purse = wallet.purse_mut(buyer);
+    if count >= SATELLITE_MAX || *purse < SATELLITE_COST {
+        return;
+    }
+    *purse -= SATELLITE_COST;
+
+    spawn_satellite(&mut commands, next_satellite_angle(&satellite_query));
+}
+
+/// Spreads the satellites evenly on the orbit.
+pub fn next_satellite_angle(satellite_query: &Query<&Satellite>) -> f32 {
+    let count = satellite_query.iter().count();
+    satellite_query
+        .iter()
+        .next()
+        .map(|first| first.angle)
+        .unwrap_or(0.0)
+        + count as f32 * 2.0 * std::f32::consts::PI / SATELLITE_MAX as f32
+}
+
+pub fn spawn_satellite(commands: &mut Commands, angle: f32) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::from(CYAN),
+                custom_size: Some(Vec2::new(12.0, 12.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 2.0),
+            ..default()
+        })
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Collider::ball(SATELLITE_RADIUS))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Team::Player)
+        .insert(Team::Player.collision_groups(Role::Body))
+        .insert(Threat(SATELLITE_THREAT))
+        .insert(Satellite {
+            angle,
+            hp: SATELLITE_HP,
+            cooldown: Timer::new(Duration::from_millis(1500), TimerMode::Once),
+        });
+}
+
+pub fn orbit_satellites(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    planet_query: Query<&Planet>,
+    mut satellite_query: Query<(&mut Satellite, &mut Transform)>,
+) {
+    let planet = match planet_query.get_single() {
+        Ok(planet) => planet,
+        Err(_) => return,
+    };
+    let radius = planet.size * 0.5 + SATELLITE_ORBIT;
+    for (mut satellite, mut transform) in &mut satellite_query {
+        satellite.angle += SATELLITE_ANGULAR_SPEED * time_scale.delta_seconds(&time);
+        transform.translation = Vec3::new(
+            f32::cos(satellite.angle) * radius,
+            f32::sin(satellite.angle) * radius,
+            transform.translation.z,
+        );
+    }
+}
+
+pub fn satellite_shooting(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    balance: Res<Balance>,
+    attachments: Res<Attachments>,
+    modifiers: Res<RunModifiers>,
+    mut satellite_query: Query<(&mut Satellite, &Transform)>,
+    enemy_grid: Res<EnemyGrid>,
+    mut shot_events: EventWriter<ShotFired>,
+) {
+    let efficiency = if modifiers.idle { IDLE_EFFICIENCY } else { 1.0 };
+    let weapon = attachments.weapon(WeaponSlot::Satellite, &balance.weapons.satellite);
+    let fire_rate = attachments.fire_rate_multiplier(WeaponSlot::Satellite);
+    for (mut satellite, transform) in &mut satellite_query {
+        satellite
+            .cooldown
+            .tick(time_scale.delta(&time).mul_f32(efficiency * fire_rate));
+        if !satellite.cooldown.finished() {
+            continue;
+        }
+
+        let origin = transform.translation.truncate();
+        if let Some((_, target)) = enemy_grid.nearest(origin, SATELLITE_RANGE) {
+            satellite.cooldown.reset();
+            shot_events.send(ShotFired { by: None });
+            let dir = (target - origin).normalize_or_zero();
+            spawn_bullet(
+                &mut commands,
+                &handles,
+                transform.translation + (dir * 12.0).extend(0.0),
+                dir * 400.0,
+                10.0 * attachments.damage_multiplier(WeaponSlot::Satellite),
+                &weapon,
+                None,
+                Team::Player,
+            );
+        }
+    }
+}
+
+pub fn satellite_collisions(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut satellite_query: Query<&mut Satellite>,
+    mut enemy_query: Query<&mut Enemy>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    for collision_event in collision_events.read() {
+        if let Started(ent, oth, _) = collision_event {
+            for (satellite_ent, enemy_ent) in [(*ent, *oth), (*oth, *ent)] {
+                if let Ok(mut satellite) = satellite_query.get_mut(satellite_ent) {
+                    if let Ok(mut enemy) = enemy_query.get_mut(enemy_ent) {
+                        if enemy.has_hit == 0 && satellite.hp > 0.0 {
+                            satellite.hp -= enemy.damage;
+                            enemy.has_hit = 1;
+                            if satellite.hp <= 0.0 {
+                                commands.entity(satellite_ent).despawn();
+                                feed.send(FeedMessage::new(
+                                    "satellite lost",
+                                    Color::from(ORANGE_RED),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
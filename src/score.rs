@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::conditions::RunOutcome;
+use crate::{EnemyKilled, ShotFired, ShotHit};
+
+/// Counters over the whole run, used for the final score.
+#[derive(Default, Resource)]
+pub struct RunStats {
+    pub kills: u32,
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+    pub scrap: u32,
+    /// Score from overkills and executions.
+    pub bonus: u32,
+}
+
+impl RunStats {
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.shots_hit as f32 / self.shots_fired as f32
+        }
+    }
+}
+
+pub struct ScoreBreakdown {
+    pub kills: u32,
+    pub accuracy: u32,
+    pub hp: u32,
+    pub time: u32,
+    pub bonus: u32,
+    /// Lowered by continuing from checkpoints.
+    pub multiplier: f32,
+}
+
+const SCORE_PER_KILL: u32 = 10;
+const SCORE_PER_ACCURACY: f32 = 1000.0;
+const SCORE_PER_HP: f32 = 10.0;
+const PAR_SECONDS_PER_WAVE: f32 = 60.0;
+const SCORE_PER_SECOND_UNDER_PAR: f32 = 2.0;
+
+impl ScoreBreakdown {
+    pub fn compute(
+        stats: &RunStats,
+        outcome: &RunOutcome,
+        planet_hp: f32,
+        multiplier: f32,
+    ) -> ScoreBreakdown {
+        let par = (outcome.wave + 1) as f32 * PAR_SECONDS_PER_WAVE;
+        ScoreBreakdown {
+            kills: stats.kills * SCORE_PER_KILL,
+            accuracy: (stats.accuracy() * SCORE_PER_ACCURACY) as u32,
+            hp: (planet_hp.max(0.0) * SCORE_PER_HP) as u32,
+            time: ((par - outcome.seconds).max(0.0) * SCORE_PER_SECOND_UNDER_PAR) as u32,
+            bonus: stats.bonus,
+            multiplier,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        ((self.kills + self.accuracy + self.hp + self.time + self.bonus) as f32 * self.multiplier)
+            as u32
+    }
+}
+
+pub fn track_run_stats(
+    mut stats: ResMut<RunStats>,
+    mut killed_events: EventReader<EnemyKilled>,
+    mut fired_events: EventReader<ShotFired>,
+    mut hit_events: EventReader<ShotHit>,
+) {
+    for event in killed_events.read() {
+        stats.kills += 1;
+        stats.scrap += event.scrap;
+    }
+    stats.shots_fired += fired_events.read().count() as u32;
+    stats.shots_hit += hit_events.read().count() as u32;
+}
@@ -0,0 +1,118 @@
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::controls::ControlScheme;
+use crate::storage;
+use crate::textures::TextureQuality;
+use crate::viewport::FitStrategy;
+
+/// Choices of the enemy count multiplier, the spawner divides by it.
+pub const ASSIST_ENEMY_COUNT: [f32; 3] = [1.0, 0.75, 0.5];
+
+/// Accessibility: multipliers easing the runs, which are then marked as
+/// assisted.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AssistOptions {
+    /// On the enemies spawned by the waves.
+    pub enemy_count: f32,
+    pub enemy_speed: f32,
+    /// On the damage the planet takes.
+    pub planet_damage: f32,
+    /// Assisted runs still go on the leaderboard, marked as such.
+    pub leaderboard: bool,
+}
+
+impl Default for AssistOptions {
+    fn default() -> Self {
+        AssistOptions {
+            enemy_count: 1.0,
+            enemy_speed: 1.0,
+            planet_damage: 1.0,
+            leaderboard: true,
+        }
+    }
+}
+
+impl AssistOptions {
+    pub fn active(&self) -> bool {
+        self.enemy_count < 1.0 || self.enemy_speed < 1.0 || self.planet_damage < 1.0
+    }
+}
+
+/// Player preferences, independent from the gameplay balance.
+#[derive(Serialize, Deserialize, Clone, Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Multiplier on every rumble pulse, 0.0 disables rumble.
+    pub rumble_intensity: f32,
+    pub show_run_timer: bool,
+    /// Accessibility: how far shots bend toward an enemy inside the
+    /// assist cone, from 0.0 (off) to 1.0 (straight at it).
+    pub aim_assist: f32,
+    /// Marks where shots hit, and adds the accuracy to the wave recap.
+    pub hit_feedback: bool,
+    /// Dotted path of the next shot, up to where it fades out.
+    pub trajectory_preview: bool,
+    /// Accessibility: the repair minigame of the intermission succeeds on
+    /// its own.
+    pub auto_repair: bool,
+    /// How the arena is framed in windows that aren't square.
+    pub fit: FitStrategy,
+    /// Inset of the hud from the window edges, as a fraction of its short
+    /// side, for notches and rounded corners.
+    pub safe_margin: f32,
+    /// Resolution of the images, see the textures module.
+    pub texture_quality: TextureQuality,
+    /// Accessibility: important audio cues are also written on screen.
+    pub captions: bool,
+    /// Accessibility: one handed and mouse only layouts for the first
+    /// player.
+    pub control_scheme: ControlScheme,
+    pub assist: AssistOptions,
+    /// Faint rings around the planet where its damage came from, always
+    /// shown in full after the run.
+    pub damage_heatmap: bool,
+    /// The keys of the first player on screen, in replays and practice
+    /// runs.
+    pub input_display: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            rumble_intensity: 1.0,
+            show_run_timer: false,
+            aim_assist: 0.0,
+            hit_feedback: false,
+            trajectory_preview: true,
+            auto_repair: false,
+            fit: FitStrategy::default(),
+            safe_margin: 0.0,
+            texture_quality: TextureQuality::default(),
+            captions: false,
+            control_scheme: ControlScheme::default(),
+            assist: AssistOptions::default(),
+            damage_heatmap: false,
+            input_display: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        let mut settings: Settings = storage::load("settings").unwrap_or_default();
+        // the file can be edited by hand, and a zero or a nan would break
+        // the division in the spawner
+        let enemy_count = settings.assist.enemy_count;
+        let enemy_count = if enemy_count.is_finite() {
+            enemy_count
+        } else {
+            AssistOptions::default().enemy_count
+        };
+        let min = ASSIST_ENEMY_COUNT[ASSIST_ENEMY_COUNT.len() - 1];
+        settings.assist.enemy_count = enemy_count.clamp(min, 1.0);
+        settings
+    }
+}
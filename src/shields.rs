@@ -0,0 +1,105 @@
+use bevy::color::palettes::css::{DODGER_BLUE, LIME, RED};
+use bevy::prelude::*;
+
+use crate::{Enemy, TimeScale};
+
+/// Seconds without damage before a shield starts to recharge.
+const SHIELD_REGEN_DELAY: f32 = 3.0;
+/// Fraction of the full shield recharged per second.
+const SHIELD_REGEN_RATE: f32 = 0.25;
+/// Energy damage is this many times as effective against shields.
+const ENERGY_SHIELD_MULTIPLIER: f32 = 2.0;
+
+const BAR_WIDTH: f32 = 32.0;
+/// Above the enemy center, clear of the sprite.
+const BAR_OFFSET: f32 = 28.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageKind {
+    Kinetic,
+    Energy,
+}
+
+/// Second health layer that takes the damage before the hp does.
+#[derive(Clone, Debug)]
+pub struct Shield {
+    pub hp: f32,
+    pub max: f32,
+    /// Seconds since the last hit.
+    pub idle: f32,
+}
+
+impl Shield {
+    pub fn new(max: f32) -> Self {
+        Shield {
+            hp: max,
+            max,
+            idle: 0.0,
+        }
+    }
+
+    /// Absorbs what it can of the damage and returns the rest.
+    pub fn absorb(&mut self, damage: f32, kind: DamageKind) -> f32 {
+        self.idle = 0.0;
+        let multiplier = match kind {
+            DamageKind::Kinetic => 1.0,
+            DamageKind::Energy => ENERGY_SHIELD_MULTIPLIER,
+        };
+        let absorbed = (damage * multiplier).min(self.hp);
+        self.hp -= absorbed;
+        damage - absorbed / multiplier
+    }
+}
+
+pub fn regen_shields(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut enemy_query: Query<&mut Enemy>,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    for mut enemy in &mut enemy_query {
+        // skip the change detection for the full shields
+        let full = match &enemy.shield {
+            Some(shield) => shield.hp >= shield.max,
+            None => true,
+        };
+        if full {
+            continue;
+        }
+        if let Some(shield) = &mut enemy.shield {
+            shield.idle += dt;
+            if shield.idle >= SHIELD_REGEN_DELAY {
+                shield.hp = (shield.hp + shield.max * SHIELD_REGEN_RATE * dt).min(shield.max);
+            }
+        }
+    }
+}
+
+/// Health bars of the damaged or shielded enemies, drawn as gizmos so
+/// that they don't split the sprite batch of the enemies.
+pub fn draw_enemy_bars(mut gizmos: Gizmos, enemy_query: Query<(&Enemy, &Transform, &Visibility)>) {
+    for (enemy, transform, visibility) in &enemy_query {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let shield = enemy
+            .shield
+            .as_ref()
+            .map(|shield| shield.hp / shield.max)
+            .unwrap_or(0.0);
+        let hp = (enemy.hp / enemy.max_hp).clamp(0.0, 1.0);
+        if hp >= 1.0 && shield <= 0.0 {
+            continue;
+        }
+        let start = transform.translation.truncate() + Vec2::new(-BAR_WIDTH * 0.5, BAR_OFFSET);
+        gizmos.line_2d(start, start + Vec2::X * BAR_WIDTH, Color::from(RED));
+        gizmos.line_2d(start, start + Vec2::X * BAR_WIDTH * hp, Color::from(LIME));
+        if shield > 0.0 {
+            gizmos.line_2d(
+                start,
+                start + Vec2::X * BAR_WIDTH * shield,
+                Color::from(DODGER_BLUE),
+            );
+        }
+    }
+}
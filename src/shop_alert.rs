@@ -0,0 +1,145 @@
+use bevy::color::palettes::css::{ORANGE_RED, WHITE};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::attachments::AttachmentScreen;
+use crate::construction::BuildMenu;
+use crate::{AssetHandles, FontName, PlanetDamaged, Spawner};
+
+/// Seconds the alert stays up after the last hit.
+const ALERT_SECONDS: f32 = 5.0;
+
+/// Planet damage taken while a shop screen covers the field. Lingering
+/// zones and the biome hazards keep going in the intermission.
+#[derive(Default, Resource)]
+pub struct ShopAlert {
+    pub damage: f32,
+    /// Elapsed seconds the alert goes away at.
+    pub until: f32,
+}
+
+impl ShopAlert {
+    fn visible(&self) -> bool {
+        self.damage > 0.0
+    }
+}
+
+#[derive(Component)]
+pub struct ShopAlertRoot;
+
+#[derive(Component)]
+pub struct ReturnToBattle;
+
+pub fn setup_shop_alert(mut commands: Commands) {
+    commands.insert_resource(ShopAlert::default());
+}
+
+/// Sums the planet damage while the players are in a shop screen of the
+/// intermission.
+pub fn watch_planet_while_shopping(
+    time: Res<Time>,
+    screen: Res<AttachmentScreen>,
+    build_menu: Res<BuildMenu>,
+    spawner_query: Query<&Spawner>,
+    mut alert: ResMut<ShopAlert>,
+    mut damage_events: EventReader<PlanetDamaged>,
+) {
+    let intermission = spawner_query
+        .iter()
+        .any(|spawner| spawner.in_intermission());
+    let shopping = intermission && (screen.open || build_menu.open);
+    let damage: f32 = damage_events.read().map(|event| event.damage).sum();
+    let now = time.elapsed_seconds();
+    if shopping && damage > 0.0 {
+        alert.damage += damage;
+        alert.until = now + ALERT_SECONDS;
+    } else if alert.visible() && (!shopping || now > alert.until) {
+        *alert = ShopAlert::default();
+    }
+}
+
+/// Closes the shop screens, back to the field.
+pub fn return_to_battle(
+    mut screen: ResMut<AttachmentScreen>,
+    mut build_menu: ResMut<BuildMenu>,
+    mut alert: ResMut<ShopAlert>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<ReturnToBattle>)>,
+) {
+    if button_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        screen.open = false;
+        build_menu.open = false;
+        *alert = ShopAlert::default();
+    }
+}
+
+pub fn show_shop_alert(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    alert: Res<ShopAlert>,
+    root_query: Query<Entity, With<ShopAlertRoot>>,
+) {
+    if !alert.is_changed() {
+        return;
+    }
+    for entity in &root_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !alert.visible() {
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(15.0),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-180.0)),
+                width: Val::Px(360.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.3, 0.0, 0.0, 0.9).into(),
+            focus_policy: FocusPolicy::Block,
+            ..default()
+        })
+        .insert(ShopAlertRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("the planet is under fire, -{:.0} hp", alert.damage),
+                style(24.0, Color::from(ORANGE_RED)),
+            ));
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                    ..default()
+                })
+                .insert(ReturnToBattle)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "return to battle",
+                        style(22.0, Color::from(WHITE)),
+                    ));
+                });
+        });
+}
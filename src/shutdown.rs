@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::history::RunHistory;
+use crate::leaderboard::Leaderboard;
+use crate::profile::Profile;
+use crate::settings::Settings;
+use crate::storage;
+
+/// Latest serialized copy of everything that is persisted, written out
+/// when the game closes. Shared with the page unload handler on the web,
+/// which runs outside of the schedule.
+#[derive(Resource, Default, Clone)]
+pub struct PendingSaves(Arc<Mutex<HashMap<&'static str, String>>>);
+
+impl PendingSaves {
+    fn stage<T: Serialize>(&self, key: &'static str, value: &T) {
+        if let (Ok(json), Ok(mut pending)) = (serde_json::to_string_pretty(value), self.0.lock()) {
+            pending.insert(key, json);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut pending) = self.0.lock() {
+            for (key, json) in pending.drain() {
+                if !storage::save_string(key, &json) {
+                    warn!("failed to save the {}", key);
+                }
+            }
+        }
+    }
+}
+
+pub fn stage_saves(
+    saves: Res<PendingSaves>,
+    settings: Res<Settings>,
+    profile: Res<Profile>,
+    leaderboard: Res<Leaderboard>,
+    history: Res<RunHistory>,
+) {
+    if settings.is_changed() {
+        saves.stage("settings", &*settings);
+    }
+    if profile.is_changed() {
+        saves.stage("profile", &*profile);
+    }
+    if leaderboard.is_changed() {
+        saves.stage("leaderboard", &*leaderboard);
+    }
+    if history.is_changed() {
+        saves.stage("history", &*history);
+    }
+}
+
+pub fn flush_on_exit(saves: Res<PendingSaves>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().next().is_some() {
+        saves.flush();
+    }
+}
+
+/// Closing the tab never sends an AppExit, so the page events flush the
+/// saves directly.
+#[cfg(target_arch = "wasm32")]
+pub fn flush_on_unload(saves: Res<PendingSaves>) {
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return,
+    };
+    let saves = saves.clone();
+    let unload = Closure::<dyn FnMut()>::new(move || saves.flush());
+    for event in ["beforeunload", "pagehide"] {
+        if window
+            .add_event_listener_with_callback(event, unload.as_ref().unchecked_ref())
+            .is_err()
+        {
+            warn!("failed to listen to {}", event);
+        }
+    }
+    // the listener lives as long as the page
+    unload.forget();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn flush_on_unload() {}
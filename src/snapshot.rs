@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use serde::*;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::conditions::RunClock;
+use crate::enemies::EnemyDefinitions;
+use crate::upgrades::{RunUpgrades, UpgradeDefinitions};
+use crate::{spawn_enemy, AssetHandles, Bullet, Enemy, Planet, Player, Spawner, Wallet};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EnemySnapshot {
+    pub enemy_id: u32,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub hp: f32,
+}
+
+/// Compact state of a run in progress, what it takes to put it back the
+/// way it was. Structures, satellites and bullets in flight aren't kept.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RunSnapshot {
+    pub seconds: f32,
+    pub planet_hp: f32,
+    /// Zero in the snapshots taken before the upgrades could raise it.
+    #[serde(default)]
+    pub planet_max_hp: f32,
+    pub wave: usize,
+    pub spawn: usize,
+    pub queue: Vec<(u32, Option<f32>)>,
+    /// Seconds left before the next spawn.
+    pub spawn_in: f32,
+    /// Seconds left of the intermission, if the wave is over.
+    pub intermission: Option<f32>,
+    pub scrap: u32,
+    pub purses: Vec<u32>,
+    /// Position of each player, by party slot.
+    pub players: Vec<Vec2>,
+    pub enemies: Vec<EnemySnapshot>,
+    /// Ids of the upgrades taken.
+    #[serde(default)]
+    pub upgrades: Vec<String>,
+}
+
+/// The parts of the world a snapshot is taken from and restored to.
+#[derive(SystemParam)]
+pub struct RunState<'w, 's> {
+    handles: Res<'w, AssetHandles>,
+    definitions: Res<'w, EnemyDefinitions>,
+    clock: ResMut<'w, RunClock>,
+    wallet: ResMut<'w, Wallet>,
+    upgrades: ResMut<'w, RunUpgrades>,
+    upgrade_definitions: Res<'w, UpgradeDefinitions>,
+    planet_query: Query<'w, 's, &'static mut Planet>,
+    spawner_query: Query<'w, 's, &'static mut Spawner>,
+    player_query: Query<'w, 's, (&'static Player, &'static mut Transform), Without<Enemy>>,
+    enemy_query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Enemy,
+            &'static Transform,
+            &'static Velocity,
+        ),
+    >,
+    bullet_query: Query<'w, 's, Entity, With<Bullet>>,
+}
+
+impl RunState<'_, '_> {
+    pub fn planet_hp(&self) -> Option<f32> {
+        self.planet_query.get_single().ok().map(|planet| planet.hp)
+    }
+
+    pub fn capture(&self) -> Option<RunSnapshot> {
+        let planet = self.planet_query.get_single().ok()?;
+        let spawner = self.spawner_query.get_single().ok()?;
+        let mut players: Vec<_> = self
+            .player_query
+            .iter()
+            .map(|(player, transform)| (player.index, transform.translation.truncate()))
+            .collect();
+        players.sort_by_key(|(index, _)| *index);
+        Some(RunSnapshot {
+            seconds: self.clock.seconds,
+            planet_hp: planet.hp,
+            planet_max_hp: planet.max_hp,
+            wave: spawner.current_wave,
+            spawn: spawner.current_spawn,
+            queue: spawner.queue.iter().copied().collect(),
+            spawn_in: spawner.spawntimer.remaining_secs(),
+            intermission: spawner
+                .intermission
+                .as_ref()
+                .map(|timer| timer.remaining_secs()),
+            scrap: self.wallet.scrap,
+            purses: self.wallet.purses.clone(),
+            players: players.into_iter().map(|(_, position)| position).collect(),
+            enemies: self
+                .enemy_query
+                .iter()
+                .filter(|(_, enemy, _, _)| enemy.hp > 0.0 && enemy.has_hit == 0)
+                .map(|(_, enemy, transform, velocity)| EnemySnapshot {
+                    enemy_id: enemy.enemy_id,
+                    position: transform.translation.truncate(),
+                    velocity: velocity.linvel,
+                    hp: enemy.hp,
+                })
+                .collect(),
+            upgrades: self.upgrades.taken.clone(),
+        })
+    }
+
+    /// Puts the run back, the enemies and the bullets on the field are
+    /// replaced by the ones of the snapshot.
+    pub fn restore(&mut self, commands: &mut Commands, snapshot: &RunSnapshot, hp_multiplier: f32) {
+        self.clock.seconds = snapshot.seconds;
+        self.wallet.scrap = snapshot.scrap;
+        self.wallet.purses = snapshot.purses.clone();
+        *self.upgrades = RunUpgrades::from_taken(&snapshot.upgrades, &self.upgrade_definitions);
+        if let Ok(mut planet) = self.planet_query.get_single_mut() {
+            planet.hp = snapshot.planet_hp;
+            if snapshot.planet_max_hp > 0.0 {
+                planet.max_hp = snapshot.planet_max_hp;
+            }
+        }
+        if let Ok(mut spawner) = self.spawner_query.get_single_mut() {
+            spawner.current_wave = snapshot.wave;
+            spawner.current_spawn = snapshot.spawn;
+            spawner.queue = snapshot.queue.iter().copied().collect();
+            let spawn_in = Duration::from_secs_f32(snapshot.spawn_in.max(0.0));
+            spawner.spawntimer = Timer::new(spawn_in, TimerMode::Once);
+            spawner.intermission = snapshot.intermission.map(|seconds| {
+                Timer::new(Duration::from_secs_f32(seconds.max(0.0)), TimerMode::Once)
+            });
+        }
+        for (player, mut transform) in &mut self.player_query {
+            if let Some(position) = snapshot.players.get(player.index) {
+                let angle = position.y.atan2(position.x);
+                transform.translation = position.extend(transform.translation.z);
+                transform.rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
+            }
+        }
+
+        for (entity, ..) in &self.enemy_query {
+            commands.entity(entity).despawn();
+        }
+        for entity in &self.bullet_query {
+            commands.entity(entity).despawn();
+        }
+        for enemy in &snapshot.enemies {
+            let hp = enemy.hp;
+            let entity = spawn_enemy(
+                commands,
+                &self.handles,
+                &self.definitions,
+                enemy.enemy_id,
+                enemy.position.extend(3.0),
+                enemy.velocity,
+                hp_multiplier,
+            );
+            commands
+                .entity(entity)
+                .add(move |mut entity: EntityWorldMut| {
+                    if let Some(mut enemy) = entity.get_mut::<Enemy>() {
+                        enemy.hp = hp;
+                    }
+                });
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+
+use serde::*;
+
+use bevy::prelude::*;
+
+use crate::story::DialogLine;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnAt {
+    pub enemy_id: u32,
+    /// Milliseconds before the next spawn of the wave.
+    pub cooldown: f32,
+    /// Angle on the spawn ring in radians, a random free spot if unset.
+    #[serde(default)]
+    pub angle: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Wave {
+    pub spawns: Vec<SpawnAt>,
+    #[serde(default)]
+    pub reinforcements: Option<Reinforcements>,
+}
+
+/// Takes over a wave once enough of it is killed: the enemies left
+/// retreat, the spawns left are dropped and these come instead.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Reinforcements {
+    /// Share of the spawns of the wave that has to be killed.
+    pub retreat_at: f32,
+    pub spawns: Vec<SpawnAt>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnWeight {
+    pub enemy_id: u32,
+    pub weight: f32,
+}
+
+/// Enemies of the generated waves from `from_wave` on, until the next band.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnBand {
+    pub from_wave: usize,
+    pub weights: Vec<SpawnWeight>,
+}
+
+/// Picks the enemy of a generated spawn from the band the wave falls in,
+/// the basic enemy if no band covers it.
+pub fn pick_enemy(bands: &[SpawnBand], wave: usize, rng: &mut impl Rng) -> u32 {
+    let band = bands
+        .iter()
+        .filter(|band| band.from_wave <= wave)
+        .max_by_key(|band| band.from_wave);
+    let weights = match band {
+        Some(band) => &band.weights,
+        None => return 0,
+    };
+    match WeightedIndex::new(weights.iter().map(|weight| weight.weight)) {
+        Ok(index) => weights[index.sample(rng)].enemy_id,
+        Err(_) => 0,
+    }
+}
+
+impl Wave {
+    pub fn from_progress(progress: i32, bands: &[SpawnBand], rng: &mut impl Rng) -> Wave {
+        let mut wave = Wave {
+            spawns: vec![],
+            reinforcements: None,
+        };
+        let num = progress * 3;
+        for _ in 0..num {
+            wave.spawns.push(SpawnAt {
+                enemy_id: pick_enemy(bands, progress as usize, rng),
+                cooldown: rng.gen_range(200.0..2000.0),
+                angle: None,
+            })
+        }
+        wave
+    }
+}
+
+/// What the director does when the wave of a scripted event starts.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ScriptAction {
+    /// A line in the feed.
+    Message(String),
+    /// Enemies queued on top of the wave, like a boss and its escort.
+    Spawn { enemy_id: u32, count: u32 },
+    /// Scrap delivered to the shared wallet.
+    Scrap(u32),
+    /// A free satellite, if the orbit has room for it.
+    Satellite,
+    /// Lines shown before the wave goes on.
+    Dialog(Vec<DialogLine>),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScriptedEvent {
+    /// Index of the wave, counted from 0 like the bands.
+    pub wave: usize,
+    pub actions: Vec<ScriptAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Resource)]
+pub struct Challenge {
+    pub waves: Vec<Wave>,
+    #[serde(default)]
+    pub events: Vec<ScriptedEvent>,
+}
+
+/// Seed of the generated waves of the current run.
+#[derive(Resource, Clone, Copy)]
+pub struct RunSeed(pub u64);
+
+impl Challenge {
+    pub fn new(seed: u64, bands: &[SpawnBand]) -> Challenge {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut challenge = Challenge::default();
+        for i in 0..100 {
+            challenge
+                .waves
+                .push(Wave::from_progress(i, bands, &mut rng));
+        }
+        challenge
+    }
+
+    /// Parses a challenge file, expanding its authoring blocks.
+    pub fn from_json(json: &str) -> serde_json::Result<Challenge> {
+        let file: ChallengeFile = serde_json::from_str(json)?;
+        Ok(file.expand())
+    }
+}
+
+fn no_ramp() -> f32 {
+    1.0
+}
+
+/// Entry of a wave in a challenge file: a plain spawn, or a block of
+/// entries that expands into several spawns when the file is loaded.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SpawnBlock {
+    /// The block spawns `repeat` times. Each copy multiplies its cooldowns
+    /// by `ramp` once more than the previous one, and `stagger` adds
+    /// milliseconds after the last spawn of every copy.
+    Repeat {
+        repeat: u32,
+        #[serde(default)]
+        stagger: f32,
+        #[serde(default = "no_ramp")]
+        ramp: f32,
+        spawns: Vec<SpawnBlock>,
+    },
+    /// Every spawn of the block comes with a twin on the opposite side of
+    /// the ring, at the same time. Twins of spawns without an angle land
+    /// on another random spot.
+    Mirror {
+        mirror: Vec<SpawnBlock>,
+    },
+    Spawn(SpawnAt),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReinforcementsFile {
+    pub retreat_at: f32,
+    pub spawns: Vec<SpawnBlock>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WaveFile {
+    pub spawns: Vec<SpawnBlock>,
+    #[serde(default)]
+    pub reinforcements: Option<ReinforcementsFile>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChallengeFile {
+    pub waves: Vec<WaveFile>,
+    #[serde(default)]
+    pub events: Vec<ScriptedEvent>,
+}
+
+impl ChallengeFile {
+    pub fn expand(&self) -> Challenge {
+        Challenge {
+            waves: self
+                .waves
+                .iter()
+                .map(|wave| {
+                    let mut spawns = vec![];
+                    expand_blocks(&wave.spawns, &mut spawns);
+                    let reinforcements = wave.reinforcements.as_ref().map(|file| {
+                        let mut spawns = vec![];
+                        expand_blocks(&file.spawns, &mut spawns);
+                        Reinforcements {
+                            retreat_at: file.retreat_at,
+                            spawns,
+                        }
+                    });
+                    Wave {
+                        spawns,
+                        reinforcements,
+                    }
+                })
+                .collect(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+fn expand_blocks(blocks: &[SpawnBlock], out: &mut Vec<SpawnAt>) {
+    for block in blocks {
+        match block {
+            SpawnBlock::Spawn(spawn) => out.push(spawn.clone()),
+            SpawnBlock::Repeat {
+                repeat,
+                stagger,
+                ramp,
+                spawns,
+            } => {
+                for copy in 0..*repeat {
+                    let start = out.len();
+                    expand_blocks(spawns, out);
+                    let scale = ramp.powi(copy as i32);
+                    for spawn in &mut out[start..] {
+                        spawn.cooldown *= scale;
+                    }
+                    if let Some(last) = out[start..].last_mut() {
+                        last.cooldown += stagger;
+                    }
+                }
+            }
+            SpawnBlock::Mirror { mirror } => {
+                let mut spawns = vec![];
+                expand_blocks(mirror, &mut spawns);
+                for spawn in spawns {
+                    // the twin keeps the cooldown, so the pair spawns together
+                    out.push(SpawnAt {
+                        cooldown: 0.0,
+                        ..spawn.clone()
+                    });
+                    out.push(SpawnAt {
+                        angle: spawn.angle.map(|angle| angle + std::f32::consts::PI),
+                        ..spawn
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// What a spawner tick asks the game to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpawnerStep {
+    /// An enemy, and the ring angle it was authored at.
+    Spawn(u32, Option<f32>),
+    /// The wave is over and the intermission started.
+    WaveEnded(usize),
+}
+
+#[derive(Component)]
+pub struct Spawner {
+    pub spawntimer: Timer,
+    pub size: f32,
+    pub current_wave: usize,
+    /// Index of the next spawn of the current wave.
+    pub current_spawn: usize,
+    pub intermission: Option<Timer>,
+    /// Enemies due to spawn, waiting for a free spot on the ring.
+    pub queue: VecDeque<(u32, Option<f32>)>,
+    /// The current wave retreated and spawns its reinforcements.
+    pub reinforced: bool,
+}
+
+impl Spawner {
+    /// The first enemy comes after `delay`.
+    pub fn new(size: f32, first_wave: usize, delay: Duration) -> Spawner {
+        Spawner {
+            spawntimer: Timer::new(delay, TimerMode::Once),
+            size,
+            current_wave: first_wave,
+            current_spawn: 0,
+            intermission: None,
+            queue: VecDeque::new(),
+            reinforced: false,
+        }
+    }
+
+    pub fn in_intermission(&self) -> bool {
+        self.intermission.is_some()
+    }
+
+    /// Sounds the retreat of the current wave once `kills` reach its
+    /// trigger, true the one time it happens. The spawns left and the
+    /// queued ones are dropped, the reinforcements start right away.
+    pub fn retreat(&mut self, challenge: &Challenge, kills: u32) -> bool {
+        if self.reinforced || self.in_intermission() {
+            return false;
+        }
+        let wave = match challenge.waves.get(self.current_wave) {
+            Some(wave) => wave,
+            None => return false,
+        };
+        let reinforcements = match &wave.reinforcements {
+            Some(reinforcements) => reinforcements,
+            None => return false,
+        };
+        let trigger = (wave.spawns.len() as f32 * reinforcements.retreat_at).ceil() as u32;
+        if kills < trigger.max(1) {
+            return false;
+        }
+        self.reinforced = true;
+        self.current_spawn = 0;
+        self.queue.clear();
+        self.spawntimer.set_duration(Duration::ZERO);
+        self.spawntimer.reset();
+        true
+    }
+
+    /// The spawns the current wave is going through.
+    fn spawns<'a>(&self, wave: &'a Wave) -> &'a [SpawnAt] {
+        match &wave.reinforcements {
+            Some(reinforcements) if self.reinforced => &reinforcements.spawns,
+            _ => &wave.spawns,
+        }
+    }
+
+    /// All the waves of the challenge are over.
+    pub fn finished(&self, challenge: &Challenge) -> bool {
+        self.current_wave >= challenge.waves.len()
+    }
+
+    /// Advances the wave state machine by `dt`, at most one step per tick.
+    /// A wave only ends once the `field_clear` of its last enemies, and
+    /// `hold` keeps the intermission from running out.
+    pub fn tick(
+        &mut self,
+        challenge: &Challenge,
+        dt: Duration,
+        field_clear: bool,
+        hold: bool,
+        intermission: Duration,
+    ) -> Option<SpawnerStep> {
+        let mut ready = false;
+        if let Some(timer) = &mut self.intermission {
+            if hold {
+                return None;
+            }
+            timer.tick(dt);
+            if !timer.finished() {
+                return None;
+            }
+            self.intermission = None;
+            self.current_wave += 1;
+            self.current_spawn = 0;
+            self.reinforced = false;
+            // the first enemy of a wave comes right away
+            ready = true;
+        }
+
+        let wave = challenge.waves.get(self.current_wave)?;
+        if !ready {
+            self.spawntimer.tick(dt);
+            if !self.spawntimer.finished() {
+                return None;
+            }
+        }
+
+        match self.spawns(wave).get(self.current_spawn) {
+            Some(spawn) => {
+                self.current_spawn += 1;
+                self.spawntimer
+                    .set_duration(Duration::from_millis(spawn.cooldown.max(0.0) as u64));
+                self.spawntimer.reset();
+                Some(SpawnerStep::Spawn(spawn.enemy_id, spawn.angle))
+            }
+            None if field_clear => {
+                self.intermission = Some(Timer::new(intermission, TimerMode::Once));
+                Some(SpawnerStep::WaveEnded(self.current_wave))
+            }
+            None => None,
+        }
+    }
+}
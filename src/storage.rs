@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// json blobs by key: files in the saves directory on native,
+// localStorage entries on the web
+
+/// Cleared for the headless runs, which neither read nor overwrite the
+/// player's saves.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVES_DIR: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_string(key: &str) -> Option<String> {
+    std::fs::read_to_string(format!("{}/{}.json", SAVES_DIR, key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_string(key: &str, value: &str) -> bool {
+    std::fs::create_dir_all(SAVES_DIR).is_ok()
+        && std::fs::write(format!("{}/{}.json", SAVES_DIR, key), value).is_ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_string(key: &str) {
+    let _ = std::fs::remove_file(format!("{}/{}.json", SAVES_DIR, key));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_string(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("planet-{}", key)).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_string(key: &str, value: &str) -> bool {
+    local_storage()
+        .map(|storage| storage.set_item(&format!("planet-{}", key), value).is_ok())
+        .unwrap_or(false)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_string(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(&format!("planet-{}", key));
+    }
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    if !enabled() {
+        return None;
+    }
+    serde_json::from_str(&load_string(key)?).ok()
+}
+
+/// Pretends to succeed while disabled.
+pub fn save_string(key: &str, value: &str) -> bool {
+    !enabled() || write_string(key, value)
+}
+
+pub fn remove(key: &str) {
+    if enabled() {
+        remove_string(key);
+    }
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) -> bool {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => save_string(key, &json),
+        Err(_) => false,
+    }
+}
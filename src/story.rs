@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use serde::*;
+
+use bevy::color::palettes::css::{GRAY, YELLOW};
+use bevy::prelude::*;
+
+use crate::conditions::RunModifiers;
+use crate::storage;
+use crate::{AssetHandles, FontName, ImageName, Spawner, WaveEnded, SPRITE_ENEMY};
+
+// embedded for the same reason as the level files, a "story" save
+// replaces it for translations and mods
+const STORY_FILE: &str = include_str!("../assets/story.json");
+
+/// Waves in a chapter, a vignette plays once the last one is cleared.
+const CHAPTER_WAVES: usize = 25;
+const PORTRAIT_SIZE: f32 = 64.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Portrait {
+    Player,
+    Planet,
+    Enemy,
+}
+
+fn default_line_seconds() -> f32 {
+    5.0
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DialogLine {
+    pub speaker: String,
+    pub portrait: Portrait,
+    pub text: String,
+    /// Seconds the line stays up unless skipped.
+    #[serde(default = "default_line_seconds")]
+    pub seconds: f32,
+}
+
+/// Played before the first wave, and after the last wave of each chapter.
+#[derive(Serialize, Deserialize, Clone, Default, Resource)]
+pub struct Story {
+    pub intro: Vec<DialogLine>,
+    pub chapters: Vec<Vec<DialogLine>>,
+}
+
+impl Story {
+    pub fn load() -> Story {
+        storage::load("story")
+            .unwrap_or_else(|| serde_json::from_str(STORY_FILE).expect("invalid story file"))
+    }
+}
+
+/// Lines waiting to be shown, the spawner waits for them to be over.
+#[derive(Default, Resource)]
+pub struct Cutscene {
+    pub lines: VecDeque<DialogLine>,
+    /// Time on the front line, once it is up.
+    pub timer: Option<Timer>,
+}
+
+impl Cutscene {
+    pub fn play(&mut self, lines: &[DialogLine]) {
+        self.lines.extend(lines.iter().cloned());
+    }
+
+    pub fn active(&self) -> bool {
+        !self.lines.is_empty()
+    }
+}
+
+#[derive(Component)]
+pub struct CutscenePanel;
+
+pub fn setup_story(
+    mut commands: Commands,
+    story: Res<Story>,
+    modifiers: Res<RunModifiers>,
+    spawner_query: Query<&Spawner>,
+) {
+    let mut cutscene = Cutscene::default();
+    let first_wave = spawner_query
+        .iter()
+        .all(|spawner| spawner.current_wave == 0);
+    if first_wave && !modifiers.sandbox && !modifiers.speedrun {
+        cutscene.play(&story.intro);
+    }
+    commands.insert_resource(cutscene);
+}
+
+pub fn queue_vignettes(
+    story: Res<Story>,
+    modifiers: Res<RunModifiers>,
+    mut cutscene: ResMut<Cutscene>,
+    mut wave_events: EventReader<WaveEnded>,
+) {
+    for event in wave_events.read() {
+        let cleared = event.wave + 1;
+        if modifiers.speedrun || cleared % CHAPTER_WAVES != 0 {
+            continue;
+        }
+        if let Some(lines) = story.chapters.get(cleared / CHAPTER_WAVES - 1) {
+            cutscene.play(lines);
+        }
+    }
+}
+
+/// Shows the lines one at a time on real time. Enter skips to the next
+/// line, escape skips the whole cutscene.
+pub fn update_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    handles: Res<AssetHandles>,
+    mut cutscene: ResMut<Cutscene>,
+    panel_query: Query<Entity, With<CutscenePanel>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        cutscene.lines.clear();
+        cutscene.timer = None;
+    } else if let Some(timer) = &mut cutscene.timer {
+        timer.tick(time.delta());
+        if !timer.finished() && !keyboard_input.just_pressed(KeyCode::Enter) {
+            return;
+        }
+        cutscene.lines.pop_front();
+        cutscene.timer = None;
+    }
+    if cutscene.timer.is_some() {
+        return;
+    }
+
+    for entity in &panel_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    let line = match cutscene.lines.front() {
+        Some(line) => line.clone(),
+        None => return,
+    };
+    cutscene.timer = Some(Timer::from_seconds(line.seconds, TimerMode::Once));
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    let image = match line.portrait {
+        Portrait::Player => ImageName::Player,
+        Portrait::Planet => ImageName::Planet,
+        Portrait::Enemy => ImageName::Sprites,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                left: Val::Percent(20.0),
+                width: Val::Percent(60.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                column_gap: Val::Px(16.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+            ..default()
+        })
+        .insert(CutscenePanel)
+        .with_children(|panel| {
+            let mut portrait = panel.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(PORTRAIT_SIZE),
+                    height: Val::Px(PORTRAIT_SIZE),
+                    flex_shrink: 0.0,
+                    ..default()
+                },
+                image: UiImage::new(handles.images.get(&image).unwrap().clone_weak()),
+                ..default()
+            });
+            if line.portrait == Portrait::Enemy {
+                portrait.insert(TextureAtlas {
+                    layout: handles.sprite_layout.clone_weak(),
+                    index: SPRITE_ENEMY,
+                });
+            }
+            panel.spawn(TextBundle::from_sections([
+                TextSection::new(
+                    format!("{}\n", line.speaker),
+                    style(22.0, Color::from(YELLOW)),
+                ),
+                TextSection::new(format!("{}\n", line.text), style(20.0, Color::WHITE)),
+                TextSection::new("[enter] next  [esc] skip", style(14.0, Color::from(GRAY))),
+            ]));
+        });
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::daynight::DayNight;
+use crate::enemies::{MovementMode, TargetingMode};
+use crate::enemy_grid::EnemyGrid;
+use crate::retreat::Retreating;
+use crate::settings::Settings;
+use crate::{Enemy, TimeScale};
+
+/// Cruise speed of the swarm, in pixels per second.
+const SWARM_SPEED: f32 = 70.0;
+/// How fast they turn toward the flock heading, per second.
+const SWARM_AGILITY: f32 = 4.0;
+/// Swarm enemies closer than this flock together.
+const NEIGHBOR_RADIUS: f32 = 48.0;
+/// Closer than this they push each other apart.
+const SEPARATION_RADIUS: f32 = 14.0;
+/// Neighbors looked at per enemy, keeps dense flocks from getting
+/// quadratic.
+const MAX_NEIGHBORS: usize = 8;
+const SEPARATION_WEIGHT: f32 = 1.5;
+const ALIGNMENT_WEIGHT: f32 = 0.6;
+const COHESION_WEIGHT: f32 = 0.4;
+const SEEK_WEIGHT: f32 = 1.0;
+
+/// Boids for the swarm enemies: they keep apart, match the heading and
+/// close in on the neighbors around them, and all of them seek the
+/// planet. The neighbors come from the enemy grid.
+pub fn flock_swarms(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    daynight: Res<DayNight>,
+    settings: Res<Settings>,
+    grid: Res<EnemyGrid>,
+    mut enemy_query: Query<
+        (Entity, &mut Enemy, &mut Transform, &mut Velocity),
+        Without<Retreating>,
+    >,
+) {
+    let dt = time_scale.delta_seconds(&time);
+    let speed_multiplier = daynight.enemy_speed_multiplier() * settings.assist.enemy_speed;
+
+    // headings are computed from the velocities of the last frame, then
+    // applied all at once
+    let mut headings = vec![];
+    for (entity, enemy, transform, velocity) in &enemy_query {
+        if enemy.targeting != TargetingMode::Swarm {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let mut separation = Vec2::ZERO;
+        let mut alignment = Vec2::ZERO;
+        let mut center = Vec2::ZERO;
+        let mut flock = 0;
+        let neighbors = grid
+            .within(position, NEIGHBOR_RADIUS)
+            .filter(|(other, _)| *other != entity)
+            .take(MAX_NEIGHBORS);
+        for (other, other_pos) in neighbors {
+            let offset = position - other_pos;
+            let distance = offset.length();
+            if distance > 0.0 && distance < SEPARATION_RADIUS {
+                separation += offset / (distance * distance);
+            }
+            let other_velocity = match enemy_query.get(other) {
+                Ok((_, other_enemy, _, other_velocity))
+                    if other_enemy.targeting == TargetingMode::Swarm =>
+                {
+                    other_velocity.linvel
+                }
+                _ => continue,
+            };
+            alignment += other_velocity;
+            center += other_pos;
+            flock += 1;
+        }
+
+        let speed = SWARM_SPEED * enemy.speed.max(0.0) * speed_multiplier;
+        let mut desired = -position.normalize_or_zero() * SEEK_WEIGHT;
+        desired += separation.normalize_or_zero() * SEPARATION_WEIGHT;
+        if flock > 0 {
+            let flock = flock as f32;
+            desired += (alignment / flock / speed.max(1.0)) * ALIGNMENT_WEIGHT;
+            desired += (center / flock - position).normalize_or_zero() * COHESION_WEIGHT;
+        }
+        let desired = desired.normalize_or_zero() * speed;
+        headings.push((entity, desired));
+    }
+
+    for (entity, desired) in headings {
+        let (_, mut enemy, mut transform, mut velocity) = match enemy_query.get_mut(entity) {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        enemy.steering = (desired - velocity.linvel) * (SWARM_AGILITY * dt).min(1.0);
+        velocity.linvel += enemy.steering;
+        if enemy.movement == MovementMode::Kinematic {
+            transform.translation += (velocity.linvel * dt).extend(0.0);
+        }
+        transform.rotation = Quat::from_rotation_z(velocity.linvel.y.atan2(velocity.linvel.x));
+    }
+}
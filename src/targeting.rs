@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::enemies::TargetingMode;
+use crate::Enemy;
+
+/// How much a structure draws the enemies that hunt structures, weighed
+/// against its distance when they pick a target.
+#[derive(Component)]
+pub struct Threat(pub f32);
+
+/// Points hunting enemies at the most threatening structure, and picks a
+/// new one once their target is destroyed.
+pub fn assign_targets(
+    mut enemy_query: Query<(&mut Enemy, &Transform)>,
+    threat_query: Query<(Entity, &Threat, &Transform), Without<Enemy>>,
+) {
+    for (mut enemy, transform) in &mut enemy_query {
+        if enemy.targeting != TargetingMode::Threat {
+            continue;
+        }
+        if let Some(target) = enemy.target {
+            if threat_query.contains(target) {
+                continue;
+            }
+        }
+        let pos = transform.translation.truncate();
+        let target = threat_query
+            .iter()
+            .filter(|(_, threat, _)| threat.0 > 0.0)
+            .map(|(entity, threat, threat_trans)| {
+                let dist = threat_trans.translation.truncate().distance(pos).max(1.0);
+                (entity, threat.0 / dist)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity);
+        // without structures they go for the planet
+        if enemy.target != target {
+            enemy.target = target;
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use bevy_rapier2d::prelude::*;
+
+const PLAYER_BODY: u32 = 0b000100;
+const PLAYER_SHOT: u32 = 0b000010;
+const ENEMY_BODY: u32 = 0b000001;
+const ENEMY_SHOT: u32 = 0b001000;
+const NEUTRAL_BODY: u32 = 0b010000;
+const NEUTRAL_SHOT: u32 = 0b100000;
+const BODIES: u32 = PLAYER_BODY | ENEMY_BODY | NEUTRAL_BODY;
+
+/// Side an entity fights for. The collision groups are derived from it,
+/// and the damage is only applied between teams that hurt each other.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Team {
+    /// The planet, its defences and the allies.
+    Player,
+    Enemy,
+    /// Hazards that take no side, they hurt everyone and nobody hurts them.
+    Neutral,
+}
+
+/// How an entity takes part in the collisions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// Ships, the planet, structures and hazards: they bump into each other
+    /// and get hit.
+    Body,
+    /// Bullets and damage zones: they go through everything but the bodies
+    /// they hurt.
+    Shot,
+    /// Sensors around the ships: they overlap whatever would hurt the
+    /// ship, and nothing reacts to them.
+    Graze,
+}
+
+const TEAMS: [Team; 3] = [Team::Player, Team::Enemy, Team::Neutral];
+
+impl Team {
+    pub fn hurts(self, other: Team) -> bool {
+        matches!(
+            (self, other),
+            (Team::Player, Team::Enemy)
+                | (Team::Enemy, Team::Player)
+                | (Team::Neutral, Team::Player)
+                | (Team::Neutral, Team::Enemy)
+        )
+    }
+
+    fn body(self) -> u32 {
+        match self {
+            Team::Player => PLAYER_BODY,
+            Team::Enemy => ENEMY_BODY,
+            Team::Neutral => NEUTRAL_BODY,
+        }
+    }
+
+    fn shot(self) -> u32 {
+        match self {
+            Team::Player => PLAYER_SHOT,
+            Team::Enemy => ENEMY_SHOT,
+            Team::Neutral => NEUTRAL_SHOT,
+        }
+    }
+
+    /// The only place where the group bits are written out.
+    pub fn collision_groups(self, role: Role) -> CollisionGroups {
+        let (memberships, filters) = match role {
+            Role::Body => (
+                self.body(),
+                TEAMS
+                    .iter()
+                    .filter(|other| other.hurts(self))
+                    .fold(BODIES, |filters, other| filters | other.shot()),
+            ),
+            Role::Shot => (
+                self.shot(),
+                TEAMS
+                    .iter()
+                    .filter(|other| self.hurts(**other))
+                    .fold(0, |filters, other| filters | other.body()),
+            ),
+            Role::Graze => (
+                self.body(),
+                TEAMS
+                    .iter()
+                    .filter(|other| other.hurts(self))
+                    .fold(0, |filters, other| filters | other.body() | other.shot()),
+            ),
+        };
+        CollisionGroups::new(
+            Group::from_bits_truncate(memberships),
+            Group::from_bits_truncate(filters),
+        )
+    }
+}
@@ -0,0 +1,178 @@
+use serde::*;
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::settings::Settings;
+use crate::{AssetHandles, ImageName, SPRITE_CELL};
+
+/// Images shipped at two resolutions, `name.png` and `name@2x.png`.
+const IMAGES: [(ImageName, &str); 3] = [
+    (ImageName::Planet, "simple_planet.png"),
+    (ImageName::Player, "player.png"),
+    (ImageName::Sprites, "sprites.png"),
+];
+
+/// Scale factor from which the auto quality picks the crisp set.
+const HD_SCALE_FACTOR: f32 = 1.5;
+
+/// Resolution of the images, picked in the menu.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum TextureQuality {
+    /// Crisp on hidpi screens, the small set on the web.
+    #[default]
+    Auto,
+    Low,
+    High,
+}
+
+impl TextureQuality {
+    pub const ALL: [TextureQuality; 3] = [
+        TextureQuality::Auto,
+        TextureQuality::Low,
+        TextureQuality::High,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextureQuality::Auto => "auto",
+            TextureQuality::Low => "low",
+            TextureQuality::High => "high",
+        }
+    }
+
+    pub fn variant(&self, scale_factor: f32) -> TextureVariant {
+        match self {
+            TextureQuality::Auto if cfg!(target_arch = "wasm32") => TextureVariant::Sd,
+            TextureQuality::Auto if scale_factor >= HD_SCALE_FACTOR => TextureVariant::Hd,
+            TextureQuality::Auto | TextureQuality::Low => TextureVariant::Sd,
+            TextureQuality::High => TextureVariant::Hd,
+        }
+    }
+}
+
+/// Set of images loaded, sprites keep their size in the world either way.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum TextureVariant {
+    /// The @1x files.
+    #[default]
+    Sd,
+    /// The @2x files, twice the pixels on each side.
+    Hd,
+}
+
+impl TextureVariant {
+    pub fn pixel_ratio(&self) -> u32 {
+        match self {
+            TextureVariant::Sd => 1,
+            TextureVariant::Hd => 2,
+        }
+    }
+
+    /// Path of the file of this variant, `path` being the @1x one.
+    pub fn path(&self, path: &str) -> String {
+        match (self, path.rsplit_once('.')) {
+            (TextureVariant::Hd, Some((stem, extension))) => format!("{stem}@2x.{extension}"),
+            _ => path.to_string(),
+        }
+    }
+}
+
+impl AssetHandles {
+    /// Loads the images of `variant` in place of the current ones, the
+    /// sprites already spawned keep the old set.
+    pub fn load_textures(
+        &mut self,
+        variant: TextureVariant,
+        asset_server: &AssetServer,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) {
+        self.texture_variant = variant;
+        for (name, path) in IMAGES {
+            self.images
+                .insert(name, asset_server.load(variant.path(path)));
+        }
+        self.sprite_layout = layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::splat(SPRITE_CELL as u32 * variant.pixel_ratio()),
+            2,
+            1,
+            None,
+            None,
+        ));
+    }
+
+    /// Path of an image of the levels, in the variant loaded.
+    pub fn texture_path(&self, path: &str) -> String {
+        self.texture_variant.path(path)
+    }
+}
+
+pub fn primary_scale_factor(window_query: &Query<&Window, With<PrimaryWindow>>) -> f32 {
+    window_query
+        .get_single()
+        .map_or(1.0, |window| window.scale_factor())
+}
+
+/// Loads `variant` and points the sprites already spawned to it, their
+/// weak handles would lose the old set.
+fn swap_textures(
+    variant: TextureVariant,
+    handles: &mut AssetHandles,
+    asset_server: &AssetServer,
+    layouts: &mut Assets<TextureAtlasLayout>,
+    sprite_query: &mut Query<(&mut Handle<Image>, Option<&mut TextureAtlas>)>,
+) {
+    let old: Vec<(ImageName, AssetId<Image>)> = IMAGES
+        .iter()
+        .filter_map(|(name, _)| handles.images.get(name).map(|handle| (*name, handle.id())))
+        .collect();
+    handles.load_textures(variant, asset_server, layouts);
+    for (mut texture, atlas) in sprite_query {
+        let name = match old.iter().find(|(_, id)| *id == texture.id()) {
+            Some((name, _)) => name,
+            None => continue,
+        };
+        *texture = handles.images.get(name).unwrap().clone_weak();
+        if let Some(mut atlas) = atlas {
+            atlas.layout = handles.sprite_layout.clone();
+        }
+    }
+}
+
+/// Follows the quality setting and the monitor the window is on, falling
+/// back to the small set for good when a @2x file is missing.
+pub fn resolve_textures(
+    settings: Res<Settings>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    asset_server: Res<AssetServer>,
+    mut handles: ResMut<AssetHandles>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut sprite_query: Query<(&mut Handle<Image>, Option<&mut TextureAtlas>)>,
+) {
+    let mut variant = settings
+        .texture_quality
+        .variant(primary_scale_factor(&window_query));
+    if handles.texture_variant == TextureVariant::Hd && !handles.hd_missing {
+        let missing = handles
+            .images
+            .values()
+            .any(|handle| matches!(asset_server.load_state(handle), LoadState::Failed(_)));
+        if missing {
+            warn!("missing @2x images, using the @1x set");
+            handles.hd_missing = true;
+        }
+    }
+    if handles.hd_missing {
+        variant = TextureVariant::Sd;
+    }
+    if variant != handles.texture_variant {
+        swap_textures(
+            variant,
+            &mut handles,
+            &asset_server,
+            &mut layouts,
+            &mut sprite_query,
+        );
+    }
+}
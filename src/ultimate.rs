@@ -0,0 +1,237 @@
+use bevy::color::palettes::css::RED;
+use bevy::prelude::*;
+
+use crate::balance::Balance;
+use crate::feed::FeedMessage;
+use crate::gamepad::Rumble;
+use crate::level::{CurrentLevel, Levels};
+use crate::shields::DamageKind;
+use crate::{AssetHandles, Enemy, EnemyKilled, FontName, TimeScale};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SweepPhase {
+    Windup,
+    Sweep,
+}
+
+pub struct Sweep {
+    pub phase: SweepPhase,
+    pub elapsed: f32,
+    pub start_angle: f32,
+}
+
+#[derive(Default, Resource)]
+pub struct Ultimate {
+    pub charge: f32,
+    pub sweep: Option<Sweep>,
+}
+
+#[derive(Component)]
+pub struct LaserBeam;
+
+#[derive(Component)]
+pub struct UltimateFlash;
+
+#[derive(Component)]
+pub struct UiUltimateMeter;
+
+pub fn setup_ultimate(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+) {
+    commands.insert_resource(Ultimate::default());
+
+    let length = levels.list[current_level.0].spawner_size * 0.5;
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::srgba(1.0, 0.2, 0.2, 0.0),
+                custom_size: Some(Vec2::new(length, 12.0)),
+                anchor: bevy::sprite::Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 5.0),
+            ..default()
+        })
+        .insert(LaserBeam);
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::srgba(1.0, 1.0, 1.0, 0.0),
+                custom_size: Some(Vec2::new(length * 2.0, length * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 11.0),
+            ..default()
+        })
+        .insert(UltimateFlash);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(50.0),
+                left: Val::Px(15.0),
+                width: Val::Px(200.0),
+                height: Val::Px(16.0),
+                ..default()
+            },
+            background_color: Color::srgb(0.05, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::srgb(1.0, 0.3, 0.2).into(),
+                    ..default()
+                })
+                .insert(UiUltimateMeter);
+            parent.spawn(
+                TextBundle::from_section(
+                    "[x]",
+                    TextStyle {
+                        font: handles
+                            .fonts
+                            .get(&FontName::IosevkaRegular)
+                            .unwrap()
+                            .clone_weak(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(-32.0),
+                    ..default()
+                }),
+            );
+        });
+}
+
+pub fn charge_ultimate(
+    balance: Res<Balance>,
+    mut ultimate: ResMut<Ultimate>,
+    mut killed_events: EventReader<EnemyKilled>,
+) {
+    for _ in killed_events.read() {
+        // kills made by the sweep itself don't refill the meter
+        if ultimate.sweep.is_none() {
+            ultimate.charge = (ultimate.charge + balance.ultimate.charge_per_kill)
+                .min(balance.ultimate.charge_max);
+        }
+    }
+}
+
+pub fn activate_ultimate(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    balance: Res<Balance>,
+    mut ultimate: ResMut<Ultimate>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyX)
+        && ultimate.sweep.is_none()
+        && ultimate.charge >= balance.ultimate.charge_max
+    {
+        ultimate.charge = 0.0;
+        ultimate.sweep = Some(Sweep {
+            phase: SweepPhase::Windup,
+            elapsed: 0.0,
+            start_angle: std::f32::consts::FRAC_PI_2,
+        });
+        feed.send(FeedMessage::new("orbital laser online", Color::from(RED)));
+    }
+}
+
+pub fn update_ultimate(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    balance: Res<Balance>,
+    mut ultimate: ResMut<Ultimate>,
+    mut rumble_events: EventWriter<Rumble>,
+    mut enemy_query: Query<(&mut Enemy, &Transform), Without<LaserBeam>>,
+    mut beam_query: Query<(&mut Transform, &mut Sprite), (With<LaserBeam>, Without<Enemy>)>,
+    mut flash_query: Query<&mut Sprite, (With<UltimateFlash>, Without<LaserBeam>)>,
+) {
+    let params = &balance.ultimate;
+    let delta = time_scale.delta_seconds(&time);
+    let mut done = false;
+    let mut beam_alpha = 0.0;
+    let mut flash_alpha = 0.0;
+
+    if let Some(sweep) = &mut ultimate.sweep {
+        sweep.elapsed += delta;
+        let angle = match sweep.phase {
+            SweepPhase::Windup => {
+                let t = (sweep.elapsed / params.windup_seconds).min(1.0);
+                beam_alpha = 0.3 * t;
+                if sweep.elapsed >= params.windup_seconds {
+                    sweep.phase = SweepPhase::Sweep;
+                    sweep.elapsed = 0.0;
+                    flash_alpha = 0.6;
+                    rumble_events.send(Rumble::new(1.0, (params.sweep_seconds * 1000.0) as u32));
+                }
+                sweep.start_angle
+            }
+            SweepPhase::Sweep => {
+                let t = (sweep.elapsed / params.sweep_seconds).min(1.0);
+                beam_alpha = 1.0;
+                flash_alpha = 0.3 * (1.0 - t);
+                if t >= 1.0 {
+                    done = true;
+                }
+                sweep.start_angle + t * 2.0 * std::f32::consts::PI
+            }
+        };
+
+        if let Ok((mut beam_trans, _)) = beam_query.get_single_mut() {
+            beam_trans.rotation = Quat::from_rotation_z(angle);
+        }
+
+        if sweep.phase == SweepPhase::Sweep {
+            let beam_dir = Vec2::new(f32::cos(angle), f32::sin(angle));
+            for (mut enemy, enemy_trans) in &mut enemy_query {
+                let pos = enemy_trans.translation.truncate();
+                let diff = beam_dir.angle_between(pos).abs();
+                if !diff.is_nan() && diff < params.beam_width {
+                    enemy.take_damage(params.damage_per_second * delta, DamageKind::Energy);
+                }
+            }
+        }
+    }
+
+    if done {
+        ultimate.sweep = None;
+    }
+
+    if let Ok((_, mut beam_sprite)) = beam_query.get_single_mut() {
+        beam_sprite.color.set_alpha(beam_alpha);
+    }
+    if let Ok(mut flash_sprite) = flash_query.get_single_mut() {
+        flash_sprite.color.set_alpha(flash_alpha);
+    }
+}
+
+pub fn update_ui_ultimate(
+    balance: Res<Balance>,
+    ultimate: Res<Ultimate>,
+    mut meter_query: Query<(&mut Style, &mut BackgroundColor), With<UiUltimateMeter>>,
+) {
+    if let Ok((mut style, mut color)) = meter_query.get_single_mut() {
+        let fill = ultimate.charge / balance.ultimate.charge_max;
+        style.size.width = Val::Percent(fill * 100.0);
+        *color = if fill >= 1.0 {
+            Color::srgb(1.0, 0.9, 0.2).into()
+        } else {
+            Color::srgb(1.0, 0.3, 0.2).into()
+        };
+    }
+}
@@ -0,0 +1,31 @@
+// The world unit is the pixel: positions, collider sizes, speeds in pixels
+// per second and accelerations in pixels per second squared. Rapier works
+// in meters, its plugin converts with the pixels per meter of the balance,
+// which only changes the solver tolerances and the mass of the bodies.
+
+/// Scale of the physics when the balance doesn't set one.
+pub const DEFAULT_PIXELS_PER_METER: f32 = 100.0;
+
+/// Rate the per frame tunings were authored at. A velocity change per frame
+/// at this rate becomes an acceleration through [`per_reference_frame`].
+pub const REFERENCE_FPS: f32 = 60.0;
+
+// collider sizes, in pixels
+
+pub const BULLET_RADIUS: f32 = 8.0;
+pub const SATELLITE_RADIUS: f32 = 6.0;
+pub const ALLY_RADIUS: f32 = 8.0;
+/// Enemies are capsules along their length.
+pub const ENEMY_HALF_LENGTH: f32 = 10.0;
+pub const ENEMY_RADIUS: f32 = 10.0;
+pub const WALL_HALF_WIDTH: f32 = 4.0;
+pub const WALL_HALF_HEIGHT: f32 = 24.0;
+pub const MINE_RADIUS: f32 = 12.0;
+/// Colliders of meshes scaled to their size, like zones and hazards.
+pub const UNIT_RADIUS: f32 = 0.5;
+
+/// Frames at the reference rate that fit in `seconds`, the factor turning
+/// a per frame tuning into one that doesn't depend on the frame rate.
+pub fn per_reference_frame(seconds: f32) -> f32 {
+    seconds * REFERENCE_FPS
+}
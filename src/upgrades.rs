@@ -0,0 +1,582 @@
+use std::collections::HashSet;
+
+use rand::prelude::*;
+use serde::*;
+
+use bevy::color::palettes::css::{GOLD, GRAY, MEDIUM_PURPLE, SKY_BLUE};
+use bevy::prelude::*;
+
+use crate::balance::{BeamBalance, MineBalance, ParryBalance, WeaponBalance, ZoneBalance};
+use crate::conditions::RunModifiers;
+use crate::feed::FeedMessage;
+use crate::level::{CurrentLevel, Levels};
+use crate::{AssetHandles, FontName, Planet, Wallet, WaveEnded};
+
+// embedded for the same reason as the level files
+const UPGRADES_FILE: &str = include_str!("../assets/upgrades.json");
+
+/// Cards offered at the end of a wave.
+pub const DRAFT_SIZE: usize = 3;
+
+const CARD_IDLE: Color = Color::srgb(0.05, 0.05, 0.05);
+const CARD_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
+const CARD_KEYS: [KeyCode; DRAFT_SIZE] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    /// Relative chance of being offered.
+    pub fn weight(self) -> f32 {
+        match self {
+            Rarity::Common => 6.0,
+            Rarity::Rare => 3.0,
+            Rarity::Epic => 1.0,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Rarity::Common => Color::WHITE,
+            Rarity::Rare => Color::from(SKY_BLUE),
+            Rarity::Epic => Color::from(MEDIUM_PURPLE),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum UpgradeEffect {
+    /// Added to the damage multiplier of the players' shots.
+    Damage(f32),
+    FireRate(f32),
+    Range(f32),
+    /// Fraction of the max hp repaired right away.
+    Repair(f32),
+    MaxHp(f32),
+    /// Hits leave a damaging zone.
+    Explosive(ZoneBalance),
+    /// Enemies a shot passes through.
+    Pierce(u8),
+    /// Kills inside the zones set off another one.
+    Chain,
+    /// The shots become proximity mines.
+    MineLayer(MineBalance),
+    /// The shots become a continuous beam.
+    Beam(BeamBalance),
+    /// A press of the parry binding reflects the enemy shots.
+    Parry(ParryBalance),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UpgradeDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub rarity: Rarity,
+    /// What the upgrade counts as towards the synergies.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub effects: Vec<UpgradeEffect>,
+}
+
+/// Bonus unlocked once the taken upgrades carry all the required tags.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SynergyDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub requires: Vec<String>,
+    pub effects: Vec<UpgradeEffect>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Resource)]
+pub struct UpgradeDefinitions {
+    /// Scrap for the first reroll of a draft, each further one costs as
+    /// much again.
+    pub reroll_cost: u32,
+    pub list: Vec<UpgradeDefinition>,
+    #[serde(default)]
+    pub synergies: Vec<SynergyDefinition>,
+}
+
+impl Default for UpgradeDefinitions {
+    fn default() -> Self {
+        serde_json::from_str(UPGRADES_FILE).expect("invalid upgrades file")
+    }
+}
+
+impl UpgradeDefinitions {
+    pub fn get(&self, id: &str) -> Option<&UpgradeDefinition> {
+        self.list.iter().find(|upgrade| upgrade.id == id)
+    }
+
+    /// Distinct upgrades weighted by rarity, among the available ids or
+    /// among all of them when none are listed.
+    pub fn draw(&self, rng: &mut impl Rng, count: usize, available: &[String]) -> Vec<usize> {
+        let indices: Vec<usize> = (0..self.list.len())
+            .filter(|i| available.is_empty() || available.contains(&self.list[*i].id))
+            .collect();
+        indices
+            .choose_multiple_weighted(rng, count, |i| self.list[*i].rarity.weight())
+            .map(|chosen| chosen.copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Upgrades picked in the current run, and the weapon bonuses they add
+/// up to.
+#[derive(Default, Resource)]
+pub struct RunUpgrades {
+    pub taken: Vec<String>,
+    /// Tags of the taken upgrades.
+    pub tags: HashSet<String>,
+    /// Ids of the synergies unlocked.
+    pub synergies: Vec<String>,
+    pub damage: f32,
+    pub fire_rate: f32,
+    pub range: f32,
+    pub zone: Option<ZoneBalance>,
+    pub pierce: u8,
+    pub chain: bool,
+    pub mines: Option<MineBalance>,
+    pub beam: Option<BeamBalance>,
+    pub parry: Option<ParryBalance>,
+}
+
+impl RunUpgrades {
+    /// Records the upgrade and its weapon effects, the planet effects are
+    /// applied by whoever has the planet at hand.
+    pub fn take(&mut self, upgrade: &UpgradeDefinition) {
+        self.taken.push(upgrade.id.clone());
+        self.tags.extend(upgrade.tags.iter().cloned());
+        self.apply(&upgrade.effects);
+    }
+
+    fn apply(&mut self, effects: &[UpgradeEffect]) {
+        for effect in effects {
+            match effect {
+                UpgradeEffect::Damage(amount) => self.damage += amount,
+                UpgradeEffect::FireRate(amount) => self.fire_rate += amount,
+                UpgradeEffect::Range(amount) => self.range += amount,
+                UpgradeEffect::Explosive(zone) => self.zone = Some(zone.clone()),
+                UpgradeEffect::Pierce(amount) => self.pierce = self.pierce.saturating_add(*amount),
+                UpgradeEffect::Chain => self.chain = true,
+                UpgradeEffect::MineLayer(mines) => self.mines = Some(mines.clone()),
+                UpgradeEffect::Beam(beam) => self.beam = Some(beam.clone()),
+                UpgradeEffect::Parry(parry) => self.parry = Some(parry.clone()),
+                UpgradeEffect::Repair(_) | UpgradeEffect::MaxHp(_) => {}
+            }
+        }
+    }
+
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+    }
+
+    /// Synergies whose tags are all taken but that aren't unlocked yet.
+    pub fn pending_synergies<'a>(
+        &self,
+        definitions: &'a UpgradeDefinitions,
+    ) -> Vec<&'a SynergyDefinition> {
+        definitions
+            .synergies
+            .iter()
+            .filter(|synergy| !self.synergies.contains(&synergy.id))
+            .filter(|synergy| self.has_tags(&synergy.requires))
+            .collect()
+    }
+
+    pub fn unlock(&mut self, synergy: &SynergyDefinition) {
+        self.synergies.push(synergy.id.clone());
+        self.apply(&synergy.effects);
+    }
+
+    /// The weapon bonuses of a list of taken upgrades, for restoring a run.
+    pub fn from_taken(taken: &[String], definitions: &UpgradeDefinitions) -> RunUpgrades {
+        let mut upgrades = RunUpgrades::default();
+        for id in taken {
+            match definitions.get(id) {
+                Some(upgrade) => upgrades.take(upgrade),
+                None => warn!("no upgrade with id {}", id),
+            }
+        }
+        for synergy in upgrades.pending_synergies(definitions) {
+            upgrades.unlock(synergy);
+        }
+        upgrades
+    }
+
+    pub fn damage_multiplier(&self) -> f32 {
+        (1.0 + self.damage).max(0.1)
+    }
+
+    pub fn fire_rate_multiplier(&self) -> f32 {
+        (1.0 + self.fire_rate).max(0.1)
+    }
+
+    /// The player weapon with the bonuses applied.
+    pub fn weapon(&self, base: &WeaponBalance) -> WeaponBalance {
+        WeaponBalance {
+            range: base.range * (1.0 + self.range).max(0.1),
+            zone: base
+                .zone
+                .clone()
+                .or_else(|| self.zone.clone())
+                .map(|zone| ZoneBalance {
+                    chain: zone.chain || self.chain,
+                    ..zone
+                }),
+            pierce: base.pierce.saturating_add(self.pierce),
+            mines: base.mines.clone().or_else(|| self.mines.clone()),
+            beam: base.beam.clone().or_else(|| self.beam.clone()),
+            ..base.clone()
+        }
+    }
+}
+
+/// Cards on offer, empty when no draft is open.
+#[derive(Default, Resource)]
+pub struct UpgradeDraft {
+    pub offer: Vec<usize>,
+    pub rerolls: u32,
+}
+
+impl UpgradeDraft {
+    pub fn is_open(&self) -> bool {
+        !self.offer.is_empty()
+    }
+
+    pub fn reroll_cost(&self, definitions: &UpgradeDefinitions) -> u32 {
+        definitions.reroll_cost * (self.rerolls + 1)
+    }
+}
+
+#[derive(Component)]
+pub struct DraftScreen;
+
+#[derive(Component)]
+pub struct UpgradeCard(usize);
+
+#[derive(Component)]
+pub struct RerollButton;
+
+#[derive(Component)]
+pub struct SynergyBrowser;
+
+/// Planet side of the effects, the rest is kept in [`RunUpgrades`].
+fn apply_to_planet(planet: &mut Planet, effects: &[UpgradeEffect]) {
+    for effect in effects {
+        match effect {
+            UpgradeEffect::Repair(fraction) => {
+                planet.hp = (planet.hp + planet.max_hp * fraction).min(planet.max_hp);
+            }
+            UpgradeEffect::MaxHp(amount) => {
+                planet.max_hp += amount;
+                planet.hp += amount;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn setup_upgrades(mut upgrades: ResMut<RunUpgrades>, mut draft: ResMut<UpgradeDraft>) {
+    *upgrades = RunUpgrades::default();
+    *draft = UpgradeDraft::default();
+}
+
+/// Opens a draft when a wave is cleared, the intermission waits for the
+/// pick. Speedruns have no intermission to wait in.
+pub fn offer_upgrades(
+    definitions: Res<UpgradeDefinitions>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    modifiers: Res<RunModifiers>,
+    mut draft: ResMut<UpgradeDraft>,
+    mut wave_events: EventReader<WaveEnded>,
+) {
+    if wave_events.read().last().is_none() || modifiers.speedrun {
+        return;
+    }
+    *draft = UpgradeDraft {
+        offer: definitions.draw(
+            &mut thread_rng(),
+            DRAFT_SIZE,
+            &levels.list[current_level.0].upgrades,
+        ),
+        rerolls: 0,
+    };
+}
+
+pub fn draft_interaction(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    definitions: Res<UpgradeDefinitions>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    mut draft: ResMut<UpgradeDraft>,
+    mut upgrades: ResMut<RunUpgrades>,
+    mut wallet: ResMut<Wallet>,
+    mut planet_query: Query<&mut Planet>,
+    mut card_query: Query<
+        (&Interaction, &UpgradeCard, &mut BackgroundColor),
+        (Changed<Interaction>, Without<RerollButton>),
+    >,
+    mut reroll_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<RerollButton>),
+    >,
+) {
+    if !draft.is_open() {
+        return;
+    }
+    let mut picked = CARD_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key));
+    for (interaction, card, mut color) in &mut card_query {
+        match *interaction {
+            Interaction::Pressed => picked = Some(card.0),
+            Interaction::Hovered => *color = CARD_HOVER.into(),
+            Interaction::None => *color = CARD_IDLE.into(),
+        }
+    }
+    let mut reroll = keyboard_input.just_pressed(KeyCode::KeyR);
+    for (interaction, mut color) in &mut reroll_query {
+        match *interaction {
+            Interaction::Pressed => reroll = true,
+            Interaction::Hovered => *color = CARD_HOVER.into(),
+            Interaction::None => *color = CARD_IDLE.into(),
+        }
+    }
+
+    if let Some(upgrade) = picked.and_then(|card| draft.offer.get(card)) {
+        let upgrade = &definitions.list[*upgrade];
+        upgrades.take(upgrade);
+        for mut planet in &mut planet_query {
+            apply_to_planet(&mut planet, &upgrade.effects);
+        }
+        draft.offer.clear();
+    } else if reroll && wallet.scrap >= draft.reroll_cost(&definitions) {
+        wallet.scrap -= draft.reroll_cost(&definitions);
+        draft.rerolls += 1;
+        draft.offer = definitions.draw(
+            &mut thread_rng(),
+            DRAFT_SIZE,
+            &levels.list[current_level.0].upgrades,
+        );
+    }
+}
+
+/// Rebuilds the card screen whenever the offer changes.
+pub fn show_draft(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    definitions: Res<UpgradeDefinitions>,
+    draft: Res<UpgradeDraft>,
+    screen_query: Query<Entity, With<DraftScreen>>,
+) {
+    if !draft.is_changed() {
+        return;
+    }
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !draft.is_open() {
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..default()
+        })
+        .insert(DraftScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "pick an upgrade",
+                style(48.0, Color::WHITE),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (card, upgrade) in draft.offer.iter().enumerate() {
+                        let upgrade = &definitions.list[*upgrade];
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(220.0),
+                                    height: Val::Px(160.0),
+                                    margin: UiRect::all(Val::Px(8.0)),
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::SpaceBetween,
+                                    ..default()
+                                },
+                                background_color: CARD_IDLE.into(),
+                                ..default()
+                            })
+                            .insert(UpgradeCard(card))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    format!("[{}] {}", card + 1, upgrade.name),
+                                    style(24.0, upgrade.rarity.color()),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    upgrade.description.clone(),
+                                    style(20.0, Color::WHITE),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    format!("{:?}", upgrade.rarity).to_lowercase(),
+                                    style(16.0, Color::from(GRAY)),
+                                ));
+                            });
+                    }
+                });
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Px(220.0),
+                        height: Val::Px(48.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: CARD_IDLE.into(),
+                    ..default()
+                })
+                .insert(RerollButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        format!("[r] reroll {} scrap", draft.reroll_cost(&definitions)),
+                        style(20.0, Color::from(GRAY)),
+                    ));
+                });
+        });
+}
+
+/// Unlocks the synergies completed by the last upgrade taken.
+pub fn evaluate_synergies(
+    definitions: Res<UpgradeDefinitions>,
+    mut upgrades: ResMut<RunUpgrades>,
+    mut planet_query: Query<&mut Planet>,
+    mut feed: EventWriter<FeedMessage>,
+) {
+    if !upgrades.is_changed() {
+        return;
+    }
+    // only borrowed mutably when something unlocks, or the change would
+    // never settle
+    for synergy in upgrades.pending_synergies(&definitions) {
+        upgrades.unlock(synergy);
+        for mut planet in &mut planet_query {
+            apply_to_planet(&mut planet, &synergy.effects);
+        }
+        feed.send(FeedMessage::new(
+            format!("synergy: {}", synergy.name),
+            Color::from(GOLD),
+        ));
+    }
+}
+
+/// Lists every synergy, with the tags still missing, on [tab].
+pub fn toggle_synergy_browser(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    handles: Res<AssetHandles>,
+    definitions: Res<UpgradeDefinitions>,
+    upgrades: Res<RunUpgrades>,
+    browser_query: Query<Entity, With<SynergyBrowser>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    if !browser_query.is_empty() {
+        for entity in &browser_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        })
+        .insert(SynergyBrowser)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "synergies",
+                style(48.0, Color::WHITE),
+            ));
+            for synergy in &definitions.synergies {
+                let unlocked = upgrades.synergies.contains(&synergy.id);
+                let mut sections = vec![TextSection::new(
+                    format!("{}: {}  ", synergy.name, synergy.description),
+                    style(22.0, if unlocked { GOLD.into() } else { Color::WHITE }),
+                )];
+                sections.extend(synergy.requires.iter().map(|tag| {
+                    let color = if upgrades.tags.contains(tag) {
+                        Color::WHITE
+                    } else {
+                        Color::from(GRAY)
+                    };
+                    TextSection::new(format!("[{}] ", tag), style(18.0, color))
+                }));
+                parent.spawn(TextBundle::from_sections(sections).with_style(Style {
+                    margin: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                }));
+            }
+            parent.spawn(TextBundle::from_section(
+                "[tab] close",
+                style(18.0, Color::from(GRAY)),
+            ));
+        });
+}
@@ -0,0 +1,243 @@
+use bevy::color::palettes::css::{CYAN, GRAY, GREEN, YELLOW};
+use bevy::prelude::*;
+
+use rand::prelude::*;
+
+use crate::balance::Balance;
+use crate::checkpoints::RunCheckpoint;
+use crate::conditions::{ResetRun, RunModifiers, RunOutcome};
+use crate::level::{CurrentLevel, Levels};
+use crate::party::{PartyStats, PARTY_COLORS};
+use crate::profile::Profile;
+use crate::score::{RunStats, ScoreBreakdown};
+use crate::spawner::RunSeed;
+use crate::{AppState, AssetHandles, Challenge, FontName, Planet, RunPhase, Wave};
+
+#[derive(Component)]
+pub struct VictoryScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum VictoryButton {
+    NewGamePlus,
+    Endless,
+    Menu,
+}
+
+const BUTTON_IDLE: Color = Color::srgb(0.05, 0.05, 0.05);
+const BUTTON_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
+
+pub fn setup_victory_screen(
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    outcome: Res<RunOutcome>,
+    stats: Res<RunStats>,
+    party: Res<PartyStats>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
+    modifiers: Res<RunModifiers>,
+    mut profile: ResMut<Profile>,
+    checkpoint: Res<RunCheckpoint>,
+    planet_query: Query<&Planet>,
+) {
+    let planet_hp = planet_query.get_single().map(|p| p.hp).unwrap_or(0.0);
+    let breakdown =
+        ScoreBreakdown::compute(&stats, &outcome, planet_hp, checkpoint.score_multiplier());
+    let total = breakdown.total();
+
+    let practice = modifiers.practice.is_some();
+    let best = !practice && profile.record_score(current_level.0, total);
+    let unlocked = if practice {
+        None
+    } else if current_level.0 + 1 < levels.list.len()
+        && profile.unlocked_levels <= current_level.0 + 1
+    {
+        profile.unlocked_levels = current_level.0 + 2;
+        Some(levels.list[current_level.0 + 1].name.clone())
+    } else {
+        None
+    };
+    let rewind = !practice && !profile.rewind;
+    if rewind {
+        profile.rewind = true;
+    }
+    if !practice {
+        profile.save();
+    }
+
+    let font = handles
+        .fonts
+        .get(&FontName::IosevkaRegular)
+        .unwrap()
+        .clone_weak();
+    let style = |size: f32, color: Color| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color,
+    };
+
+    let mut lines = vec![
+        (outcome.reason.clone(), Color::WHITE),
+        (format!("kills {:>14}", breakdown.kills), Color::WHITE),
+        (
+            format!(
+                "accuracy {:>3.0}% {:>7}",
+                stats.accuracy() * 100.0,
+                breakdown.accuracy
+            ),
+            Color::WHITE,
+        ),
+        (format!("hp bonus {:>11}", breakdown.hp), Color::WHITE),
+        (format!("time bonus {:>9}", breakdown.time), Color::WHITE),
+        (format!("kill bonus {:>9}", breakdown.bonus), Color::WHITE),
+    ];
+    if checkpoint.continues > 0 {
+        lines.push((
+            format!(
+                "{} continues x{:.2}",
+                checkpoint.continues, breakdown.multiplier
+            ),
+            Color::from(GRAY),
+        ));
+    }
+    lines.push((format!("score {:>14}", total), Color::from(YELLOW)));
+    if party.kills.len() > 1 {
+        for (index, kills) in party.kills.iter().enumerate() {
+            lines.push((
+                format!("p{} kills {:>11}", index + 1, kills),
+                PARTY_COLORS[index],
+            ));
+        }
+    }
+    if practice {
+        lines.push((
+            "practice, score not recorded".to_string(),
+            Color::from(GRAY),
+        ));
+    }
+    if best {
+        lines.push(("new best!".to_string(), Color::from(YELLOW)));
+    }
+    if let Some(level) = unlocked {
+        lines.push((format!("unlocked {}", level), Color::from(CYAN)));
+    }
+    if rewind {
+        lines.push((
+            "unlocked rewind, a destroyed planet goes back in time once per run".to_string(),
+            Color::from(CYAN),
+        ));
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(VictoryScreen)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "victory",
+                style(96.0, Color::from(GREEN)),
+            ));
+            for (line, color) in lines {
+                parent.spawn(TextBundle::from_section(line, style(32.0, color)));
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (VictoryButton::NewGamePlus, "new game+"),
+                        (VictoryButton::Endless, "endless"),
+                        (VictoryButton::Menu, "menu"),
+                    ] {
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(56.0),
+                                    margin: UiRect::all(Val::Px(8.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: BUTTON_IDLE.into(),
+                                ..default()
+                            })
+                            .insert(button)
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    style(32.0, Color::WHITE),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+pub fn victory_interaction(
+    mut phase: ResMut<NextState<RunPhase>>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut challenge: ResMut<Challenge>,
+    seed: Res<RunSeed>,
+    balance: Res<Balance>,
+    mut reset_events: EventWriter<ResetRun>,
+    mut button_query: Query<
+        (&Interaction, &VictoryButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, button, mut color) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => match button {
+                VictoryButton::NewGamePlus => {
+                    modifiers.ng_plus += 1;
+                    modifiers.endless = false;
+                    reset_events.send(ResetRun {
+                        to: AppState::InGame,
+                    });
+                }
+                VictoryButton::Endless => {
+                    // keep the run going with procedural waves after the last one
+                    modifiers.endless = true;
+                    let len = challenge.waves.len() as i32;
+                    let mut rng = StdRng::seed_from_u64(seed.0.wrapping_add(len as u64));
+                    challenge.waves.extend((len..len + 100).map(|progress| {
+                        Wave::from_progress(progress, &balance.spawner.bands, &mut rng)
+                    }));
+                    phase.set(RunPhase::Playing);
+                }
+                VictoryButton::Menu => {
+                    reset_events.send(ResetRun { to: AppState::Menu });
+                }
+            },
+            Interaction::Hovered => *color = BUTTON_HOVER.into(),
+            Interaction::None => *color = BUTTON_IDLE.into(),
+        }
+    }
+}
+
+pub fn cleanup_victory_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<VictoryScreen>>,
+) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
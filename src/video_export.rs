@@ -0,0 +1,55 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+
+use crate::conditions::{PracticeLoadout, RunModifiers};
+use crate::input_trace::InputReplay;
+use crate::AppState;
+
+/// Frames rendered past the end of the trace, the screenshots are written
+/// a frame or two after they are taken.
+const EXPORT_TAIL_FRAMES: u32 = 3;
+
+/// Directory the frames of a replay go to, one png per simulated frame.
+#[derive(Resource)]
+pub struct FrameExport {
+    pub dir: String,
+    pub frame: u32,
+}
+
+/// Skips the menu into the replayed run, which doesn't end up in the
+/// profile like the benchmark.
+pub fn start_replay(mut modifiers: ResMut<RunModifiers>, mut state: ResMut<NextState<AppState>>) {
+    modifiers.practice = Some(PracticeLoadout::default());
+    state.set(AppState::InGame);
+}
+
+/// Saves every rendered frame of the replay. The time is stepped by a
+/// fixed duration, so the frames come out the same however long each
+/// takes to render, and quits once the trace is over.
+pub fn export_frames(
+    replay: Res<InputReplay>,
+    mut export: ResMut<FrameExport>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if replay.frame >= replay.trace.frames() + EXPORT_TAIL_FRAMES {
+        info!(frames = export.frame, dir = export.dir, "replay exported");
+        exit_events.send(AppExit::Success);
+        return;
+    }
+    if replay.finished() {
+        return;
+    }
+    let window = match window_query.get_single() {
+        Ok(window) => window,
+        Err(_) => return,
+    };
+    let path = format!("{}/frame_{:06}.png", export.dir, export.frame);
+    if let Err(err) = screenshot_manager.save_screenshot_to_disk(window, path) {
+        warn!("failed to save the frame {}: {}", export.frame, err);
+    }
+    export.frame += 1;
+}
@@ -0,0 +1,231 @@
+use serde::*;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::conditions::Persistent;
+use crate::settings::Settings;
+use crate::CameraZoom;
+
+/// Side of the square of world the camera frames, the arena.
+pub const VIEW_SIZE: f32 = 1024.0;
+
+/// How the arena is framed when the window isn't square.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum FitStrategy {
+    /// The whole arena, with bars along the long side of the window.
+    Fit,
+    /// No bars, the long side of the window crops the arena.
+    Fill,
+    /// Pixels stay 1:1 and big windows see around the arena, small ones
+    /// zoom out to keep it whole.
+    #[default]
+    Expand,
+}
+
+impl FitStrategy {
+    pub const ALL: [FitStrategy; 3] = [FitStrategy::Fit, FitStrategy::Fill, FitStrategy::Expand];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FitStrategy::Fit => "fit",
+            FitStrategy::Fill => "fill",
+            FitStrategy::Expand => "expand",
+        }
+    }
+
+    /// Projection scale framing the arena in a window of `size`.
+    pub fn scale(&self, size: Vec2) -> f32 {
+        match self {
+            FitStrategy::Fit => VIEW_SIZE / size.min_element(),
+            FitStrategy::Fill => VIEW_SIZE / size.max_element(),
+            FitStrategy::Expand => (VIEW_SIZE / size.min_element()).max(1.0),
+        }
+    }
+}
+
+/// Insets of the window the hud stays out of, in logical pixels: the
+/// letterbox bars and the margin of the settings.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub struct SafeArea {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum LetterboxBar {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Offsets a hud root was spawned with, before the safe area is added.
+#[derive(Component)]
+pub struct SafeAreaBase {
+    left: Option<f32>,
+    right: Option<f32>,
+    top: Option<f32>,
+    bottom: Option<f32>,
+}
+
+/// Width of the side bars and height of the top and bottom ones.
+fn letterbox(fit: FitStrategy, size: Vec2) -> Vec2 {
+    if fit == FitStrategy::Fit {
+        (size - Vec2::splat(size.min_element())) / 2.0
+    } else {
+        Vec2::ZERO
+    }
+}
+
+fn px(val: Val) -> Option<f32> {
+    match val {
+        Val::Px(value) => Some(value),
+        _ => None,
+    }
+}
+
+pub fn setup_letterbox(mut commands: Commands) {
+    for bar in [
+        LetterboxBar::Left,
+        LetterboxBar::Right,
+        LetterboxBar::Top,
+        LetterboxBar::Bottom,
+    ] {
+        let mut style = Style {
+            position_type: PositionType::Absolute,
+            width: Val::Px(0.0),
+            height: Val::Px(0.0),
+            ..default()
+        };
+        match bar {
+            LetterboxBar::Left => style.left = Val::Px(0.0),
+            LetterboxBar::Right => style.right = Val::Px(0.0),
+            LetterboxBar::Top => style.top = Val::Px(0.0),
+            LetterboxBar::Bottom => style.bottom = Val::Px(0.0),
+        }
+        commands
+            .spawn(NodeBundle {
+                style,
+                background_color: Color::BLACK.into(),
+                // over the world, under every other widget
+                z_index: ZIndex::Global(-1),
+                ..default()
+            })
+            .insert(bar)
+            .insert(Persistent);
+    }
+}
+
+/// Frames the camera on the arena and works out the safe area.
+pub fn frame_camera(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    zoom: Res<CameraZoom>,
+    settings: Res<Settings>,
+    mut safe_area: ResMut<SafeArea>,
+    mut projection: Query<&mut OrthographicProjection>,
+) {
+    let window = match window_query.get_single() {
+        Ok(window) => window,
+        Err(_) => return,
+    };
+    let size = Vec2::new(window.width(), window.height());
+    if size.min_element() <= 0.0 {
+        return;
+    }
+    projection.single_mut().scale = settings.fit.scale(size) * zoom.0;
+
+    let bars = letterbox(settings.fit, size);
+    let margin = size.min_element() * settings.safe_margin;
+    let area = SafeArea {
+        left: bars.x + margin,
+        right: bars.x + margin,
+        top: bars.y + margin,
+        bottom: bars.y + margin,
+    };
+    if *safe_area != area {
+        *safe_area = area;
+    }
+}
+
+pub fn update_letterbox(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<Settings>,
+    mut bar_query: Query<(&LetterboxBar, &mut Style)>,
+) {
+    let window = match window_query.get_single() {
+        Ok(window) => window,
+        Err(_) => return,
+    };
+    let size = Vec2::new(window.width(), window.height());
+    let bars = letterbox(settings.fit, size);
+    for (bar, mut style) in &mut bar_query {
+        let (width, height) = match bar {
+            LetterboxBar::Left | LetterboxBar::Right => (Val::Px(bars.x), Val::Percent(100.0)),
+            LetterboxBar::Top | LetterboxBar::Bottom => (Val::Percent(100.0), Val::Px(bars.y)),
+        };
+        if style.width != width || style.height != height {
+            style.width = width;
+            style.height = height;
+        }
+    }
+}
+
+fn apply_safe_area(style: &mut Style, base: &SafeAreaBase, area: &SafeArea) {
+    if let Some(left) = base.left {
+        style.left = Val::Px(left + area.left);
+    }
+    if let Some(right) = base.right {
+        style.right = Val::Px(right + area.right);
+    }
+    if let Some(top) = base.top {
+        style.top = Val::Px(top + area.top);
+    }
+    if let Some(bottom) = base.bottom {
+        style.bottom = Val::Px(bottom + area.bottom);
+    }
+}
+
+/// Hud widgets are absolute roots placed in pixels from the window edges,
+/// they are pushed inside the safe area as they spawn and when it changes.
+pub fn anchor_to_safe_area(
+    mut commands: Commands,
+    safe_area: Res<SafeArea>,
+    mut new_query: Query<
+        (Entity, &mut Style),
+        (
+            With<Node>,
+            Without<Parent>,
+            Without<SafeAreaBase>,
+            Without<LetterboxBar>,
+        ),
+    >,
+    mut anchored_query: Query<(&mut Style, &SafeAreaBase)>,
+) {
+    for (entity, mut style) in &mut new_query {
+        let base = if style.position_type == PositionType::Absolute {
+            SafeAreaBase {
+                left: px(style.left),
+                right: px(style.right),
+                top: px(style.top),
+                bottom: px(style.bottom),
+            }
+        } else {
+            SafeAreaBase {
+                left: None,
+                right: None,
+                top: None,
+                bottom: None,
+            }
+        };
+        apply_safe_area(&mut style, &base, &safe_area);
+        commands.entity(entity).insert(base);
+    }
+    if safe_area.is_changed() {
+        for (mut style, base) in &mut anchored_query {
+            apply_safe_area(&mut style, base, &safe_area);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy::window::{PresentMode, WindowMode, WindowResolution};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::window::PrimaryWindow;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::winit::WinitWindows;
+
+/// Branding and platform behavior of the primary window.
+#[derive(Resource, Clone)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: f32,
+    pub height: f32,
+    /// Png shown in the title bar and taskbar on native.
+    pub icon: &'static [u8],
+    /// Selector of the canvas element the game draws into on the web.
+    pub canvas: String,
+    /// Keep the canvas as big as its parent element on the web.
+    pub fit_canvas_to_parent: bool,
+    /// Borderless on the whole monitor, ignoring the size.
+    pub fullscreen: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: "Planet".to_string(),
+            width: 1280.0,
+            height: 720.0,
+            icon: include_bytes!("../assets/simple_planet.png"),
+            canvas: "#planet".to_string(),
+            fit_canvas_to_parent: true,
+            fullscreen: false,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn window(&self) -> Window {
+        Window {
+            title: self.title.clone(),
+            name: Some(self.title.to_lowercase()),
+            resolution: WindowResolution::new(self.width, self.height),
+            mode: if self.fullscreen {
+                WindowMode::BorderlessFullscreen
+            } else {
+                WindowMode::Windowed
+            },
+            present_mode: PresentMode::AutoVsync,
+            canvas: Some(self.canvas.clone()),
+            fit_canvas_to_parent: self.fit_canvas_to_parent,
+            // let the browser keep its shortcuts (reload, devtools)
+            prevent_default_event_handling: false,
+            ..default()
+        }
+    }
+}
+
+/// The browser takes the icon from the page, so this only runs on native.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_window_icon(
+    config: Res<WindowConfig>,
+    winit_windows: NonSend<WinitWindows>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+) {
+    let window = match window_query
+        .get_single()
+        .ok()
+        .and_then(|entity| winit_windows.get_window(entity))
+    {
+        Some(window) => window,
+        None => return,
+    };
+    let image = match image::load_from_memory(config.icon) {
+        Ok(image) => image.into_rgba8(),
+        Err(_) => {
+            warn!("failed to decode the window icon");
+            return;
+        }
+    };
+    let (width, height) = image.dimensions();
+    match winit::window::Icon::from_rgba(image.into_raw(), width, height) {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(_) => warn!("failed to set the window icon"),
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+
+use bevy_rapier2d::prelude::*;
+
+use crate::balance::ZoneBalance;
+use crate::settings::Settings;
+use crate::shields::DamageKind;
+use crate::teams::{Role, Team};
+use crate::units::UNIT_RADIUS;
+use crate::{AssetHandles, Enemy, MaterialName, MeshName, Planet, PlanetDamaged, TimeScale};
+
+#[derive(Component)]
+pub struct Zone {
+    /// Side the zone fights for, it hurts the teams that side hurts.
+    pub team: Team,
+    pub damage_per_tick: f32,
+    pub tick: Timer,
+    pub lifetime: Timer,
+    /// Player credited for the kills, none for boss attacks.
+    pub owner: Option<usize>,
+    /// Set off where an enemy dies inside the zone.
+    pub chain: Option<ZoneBalance>,
+}
+
+pub fn spawn_zone(
+    commands: &mut Commands,
+    handles: &AssetHandles,
+    position: Vec2,
+    zone: &ZoneBalance,
+    team: Team,
+    owner: Option<usize>,
+) {
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: handles
+                .meshes
+                .get(&MeshName::Circle)
+                .unwrap()
+                .clone_weak()
+                .into(),
+            transform: Transform {
+                translation: position.extend(0.5),
+                scale: Vec3::new(zone.radius * 2.0, zone.radius * 2.0, 1.0),
+                ..default()
+            },
+            material: handles
+                .materials
+                .get(&MaterialName::Zone)
+                .unwrap()
+                .clone_weak(),
+            ..default()
+        })
+        // the mesh is scaled, so the collider is expressed in unit size
+        .insert(Collider::ball(UNIT_RADIUS))
+        .insert(Sensor)
+        // the planet is fixed, without this the pair would never be checked
+        .insert(ActiveCollisionTypes::all())
+        // zones overlap their targets the way the team's projectiles would
+        .insert(team.collision_groups(Role::Shot))
+        .insert(Zone {
+            team,
+            damage_per_tick: zone.damage_per_tick,
+            tick: Timer::new(
+                Duration::from_secs_f32(zone.tick_seconds),
+                TimerMode::Repeating,
+            ),
+            lifetime: Timer::new(
+                Duration::from_secs_f32(zone.duration_seconds),
+                TimerMode::Once,
+            ),
+            owner,
+            chain: zone.chain.then(|| zone.clone()),
+        });
+}
+
+pub fn damage_zones(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut commands: Commands,
+    handles: Res<AssetHandles>,
+    rapier_context: Res<RapierContext>,
+    mut zone_query: Query<(Entity, &mut Zone, &Transform)>,
+    mut enemy_query: Query<(&mut Enemy, &Team, &Transform)>,
+    mut planet_query: Query<(&mut Planet, &Team)>,
+    mut damage_events: EventWriter<PlanetDamaged>,
+    settings: Res<Settings>,
+) {
+    let dt = time_scale.delta(&time);
+    for (entity, mut zone, transform) in &mut zone_query {
+        zone.lifetime.tick(dt);
+        if zone.lifetime.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        zone.tick.tick(dt);
+        let ticks = zone.tick.times_finished_this_tick();
+        if ticks == 0 {
+            continue;
+        }
+        let damage = zone.damage_per_tick * ticks as f32;
+
+        for (first, second, intersecting) in rapier_context.intersection_pairs_with(entity) {
+            if !intersecting {
+                continue;
+            }
+            let other = if first == entity { second } else { first };
+            if let Ok((mut enemy, team, enemy_transform)) = enemy_query.get_mut(other) {
+                if zone.team.hurts(*team) {
+                    let alive = enemy.hp > 0.0;
+                    enemy.take_damage(damage, DamageKind::Energy);
+                    if let Some(chain) = zone.chain.as_ref().filter(|_| alive && enemy.hp <= 0.0) {
+                        spawn_zone(
+                            &mut commands,
+                            &handles,
+                            enemy_transform.translation.truncate(),
+                            chain,
+                            zone.team,
+                            zone.owner,
+                        );
+                    }
+                    debug!(target: "planet_td::damage", enemy = enemy.enemy_id, damage, hp = enemy.hp, "zone tick");
+                    if zone.owner.is_some() {
+                        enemy.last_hit_by = zone.owner;
+                    }
+                }
+            }
+            if let Ok((mut planet, team)) = planet_query.get_mut(other) {
+                if zone.team.hurts(*team) {
+                    let damage = damage * settings.assist.planet_damage;
+                    planet.hp -= damage;
+                    let from = transform.translation.truncate();
+                    damage_events.send(PlanetDamaged {
+                        damage,
+                        angle: from.y.atan2(from.x),
+                    });
+                    debug!(target: "planet_td::damage", damage, hp = planet.hp, "zone tick");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! The challenge file tooling, without the game.
+
+use planet_td::composer::{estimate, validate, EnemyDefinitions};
+
+#[test]
+fn shipped_challenges_validate() {
+    let enemies = EnemyDefinitions::default();
+    let json = include_str!("../assets/challenges/simple.json");
+    assert!(validate(json, &enemies).is_ok());
+}
+
+#[test]
+fn reports_every_bad_spawn() {
+    let enemies = EnemyDefinitions::default();
+    let errors = validate(
+        r#"{ "waves": [{ "spawns": [
+            { "enemy_id": 99, "cooldown": 500 },
+            { "enemy_id": 0, "cooldown": -1 }
+        ] }] }"#,
+        &enemies,
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(validate("{ \"waves\": [] }", &enemies).is_err());
+    assert!(validate("not json", &enemies).is_err());
+}
+
+#[test]
+fn denser_waves_are_harder() {
+    let enemies = EnemyDefinitions::default();
+    let wave = |cooldown: f32| {
+        format!(
+            r#"{{ "waves": [{{ "spawns": [{{ "repeat": 5, "spawns": [
+                {{ "enemy_id": 0, "cooldown": {} }}
+            ] }}] }}] }}"#,
+            cooldown
+        )
+    };
+    let spread = validate(&wave(2000.0), &enemies).unwrap();
+    let dense = validate(&wave(200.0), &enemies).unwrap();
+    assert!(estimate(&dense, &enemies)[0].pressure() > estimate(&spread, &enemies)[0].pressure());
+}
@@ -0,0 +1,19 @@
+//! The control schemes against the keys the game reads directly.
+
+use planet_td::controls::{Binding, ControlScheme, RESERVED_KEYS};
+
+#[test]
+fn bindings_keep_off_the_reserved_keys() {
+    for scheme in ControlScheme::ALL {
+        for binding in scheme.bindings() {
+            if let Binding::Key(key) = binding {
+                assert!(
+                    !RESERVED_KEYS.contains(&key),
+                    "{} binds {:?}",
+                    scheme.name(),
+                    key
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Offline progress of the idle mode, against a made up clock.
+
+use planet_td::idle::{IdleBank, IDLE_BANK_MAX, OFFLINE_CAP_SECONDS};
+
+const NOW: u64 = 1_700_000_000;
+const HOUR: u64 = 60 * 60;
+
+fn stamped(seconds_ago: i64) -> IdleBank {
+    IdleBank {
+        last_seen: (NOW as i64 - seconds_ago) as u64,
+        scrap: 0,
+    }
+}
+
+#[test]
+fn nothing_without_elapsed_time() {
+    let mut bank = stamped(0);
+    assert_eq!(bank.catch_up(NOW), 0);
+    assert_eq!(bank.scrap, 0);
+
+    // a bank that was never stamped has no offline time yet
+    let mut bank = IdleBank::default();
+    assert_eq!(bank.catch_up(NOW), 0);
+    assert_eq!(bank.last_seen, NOW);
+}
+
+#[test]
+fn clock_going_backwards_earns_nothing() {
+    let mut bank = stamped(-(HOUR as i64));
+    assert_eq!(bank.catch_up(NOW), 0);
+    assert_eq!(bank.scrap, 0);
+    assert_eq!(bank.last_seen, NOW);
+}
+
+#[test]
+fn offline_time_is_capped() {
+    let cap = OFFLINE_CAP_SECONDS as i64;
+    let short = stamped(cap - HOUR as i64).catch_up(NOW);
+    let capped = stamped(cap).catch_up(NOW);
+    assert!(short < capped);
+    assert_eq!(stamped(cap + HOUR as i64).catch_up(NOW), capped);
+    assert_eq!(stamped(30 * 24 * HOUR as i64).catch_up(NOW), capped);
+}
+
+#[test]
+fn bank_is_capped() {
+    let mut bank = stamped(24 * HOUR as i64);
+    bank.scrap = IDLE_BANK_MAX - 1;
+    assert_eq!(bank.catch_up(NOW), 1);
+    assert_eq!(bank.scrap, IDLE_BANK_MAX);
+}
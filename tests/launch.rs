@@ -0,0 +1,85 @@
+//! The command line options, without starting the game.
+
+use proptest::prelude::*;
+
+use planet_td::launch::LaunchOptions;
+
+fn parse(args: &[&str]) -> Result<LaunchOptions, String> {
+    LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+fn error(args: &[&str]) -> String {
+    parse(args).err().expect("the options should be rejected")
+}
+
+#[test]
+fn no_options_are_the_defaults() {
+    let options = parse(&[]).unwrap();
+    assert_eq!(options.seed, None);
+    assert_eq!(options.wave, None);
+    assert!(options.challenge.is_none());
+    assert!(!options.fullscreen && !options.mute && !options.bench);
+}
+
+#[test]
+fn reads_the_flags() {
+    let options = parse(&["--fullscreen", "--mute", "--bench", "--wave", "3"]).unwrap();
+    assert!(options.fullscreen && options.mute && options.bench);
+    assert_eq!(options.wave, Some(2));
+    // the last of the window flags wins
+    assert!(!parse(&["--fullscreen", "--windowed"]).unwrap().fullscreen);
+    // waves count from 1, a zero is the first one
+    assert_eq!(parse(&["--wave", "0"]).unwrap().wave, Some(0));
+}
+
+#[test]
+fn reads_a_challenge_file() {
+    let options = parse(&["--challenge", "assets/challenges/simple.json"]).unwrap();
+    assert!(options.challenge.is_some());
+    assert!(error(&["--challenge", "assets/challenges/missing.json"]).contains("missing.json"));
+}
+
+#[test]
+fn rejects_unknown_options() {
+    assert_eq!(error(&["--sed", "4"]), "unknown option --sed");
+    assert_eq!(error(&["42"]), "unknown option 42");
+    assert!(error(&["--mute", "-m"]).contains("-m"));
+    assert!(error(&["--server"]).starts_with("--server"));
+}
+
+#[test]
+fn rejects_missing_values() {
+    for flag in ["--seed", "--wave", "--challenge", "--replay", "--export"] {
+        assert_eq!(error(&[flag]), format!("{} needs a value", flag));
+    }
+    assert_eq!(error(&["--mute", "--seed"]), "--seed needs a value");
+}
+
+#[test]
+fn rejects_bad_numbers() {
+    for seed in ["abc", "-1", "1.5", "", "18446744073709551616"] {
+        assert_eq!(error(&["--seed", seed]), "--seed <n>");
+    }
+    assert_eq!(error(&["--wave", "first"]), "--wave <n>");
+}
+
+#[test]
+fn export_needs_a_replay() {
+    let dir = std::env::temp_dir().join("planet-td-frames");
+    let dir = dir.to_str().unwrap();
+    assert_eq!(error(&["--export", dir]), "--export needs a --replay");
+}
+
+proptest! {
+    #[test]
+    fn any_seed_is_kept(seed in any::<u64>()) {
+        let options = parse(&["--seed", &seed.to_string()]).unwrap();
+        prop_assert_eq!(options.seed, Some(seed));
+    }
+
+    #[test]
+    fn unknown_words_are_rejected(word in "[a-z]{1,12}") {
+        let flag = format!("--x{}", word);
+        prop_assert_eq!(error(&[&flag]), format!("unknown option {}", flag));
+    }
+}
@@ -0,0 +1,44 @@
+//! Replays recorded input traces in the headless game, checking the run
+//! stays sane frame by frame. Traces are recorded in game with F9 and
+//! end up in saves/trace.json.
+
+use planet_td::headless::{headless_app, snapshot, start_run};
+use planet_td::input_trace::{InputReplay, InputTrace};
+
+const ENTITY_LIMIT: u32 = 10_000;
+
+fn replay(trace: &str, level: usize, until_wave: usize) {
+    let trace: InputTrace = serde_json::from_str(trace).expect("invalid trace");
+    let frames = trace.frames();
+    let mut app = headless_app();
+    app.insert_resource(InputReplay::new(trace));
+    start_run(&mut app, level);
+
+    for frame in 0..frames {
+        app.update();
+        let run = snapshot(app.world_mut()).expect("the run ended early");
+        assert!(
+            run.enemies <= run.max_enemies,
+            "{} enemies alive on frame {}",
+            run.enemies,
+            frame
+        );
+        assert!(
+            run.entities < ENTITY_LIMIT,
+            "{} entities on frame {}",
+            run.entities,
+            frame
+        );
+        assert!(!run.over, "the run was over on frame {}", frame);
+        if run.wave >= until_wave {
+            assert!(run.planet_hp > 0.0);
+            return;
+        }
+    }
+    panic!("the trace ended before clearing wave {}", until_wave);
+}
+
+#[test]
+fn sweep_fire_survives_wave_three() {
+    replay(include_str!("traces/sweep_fire.json"), 0, 3);
+}
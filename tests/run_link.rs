@@ -0,0 +1,68 @@
+//! The shared run links, written and read back.
+
+use planet_td::run_link::RunLink;
+
+fn link(level: &str) -> RunLink {
+    RunLink {
+        level: level.to_string(),
+        seed: Some(42),
+        ng_plus: 2,
+        speedrun: true,
+        endless: false,
+        players: 3,
+        friendly_fire: true,
+        shared_scrap: false,
+    }
+}
+
+#[test]
+fn links_round_trip() {
+    for level in ["belt", "gas giant", "a&b=c", "50%+?#", "ring/β"] {
+        let written = link(level);
+        let read = RunLink::parse(&written.query()).expect("the link names a level");
+        assert_eq!(read.level, level);
+        assert_eq!(read.seed, Some(42));
+        assert_eq!(read.ng_plus, 2);
+        assert!(read.speedrun);
+        assert!(!read.endless);
+        assert_eq!(read.players, 3);
+        assert!(read.friendly_fire);
+        assert!(!read.shared_scrap);
+    }
+}
+
+#[test]
+fn reads_encoded_queries() {
+    let read = RunLink::parse("?level=gas%20giant&seed=7").unwrap();
+    assert_eq!(read.level, "gas giant");
+    assert_eq!(read.seed, Some(7));
+    assert_eq!(
+        RunLink::parse("?level=gas+giant").unwrap().level,
+        "gas giant"
+    );
+    assert_eq!(RunLink::parse("?level=%E2%98%84").unwrap().level, "☄");
+}
+
+#[test]
+fn malformed_queries() {
+    assert!(RunLink::parse("").is_none());
+    assert!(RunLink::parse("?seed=42").is_none());
+    // a bad escape in the level leaves no level
+    for query in [
+        "?level=%",
+        "?level=%4",
+        "?level=%zz",
+        "?level=%+f",
+        "?level=%E2%98",
+    ] {
+        assert!(RunLink::parse(query).is_none(), "{}", query);
+    }
+    // elsewhere it only drops the pair
+    let read = RunLink::parse("?level=belt&seed=%4&players=%x9&ng=nine").unwrap();
+    assert_eq!(read.level, "belt");
+    assert_eq!(read.seed, None);
+    assert_eq!(read.players, 1);
+    assert_eq!(read.ng_plus, 0);
+    // out of range values are clamped
+    assert_eq!(RunLink::parse("?level=belt&players=99").unwrap().players, 4);
+}
@@ -0,0 +1,226 @@
+//! The spawner state machine against randomly generated challenges.
+
+use std::time::Duration;
+
+use proptest::prelude::*;
+
+use planet_td::spawner::{
+    Challenge, Reinforcements, SpawnAt, SpawnBand, SpawnWeight, Spawner, SpawnerStep, Wave,
+};
+
+/// Ticks after which a run that hasn't finished counts as stuck.
+const TICK_LIMIT: usize = 200_000;
+
+fn challenge() -> impl Strategy<Value = Challenge> {
+    let angle = prop::option::of(0.0f32..std::f32::consts::TAU);
+    let spawn = (0u32..4, 0.0f32..3000.0, angle).prop_map(|(enemy_id, cooldown, angle)| SpawnAt {
+        enemy_id,
+        cooldown,
+        angle,
+    });
+    let wave = prop::collection::vec(spawn, 0..12).prop_map(|spawns| Wave {
+        spawns,
+        reinforcements: None,
+    });
+    prop::collection::vec(wave, 0..8).prop_map(|waves| Challenge {
+        waves,
+        ..Default::default()
+    })
+}
+
+/// Everything the spawner asked for, ticking with the given frame times
+/// and field states in a loop until the challenge is over.
+fn run(
+    challenge: &Challenge,
+    spawner: &mut Spawner,
+    frames: &[(u64, bool)],
+    intermission: Duration,
+) -> Vec<SpawnerStep> {
+    let mut steps = vec![];
+    for tick in 0..TICK_LIMIT {
+        if spawner.finished(challenge) {
+            return steps;
+        }
+        let (ms, field_clear) = frames[tick % frames.len()];
+        let step = spawner.tick(
+            challenge,
+            Duration::from_millis(ms),
+            field_clear,
+            false,
+            intermission,
+        );
+        if let Some(SpawnerStep::WaveEnded(_)) = step {
+            assert!(field_clear, "a wave ended with enemies on the field");
+        }
+        steps.extend(step);
+    }
+    panic!("the spawner didn't finish in {} ticks", TICK_LIMIT);
+}
+
+/// The steps a challenge should produce from a given wave on.
+fn expected(challenge: &Challenge, first_wave: usize) -> Vec<SpawnerStep> {
+    let mut steps = vec![];
+    for (index, wave) in challenge.waves.iter().enumerate().skip(first_wave) {
+        steps.extend(
+            wave.spawns
+                .iter()
+                .map(|s| SpawnerStep::Spawn(s.enemy_id, s.angle)),
+        );
+        steps.push(SpawnerStep::WaveEnded(index));
+    }
+    steps
+}
+
+fn frames() -> impl Strategy<Value = Vec<(u64, bool)>> {
+    // the field is clear at least once per loop, or waves never end
+    prop::collection::vec((1u64..200, any::<bool>()), 1..16).prop_map(|mut frames| {
+        frames.push((16, true));
+        frames
+    })
+}
+
+proptest! {
+    #[test]
+    fn spawns_every_enemy_in_order(
+        challenge in challenge(),
+        frames in frames(),
+        intermission in 0u64..3000,
+    ) {
+        let mut spawner = Spawner::new(100.0, 0, Duration::from_millis(2000));
+        let steps = run(&challenge, &mut spawner, &frames, Duration::from_millis(intermission));
+        prop_assert_eq!(steps, expected(&challenge, 0));
+    }
+
+    #[test]
+    fn starts_from_any_wave(
+        challenge in challenge(),
+        first_wave in 0usize..10,
+        frames in frames(),
+    ) {
+        let first_wave = first_wave.min(challenge.waves.len());
+        let mut spawner = Spawner::new(100.0, first_wave, Duration::ZERO);
+        let steps = run(&challenge, &mut spawner, &frames, Duration::ZERO);
+        prop_assert_eq!(steps, expected(&challenge, first_wave));
+    }
+
+    #[test]
+    fn hold_keeps_the_intermission(
+        challenge in challenge(),
+        held in 1usize..100,
+    ) {
+        let mut spawner = Spawner::new(100.0, 0, Duration::ZERO);
+        let frame = Duration::from_millis(16);
+        let intermission = Duration::from_millis(100);
+        for _ in 0..TICK_LIMIT {
+            if spawner.in_intermission() || spawner.finished(&challenge) {
+                break;
+            }
+            spawner.tick(&challenge, frame, true, false, intermission);
+        }
+        let wave = spawner.current_wave;
+        for _ in 0..held {
+            prop_assert_eq!(spawner.tick(&challenge, frame, true, true, intermission), None);
+        }
+        prop_assert_eq!(spawner.current_wave, wave);
+    }
+}
+
+#[test]
+fn expands_authoring_blocks() {
+    let challenge = Challenge::from_json(
+        r#"{ "waves": [{ "spawns": [
+            { "enemy_id": 1, "cooldown": 500 },
+            { "repeat": 3, "stagger": 100, "ramp": 0.5, "spawns": [
+                { "mirror": [{ "enemy_id": 2, "cooldown": 1000, "angle": 0.0 }] }
+            ] }
+        ] }] }"#,
+    )
+    .unwrap();
+    let spawns: Vec<_> = challenge.waves[0]
+        .spawns
+        .iter()
+        .map(|s| (s.enemy_id, s.cooldown, s.angle))
+        .collect();
+    let pi = std::f32::consts::PI;
+    assert_eq!(
+        spawns,
+        vec![
+            (1, 500.0, None),
+            (2, 0.0, Some(0.0)),
+            (2, 1100.0, Some(pi)),
+            (2, 0.0, Some(0.0)),
+            (2, 600.0, Some(pi)),
+            (2, 0.0, Some(0.0)),
+            (2, 350.0, Some(pi)),
+        ]
+    );
+}
+
+#[test]
+fn reinforcements_replace_the_rest_of_the_wave() {
+    let spawn = |enemy_id| SpawnAt {
+        enemy_id,
+        cooldown: 100.0,
+        angle: None,
+    };
+    let challenge = Challenge {
+        waves: vec![Wave {
+            spawns: vec![spawn(0); 10],
+            reinforcements: Some(Reinforcements {
+                retreat_at: 0.5,
+                spawns: vec![spawn(1), spawn(1)],
+            }),
+        }],
+        ..Default::default()
+    };
+    let mut spawner = Spawner::new(100.0, 0, Duration::ZERO);
+    let frame = Duration::from_millis(100);
+    let intermission = Duration::ZERO;
+    let mut steps = vec![];
+    for _ in 0..6 {
+        steps.extend(spawner.tick(&challenge, frame, false, false, intermission));
+    }
+    assert!(!spawner.retreat(&challenge, 4));
+    assert!(spawner.retreat(&challenge, 5));
+    assert!(!spawner.retreat(&challenge, 6));
+    for _ in 0..10 {
+        steps.extend(spawner.tick(&challenge, frame, true, false, intermission));
+    }
+    let mut expected = vec![SpawnerStep::Spawn(0, None); 6];
+    expected.extend([
+        SpawnerStep::Spawn(1, None),
+        SpawnerStep::Spawn(1, None),
+        SpawnerStep::WaveEnded(0),
+    ]);
+    assert_eq!(steps, expected);
+}
+
+#[test]
+fn challenge_files_parse() {
+    let challenge = Challenge::from_json(include_str!("../assets/challenges/simple.json")).unwrap();
+    assert_eq!(challenge.waves.len(), 3);
+}
+
+proptest! {
+    #[test]
+    fn generated_waves_follow_the_bands(seed in any::<u64>()) {
+        let bands = vec![
+            SpawnBand {
+                from_wave: 0,
+                weights: vec![SpawnWeight { enemy_id: 0, weight: 1.0 }],
+            },
+            SpawnBand {
+                from_wave: 10,
+                weights: vec![
+                    SpawnWeight { enemy_id: 1, weight: 1.0 },
+                    SpawnWeight { enemy_id: 2, weight: 0.0 },
+                ],
+            },
+        ];
+        let challenge = Challenge::new(seed, &bands);
+        for (index, wave) in challenge.waves.iter().enumerate() {
+            let expected = if index < 10 { 0 } else { 1 };
+            prop_assert!(wave.spawns.iter().all(|spawn| spawn.enemy_id == expected));
+        }
+    }
+}